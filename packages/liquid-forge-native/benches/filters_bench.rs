@@ -18,8 +18,9 @@
 //!
 //! Run with: cargo bench
 
-use criterion::{criterion_group, criterion_main, Criterion};
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
 use liquid_forge_native::*;
+use napi::bindgen_prelude::{Buffer, Either};
 use std::hint::black_box;
 
 fn bench_handleize(c: &mut Criterion) {
@@ -61,6 +62,33 @@ fn bench_escape(c: &mut Criterion) {
     });
 }
 
+/// Builds a multi-kilobyte product description with markup and line breaks.
+fn large_description(paragraphs: usize) -> String {
+    let paragraph = "<p>Camiseta 100% algodón \"premium\" con estampado & acabado \
+                     suave. Ideal para el día a día, no pierde color tras varios lavados.</p>\r\n\
+                     Talla recomendada: la habitual.\n\n";
+    paragraph.repeat(paragraphs)
+}
+
+fn bench_large_payloads(c: &mut Criterion) {
+    for size in [16, 256] {
+        let text = large_description(size);
+        let kb = text.len() / 1024;
+
+        c.bench_function(&format!("escape_{}kb", kb), |b| {
+            b.iter(|| escape(black_box(Some(text.clone()))))
+        });
+
+        c.bench_function(&format!("strip_newlines_{}kb", kb), |b| {
+            b.iter(|| strip_newlines(black_box(Some(text.clone()))))
+        });
+
+        c.bench_function(&format!("newline_to_br_{}kb", kb), |b| {
+            b.iter(|| newline_to_br(black_box(Some(text.clone()))))
+        });
+    }
+}
+
 fn bench_truncate(c: &mut Criterion) {
     c.bench_function("truncate_short", |b| {
         b.iter(|| {
@@ -121,12 +149,84 @@ fn bench_strip_html(c: &mut Criterion) {
     });
 }
 
+/// Product titles as a collection page would filter them.
+fn product_titles(count: usize) -> Vec<String> {
+    (0..count)
+        .map(|i| format!("Camiseta Básica Ñandú & Co. \"Edición {}\"", i))
+        .collect()
+}
+
+fn bench_batches(c: &mut Criterion) {
+    for count in [50, 500] {
+        let titles = product_titles(count);
+        let buffer = titles.join("\0").into_bytes();
+        let inputs = || titles.iter().cloned().map(Some).collect::<Vec<_>>();
+
+        // Throughput in elements reports the amortized cost per item. Inputs
+        // are built outside the timed routine, so only the native work is
+        // measured; the JavaScript-to-native call cost that batching saves
+        // is measured by examples/batch-benchmark.js.
+        let mut group = c.benchmark_group(format!("batch_{}", count));
+        group.throughput(Throughput::Elements(count as u64));
+
+        group.bench_function("handleize_each", |b| {
+            b.iter_batched(
+                inputs,
+                |inputs| {
+                    inputs
+                        .into_iter()
+                        .map(|title| handleize(black_box(title)))
+                        .collect::<Vec<_>>()
+                },
+                BatchSize::SmallInput,
+            )
+        });
+        group.bench_function("handleize_many_array", |b| {
+            b.iter_batched(
+                || Either::A(inputs()),
+                |inputs| handleize_many(black_box(inputs)),
+                BatchSize::SmallInput,
+            )
+        });
+        group.bench_function("handleize_many_buffer", |b| {
+            b.iter_batched(
+                || Either::B(Buffer::from(buffer.clone())),
+                |inputs| handleize_many(black_box(inputs)),
+                BatchSize::SmallInput,
+            )
+        });
+        group.bench_function("escape_many_buffer", |b| {
+            b.iter_batched(
+                || Either::B(Buffer::from(buffer.clone())),
+                |inputs| escape_many(black_box(inputs)),
+                BatchSize::SmallInput,
+            )
+        });
+        group.bench_function("map_filter_truncate", |b| {
+            b.iter_batched(
+                || Either::A(titles.iter().map(|t| Some(t.as_str().into())).collect()),
+                |inputs| {
+                    map_filter(
+                        "truncate".to_string(),
+                        black_box(inputs),
+                        Some(vec![20.into()]),
+                    )
+                },
+                BatchSize::SmallInput,
+            )
+        });
+        group.finish();
+    }
+}
+
 criterion_group!(
     benches,
     bench_handleize,
     bench_escape,
+    bench_large_payloads,
     bench_truncate,
     bench_append,
-    bench_strip_html
+    bench_strip_html,
+    bench_batches
 );
 criterion_main!(benches);
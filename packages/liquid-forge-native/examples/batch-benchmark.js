@@ -0,0 +1,68 @@
+/**
+ * Benchmark de llamadas por lote vs una llamada nativa por elemento
+ *
+ * Cada llamada de JavaScript a Rust tiene un costo fijo (cruzar NAPI y
+ * convertir argumentos). Las funciones por lote lo pagan una sola vez por
+ * lista, y este benchmark mide ese ahorro desde JavaScript, algo que
+ * `cargo bench` no puede ver.
+ *
+ * Uso:
+ *   node examples/batch-benchmark.js
+ */
+
+const { performance } = require('perf_hooks');
+
+let nativeFilters = null;
+try {
+  nativeFilters = require('../index.js');
+  console.log('Native filters loaded successfully\n');
+} catch (error) {
+  console.log('Native filters not available');
+  console.log('  Run: pnpm build\n');
+  process.exit(1);
+}
+
+const { handleize, escape, truncate, handleizeMany, escapeMany, mapFilter } = nativeFilters;
+
+const words = ['Camisa', 'Azul', 'Ñoño', '&', 'Friends', 'Café', 'con', 'Leche', 'Edición', 'Especial'];
+
+/** Títulos de producto de 3 a 7 palabras */
+function productTitles(count) {
+  return Array.from({ length: count }, (_, i) =>
+    Array.from({ length: 3 + (i % 5) }, (_, j) => words[(i + j * 3) % words.length]).join(' ')
+  );
+}
+
+/** Tiempo por elemento en microsegundos */
+function measure(fn, count, iterations) {
+  for (let i = 0; i < 100; i++) fn();
+  const start = performance.now();
+  for (let i = 0; i < iterations; i++) fn();
+  return ((performance.now() - start) * 1000) / (iterations * count);
+}
+
+console.log('Benchmark: una llamada por elemento vs una llamada por lote');
+console.log('═'.repeat(60));
+
+for (const count of [50, 500]) {
+  const titles = productTitles(count);
+  const buffer = Buffer.from(titles.join('\0'));
+  const iterations = 200000 / count;
+
+  const cases = [
+    ['handleize (uno por uno)', () => titles.map((title) => handleize(title))],
+    ['handleizeMany (array)', () => handleizeMany(titles)],
+    ['handleizeMany (Buffer)', () => handleizeMany(buffer)],
+    ['escape (uno por uno)', () => titles.map((title) => escape(title))],
+    ['escapeMany (Buffer)', () => escapeMany(buffer)],
+    ['truncate (uno por uno)', () => titles.map((title) => truncate(title, 20))],
+    ['mapFilter truncate', () => mapFilter('truncate', titles, [20])],
+  ];
+
+  console.log(`\n${count} elementos`);
+  for (const [name, fn] of cases) {
+    console.log(`   ${name.padEnd(26)} ${measure(fn, count, iterations).toFixed(3)} µs/elemento`);
+  }
+}
+
+console.log();
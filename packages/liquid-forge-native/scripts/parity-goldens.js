@@ -0,0 +1,68 @@
+/**
+ * Genera las salidas esperadas de fixtures/parity con LiquidJS
+ *
+ * El test `test_render_matches_liquidjs` compara el renderizador nativo con
+ * estos archivos. Ejecutar de nuevo al agregar casos a cases.json.
+ *
+ * Uso:
+ *   node scripts/parity-goldens.js
+ */
+
+const fs = require('fs');
+const path = require('path');
+const { createRequire } = require('module');
+
+// LiquidJS es una dependencia de liquid-forge
+const { Liquid } = createRequire(path.join(__dirname, '../../liquid-forge/package.json'))('liquidjs');
+
+const parityDir = path.join(__dirname, '../fixtures/parity');
+const themesDir = path.join(__dirname, '../../example-themes');
+
+const context = JSON.parse(fs.readFileSync(path.join(parityDir, 'context.json'), 'utf8'));
+const cases = JSON.parse(fs.readFileSync(path.join(parityDir, 'cases.json'), 'utf8'));
+
+/** Carpeta del tema que contiene `file`: la primera con snippets/ */
+function themeOf(file) {
+  let dir = path.dirname(file);
+  while (!fs.existsSync(path.join(dir, 'snippets'))) {
+    dir = path.dirname(dir);
+  }
+  return dir;
+}
+
+/** Motor con los mismos filtros y tags que el host del test de Rust */
+function engine(theme) {
+  const liquid = new Liquid({
+    root: theme,
+    partials: path.join(theme, 'snippets'),
+    extname: '.liquid',
+  });
+  liquid.registerFilter('t', (key) => key);
+  liquid.registerTag('schema', {
+    parse(token, remainTokens) {
+      let next;
+      do {
+        next = remainTokens.shift();
+      } while (next && !(next.name === 'endschema'));
+    },
+    render() {
+      return '';
+    },
+  });
+  return liquid;
+}
+
+fs.mkdirSync(path.join(parityDir, 'expected'), { recursive: true });
+
+for (const testCase of cases) {
+  let source = testCase.template;
+  let theme = themesDir;
+  if (testCase.file) {
+    const file = path.join(themesDir, testCase.file);
+    source = fs.readFileSync(file, 'utf8');
+    theme = themeOf(file);
+  }
+  const output = engine(theme).parseAndRenderSync(source, context);
+  fs.writeFileSync(path.join(parityDir, 'expected', `${testCase.name}.html`), output);
+  console.log(`${testCase.name}: ${output.length} bytes`);
+}
@@ -0,0 +1,299 @@
+/*
+ * Copyright 2025 Fasttify LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Collection of the `{% style %}` and `{% javascript %}` output of the
+//! sections of a page.
+//!
+//! A section rendered several times emits the same fragment each time; it
+//! is kept once, in the order first seen. Hashes are XXH3, which is stable
+//! across builds and platforms, so they can be used for caching.
+
+use xxhash_rust::xxh3::xxh3_64;
+
+use crate::minify::minify_css;
+use crate::security::js::{lex_js, JsKind};
+
+/// A fragment and the sections that emitted it.
+struct Fragment {
+    hash: u64,
+    content: String,
+    sections: Vec<String>,
+}
+
+/// Fragments of one kind, in the order first seen.
+#[derive(Default)]
+struct Fragments {
+    items: Vec<Fragment>,
+}
+
+impl Fragments {
+    fn add(&mut self, content: String, section_id: String) -> bool {
+        let hash = xxh3_64(content.as_bytes());
+        match self
+            .items
+            .iter_mut()
+            .find(|item| item.hash == hash && item.content == content)
+        {
+            Some(item) => {
+                if !item.sections.contains(&section_id) {
+                    item.sections.push(section_id);
+                }
+                false
+            }
+            None => {
+                self.items.push(Fragment {
+                    hash,
+                    content,
+                    sections: vec![section_id],
+                });
+                true
+            }
+        }
+    }
+
+    /// Forgets `section_id`, dropping the fragments only it emitted.
+    fn remove_section(&mut self, section_id: &str) {
+        for item in &mut self.items {
+            item.sections.retain(|section| section != section_id);
+        }
+        self.items.retain(|item| !item.sections.is_empty());
+    }
+
+    fn combined(&self, minify: impl Fn(&str) -> String) -> String {
+        let parts: Vec<String> = self
+            .items
+            .iter()
+            .map(|item| minify(&item.content))
+            .collect();
+        parts.join("\n")
+    }
+
+    /// Hash of the fragment hashes in order: changes whenever the combined
+    /// output does.
+    fn hash(&self) -> String {
+        let hashes: Vec<u8> = self
+            .items
+            .iter()
+            .flat_map(|item| item.hash.to_le_bytes())
+            .collect();
+        format!("{:016x}", xxh3_64(&hashes))
+    }
+}
+
+/// Collapses JavaScript whitespace and drops comments other than
+/// `/*! ... */`. Line breaks are kept, as automatic semicolon insertion
+/// depends on them.
+fn collapse_js(source: &str) -> String {
+    let mut out = String::with_capacity(source.len());
+    let mut last_end = 0;
+    let mut gap_has_newline = false;
+    let mut gap = false;
+    for token in lex_js(source) {
+        let text = &source[token.start..token.end];
+        let before = &source[last_end..token.start];
+        gap |= !before.is_empty();
+        gap_has_newline |= before.contains('\n');
+        last_end = token.end;
+        if token.kind == JsKind::Comment && !text.starts_with("/*!") {
+            gap = true;
+            gap_has_newline |= text.contains('\n');
+            continue;
+        }
+        if !out.is_empty() && gap {
+            out.push(if gap_has_newline { '\n' } else { ' ' });
+        }
+        out.push_str(text);
+        gap = false;
+        gap_has_newline = false;
+    }
+    out
+}
+
+/// Collects the CSS and JavaScript fragments of a page's sections.
+///
+/// # Examples
+///
+/// ```javascript
+/// const assets = new AssetCollector();
+/// assets.addCss(".card { color: red }", "product-grid");
+/// assets.addCss(".card { color: red }", "related-products"); // false: already collected
+/// `<link rel="stylesheet" href="/sections.css?v=${assets.cssHash}">`
+/// ```
+#[napi]
+#[derive(Default)]
+pub struct AssetCollector {
+    css: Fragments,
+    js: Fragments,
+}
+
+#[napi]
+impl AssetCollector {
+    #[napi(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the CSS a section emitted. Returns whether the content is new.
+    #[napi]
+    pub fn add_css(&mut self, content: String, section_id: String) -> bool {
+        self.css.add(content, section_id)
+    }
+
+    /// Adds the JavaScript a section emitted. Returns whether the content
+    /// is new.
+    #[napi]
+    pub fn add_js(&mut self, content: String, section_id: String) -> bool {
+        self.js.add(content, section_id)
+    }
+
+    /// Forgets a section, as before rendering it again. Fragments other
+    /// sections emitted too are kept.
+    #[napi]
+    pub fn remove_section(&mut self, section_id: String) {
+        self.css.remove_section(&section_id);
+        self.js.remove_section(&section_id);
+    }
+
+    /// The CSS fragments joined by line breaks, each minified if `minify`
+    /// is true.
+    #[napi]
+    pub fn combined_css(&self, minify: Option<bool>) -> String {
+        if minify.unwrap_or(false) {
+            self.css.combined(|css| minify_css(css.to_string()).content)
+        } else {
+            self.css.combined(str::to_string)
+        }
+    }
+
+    /// The JavaScript fragments joined by line breaks, each minified if
+    /// `minify` is true.
+    #[napi]
+    pub fn combined_js(&self, minify: Option<bool>) -> String {
+        if minify.unwrap_or(false) {
+            self.js.combined(collapse_js)
+        } else {
+            self.js.combined(str::to_string)
+        }
+    }
+
+    /// 16 hex digits identifying the combined CSS, for a `?v=` query or
+    /// an ETag.
+    #[napi(getter)]
+    pub fn css_hash(&self) -> String {
+        self.css.hash()
+    }
+
+    /// 16 hex digits identifying the combined JavaScript.
+    #[napi(getter)]
+    pub fn js_hash(&self) -> String {
+        self.js.hash()
+    }
+
+    /// Number of distinct CSS fragments.
+    #[napi(getter)]
+    pub fn css_count(&self) -> u32 {
+        self.css.items.len() as u32
+    }
+
+    /// Number of distinct JavaScript fragments.
+    #[napi(getter)]
+    pub fn js_count(&self) -> u32 {
+        self.js.items.len() as u32
+    }
+
+    /// Removes every fragment, for a new render.
+    #[napi]
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn add_css(collector: &mut AssetCollector, content: &str, section: &str) -> bool {
+        collector.add_css(content.to_string(), section.to_string())
+    }
+
+    #[test]
+    fn test_dedup_in_first_seen_order() {
+        let mut collector = AssetCollector::new();
+        assert!(add_css(&mut collector, ".b {}", "s1"));
+        assert!(add_css(&mut collector, ".a {}", "s2"));
+        assert!(!add_css(&mut collector, ".b {}", "s3"));
+        assert!(!add_css(&mut collector, ".a {}", "s2"));
+        assert!(collector.add_js("init()".to_string(), "s1".to_string()));
+        assert_eq!(collector.combined_css(None), ".b {}\n.a {}");
+        assert_eq!(collector.combined_js(None), "init()");
+        assert_eq!((collector.css_count(), collector.js_count()), (2, 1));
+
+        collector.remove_section("s1".to_string());
+        assert_eq!(collector.combined_css(None), ".b {}\n.a {}");
+        assert_eq!(collector.combined_js(None), "");
+        collector.remove_section("s3".to_string());
+        assert_eq!(collector.combined_css(None), ".a {}");
+
+        collector.clear();
+        assert_eq!(collector.css_count(), 0);
+    }
+
+    #[test]
+    fn test_hash() {
+        let mut first = AssetCollector::new();
+        let mut second = AssetCollector::new();
+        assert_eq!(first.css_hash(), second.css_hash());
+        add_css(&mut first, ".a {}", "s1");
+        add_css(&mut first, ".b {}", "s2");
+        add_css(&mut second, ".a {}", "x");
+        add_css(&mut second, ".a {}", "y");
+        add_css(&mut second, ".b {}", "z");
+        let hash = first.css_hash();
+        assert_eq!(hash.len(), 16);
+        assert!(hash.bytes().all(|b| b.is_ascii_hexdigit()));
+        assert_eq!(hash, second.css_hash());
+        // Fixed across builds and platforms
+        assert_eq!(hash, "bd6c1282f49aaf93");
+
+        let mut reordered = AssetCollector::new();
+        add_css(&mut reordered, ".b {}", "s2");
+        add_css(&mut reordered, ".a {}", "s1");
+        assert_ne!(hash, reordered.css_hash());
+        assert_ne!(hash, first.js_hash());
+    }
+
+    #[test]
+    fn test_minified_output() {
+        let mut collector = AssetCollector::new();
+        add_css(
+            &mut collector,
+            ".a {\n  color: red; /* note */\n}\n/*! keep */",
+            "s1",
+        );
+        add_css(&mut collector, ".b  { content: '  x  ' }", "s2");
+        assert_eq!(
+            collector.combined_css(Some(true)),
+            ".a{color:red}/*! keep */\n.b{content:'  x  '}"
+        );
+
+        let js = "let a = 1 // one\nlet b = `  ${ a }  `;\n\n/* two\n */ const re = /  x  /g;  /*! keep */ f(a,  b)";
+        collector.add_js(js.to_string(), "s1".to_string());
+        assert_eq!(
+            collector.combined_js(Some(true)),
+            "let a = 1\nlet b = `  ${ a }  `;\nconst re = /  x  /g; /*! keep */ f(a, b)"
+        );
+    }
+}
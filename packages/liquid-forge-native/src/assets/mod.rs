@@ -0,0 +1,544 @@
+/*
+ * Copyright 2025 Fasttify LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Theme asset validation.
+//!
+//! Content types come from magic bytes rather than extensions, so a `.png`
+//! holding an SVG is caught, and stylesheets and scripts are budgeted by
+//! their compressed size, which is what visitors download.
+
+mod collector;
+mod sniff;
+
+use std::io::Write;
+
+use napi::bindgen_prelude::Buffer;
+use rayon::prelude::*;
+
+pub use collector::*;
+
+use crate::liquid::html::start_tags;
+use sniff::{dimensions, sniff, Format};
+
+const DEFAULT_MAX_ASSET_SIZE: u32 = 10 * 1024 * 1024;
+const DEFAULT_MAX_IMAGE_SIZE: u32 = 500 * 1024;
+const DEFAULT_MAX_IMAGE_DIMENSION: u32 = 4096;
+const DEFAULT_MAX_CSS_SIZE: u32 = 50 * 1024;
+const DEFAULT_MAX_JS_SIZE: u32 = 100 * 1024;
+
+/// Brotli quality and window for compressed sizes. Quality 9 comes within
+/// a few percent of the maximum, 11, at a tenth of the time.
+const BROTLI_QUALITY: u32 = 9;
+const BROTLI_WINDOW: u32 = 22;
+
+/// A theme asset.
+#[napi(object)]
+pub struct AssetFile {
+    /// Path inside the theme, such as `assets/logo.png`.
+    pub path: String,
+    pub content: Buffer,
+}
+
+/// Size limits, in bytes unless noted.
+#[napi(object)]
+#[derive(Debug, Clone, Default)]
+pub struct AssetBudget {
+    /// Any file; larger ones are errors. Defaults to 10 MB.
+    pub max_asset_size: Option<u32>,
+    /// Raster images. Defaults to 500 KB.
+    pub max_image_size: Option<u32>,
+    /// Longest side of a raster image, in pixels. Defaults to 4096.
+    pub max_image_dimension: Option<u32>,
+    /// Gzipped stylesheets. Defaults to 50 KB.
+    pub max_css_size: Option<u32>,
+    /// Gzipped scripts. Defaults to 100 KB.
+    pub max_js_size: Option<u32>,
+}
+
+/// A problem with an asset.
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct AssetIssue {
+    /// "type-mismatch", "unsafe-svg", "file-too-large", "large-image",
+    /// "image-dimensions" or "over-budget".
+    pub code: String,
+    /// "error" or "warning".
+    pub severity: String,
+    pub message: String,
+}
+
+/// What was found about an asset.
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct AssetReport {
+    pub path: String,
+    /// The format of the content: "jpeg", "png", "gif", "webp", "avif",
+    /// "svg", "ico", "woff", "woff2", "ttf", "otf", "eot", "text" or
+    /// "binary".
+    pub content_type: String,
+    pub size: u32,
+    /// Pixel dimensions of raster images.
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    /// Compressed sizes of stylesheets and scripts.
+    pub gzip_size: Option<u32>,
+    pub brotli_size: Option<u32>,
+    pub issues: Vec<AssetIssue>,
+}
+
+/// Reports of every asset, in the order given.
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct AssetValidationResult {
+    pub assets: Vec<AssetReport>,
+    pub error_count: u32,
+    pub warning_count: u32,
+}
+
+/// An `AssetBudget` with its defaults applied.
+struct Limits {
+    asset: u32,
+    image: u32,
+    dimension: u32,
+    css: u32,
+    js: u32,
+}
+
+impl From<AssetBudget> for Limits {
+    fn from(budget: AssetBudget) -> Self {
+        Limits {
+            asset: budget.max_asset_size.unwrap_or(DEFAULT_MAX_ASSET_SIZE),
+            image: budget.max_image_size.unwrap_or(DEFAULT_MAX_IMAGE_SIZE),
+            dimension: budget
+                .max_image_dimension
+                .unwrap_or(DEFAULT_MAX_IMAGE_DIMENSION),
+            css: budget.max_css_size.unwrap_or(DEFAULT_MAX_CSS_SIZE),
+            js: budget.max_js_size.unwrap_or(DEFAULT_MAX_JS_SIZE),
+        }
+    }
+}
+
+fn issue(code: &str, severity: &str, message: String) -> AssetIssue {
+    AssetIssue {
+        code: code.to_string(),
+        severity: severity.to_string(),
+        message,
+    }
+}
+
+/// A size for messages: bytes, KB or MB.
+fn format_size(size: u32) -> String {
+    match size {
+        0..1024 => format!("{} B", size),
+        1024..1_048_576 => format!("{:.1} KB", size as f64 / 1024.0),
+        _ => format!("{:.2} MB", size as f64 / 1_048_576.0),
+    }
+}
+
+pub(crate) fn gzip_size(bytes: &[u8]) -> u32 {
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    // Writing to a Vec can't fail
+    let _ = encoder.write_all(bytes);
+    encoder.finish().map_or(0, |out| out.len() as u32)
+}
+
+pub(crate) fn brotli_size(bytes: &[u8]) -> u32 {
+    let mut out = Vec::new();
+    {
+        let mut writer =
+            brotli::CompressorWriter::new(&mut out, 4096, BROTLI_QUALITY, BROTLI_WINDOW);
+        let _ = writer.write_all(bytes);
+    }
+    out.len() as u32
+}
+
+/// The construct of an SVG that can run script, if any: scripts, event
+/// handler attributes, `javascript:` links and embedded documents.
+fn svg_script(svg: &str) -> Option<String> {
+    start_tags(svg).into_iter().find_map(|element| {
+        if matches!(
+            element.name.as_str(),
+            "script" | "foreignobject" | "iframe" | "embed" | "object"
+        ) {
+            return Some(format!("a <{}> element", element.name));
+        }
+        element.attributes.iter().find_map(|attr| {
+            let value = attr.value.as_deref().unwrap_or_default();
+            let script_url = matches!(attr.name.as_str(), "href" | "xlink:href" | "src")
+                && value
+                    .trim_start()
+                    .get(..11)
+                    .is_some_and(|scheme| scheme.eq_ignore_ascii_case("javascript:"));
+            if attr.name.starts_with("on") {
+                Some(format!("an '{}' handler", attr.name))
+            } else if script_url {
+                Some("a javascript: URL".to_string())
+            } else {
+                None
+            }
+        })
+    })
+}
+
+fn validate(path: &str, bytes: &[u8], limits: &Limits) -> AssetReport {
+    let format = sniff(bytes);
+    let size = bytes.len() as u32;
+    let extension = path
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_ascii_lowercase())
+        .unwrap_or_default();
+    let mut report = AssetReport {
+        path: path.to_string(),
+        content_type: format.as_str().to_string(),
+        size,
+        width: None,
+        height: None,
+        gzip_size: None,
+        brotli_size: None,
+        issues: Vec::new(),
+    };
+
+    if let Some(expected) = Format::expected(&extension) {
+        // SVG is text too
+        let text_ok = expected == [Format::Text] && format == Format::Svg;
+        if !expected.contains(&format) && !text_ok {
+            report.issues.push(issue(
+                "type-mismatch",
+                "error",
+                format!(
+                    "The .{} extension doesn't match the content, which is {}",
+                    extension,
+                    format.as_str()
+                ),
+            ));
+        }
+    }
+    // Scripts and event handlers are expected in templates; the security
+    // scan checks what they run
+    let template = matches!(extension.as_str(), "liquid" | "html" | "htm");
+    if format == Format::Svg && !template {
+        if let Some(construct) = svg_script(&String::from_utf8_lossy(bytes)) {
+            report.issues.push(issue(
+                "unsafe-svg",
+                "error",
+                format!("SVG content with {} can run script", construct),
+            ));
+        }
+    }
+    if size > limits.asset {
+        report.issues.push(issue(
+            "file-too-large",
+            "error",
+            format!(
+                "{} is over the {} limit",
+                format_size(size),
+                format_size(limits.asset)
+            ),
+        ));
+    }
+
+    if format.is_raster() {
+        if size > limits.image {
+            report.issues.push(issue(
+                "large-image",
+                "warning",
+                format!(
+                    "{} image is over the {} budget; consider optimizing it for the web",
+                    format_size(size),
+                    format_size(limits.image)
+                ),
+            ));
+        }
+        if let Some((width, height)) = dimensions(format, bytes) {
+            report.width = Some(width);
+            report.height = Some(height);
+            if width.max(height) > limits.dimension {
+                report.issues.push(issue(
+                    "image-dimensions",
+                    "warning",
+                    format!(
+                        "{}x{} image is larger than {}px; consider resizing it",
+                        width, height, limits.dimension
+                    ),
+                ));
+            }
+        }
+    }
+
+    let budget = match extension.as_str() {
+        "css" => Some(("Stylesheet", limits.css)),
+        "js" => Some(("Script", limits.js)),
+        _ => None,
+    };
+    if let Some((what, budget)) = budget.filter(|_| format == Format::Text) {
+        let gzip = gzip_size(bytes);
+        report.gzip_size = Some(gzip);
+        report.brotli_size = Some(brotli_size(bytes));
+        if gzip > budget {
+            report.issues.push(issue(
+                "over-budget",
+                "warning",
+                format!(
+                    "{} is {} gzipped, over the {} budget; consider splitting it",
+                    what,
+                    format_size(gzip),
+                    format_size(budget)
+                ),
+            ));
+        }
+    }
+    report
+}
+
+/// Validates theme assets by their content.
+///
+/// Checks that each extension matches the format sniffed from the content,
+/// that SVG can't run script, and that files, images and gzipped
+/// stylesheets and scripts stay within budget. Assets are validated in
+/// parallel.
+///
+/// # Arguments
+///
+/// * `files` - Theme assets
+/// * `budget` - Size limits; each defaults to the TypeScript validator's
+///
+/// # Returns
+///
+/// A report per asset with its content type, dimensions, compressed sizes
+/// and issues
+///
+/// # Examples
+///
+/// ```javascript
+/// const result = validateAssets([{ path: "assets/hero.png", content: fs.readFileSync("hero.png") }]);
+/// result.assets[0].contentType // "png"
+/// result.assets[0].width // 1920
+/// ```
+#[napi]
+pub fn validate_assets(
+    files: Vec<AssetFile>,
+    budget: Option<AssetBudget>,
+) -> AssetValidationResult {
+    let limits = Limits::from(budget.unwrap_or_default());
+    let assets: Vec<AssetReport> = files
+        .par_iter()
+        .map(|file| validate(&file.path, &file.content, &limits))
+        .collect();
+    let count = |severity: &str| {
+        assets
+            .iter()
+            .flat_map(|asset| &asset.issues)
+            .filter(|issue| issue.severity == severity)
+            .count() as u32
+    };
+    AssetValidationResult {
+        error_count: count("error"),
+        warning_count: count("warning"),
+        assets,
+    }
+}
+
+/// Recognizes the format of a file from its content.
+///
+/// # Arguments
+///
+/// * `content` - The file content
+///
+/// # Returns
+///
+/// "jpeg", "png", "gif", "webp", "avif", "svg", "ico", "woff", "woff2",
+/// "ttf", "otf", "eot", "text" or "binary"
+///
+/// # Examples
+///
+/// ```javascript
+/// detectContentType(fs.readFileSync("logo.png")) // "svg" if it's really an SVG
+/// ```
+#[napi]
+pub fn detect_content_type(content: Buffer) -> String {
+    sniff(&content).as_str().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::sniff::tests::png;
+    use super::*;
+
+    use crate::test_support::example_theme_files;
+
+    fn asset(path: &str, content: &[u8]) -> AssetFile {
+        AssetFile {
+            path: path.to_string(),
+            content: Buffer::from(content.to_vec()),
+        }
+    }
+
+    fn codes(report: &AssetReport) -> Vec<&str> {
+        report.issues.iter().map(|i| i.code.as_str()).collect()
+    }
+
+    #[test]
+    fn test_svg_script() {
+        let cases = [
+            ("<svg><path d=\"M0 0\"/></svg>", None),
+            (
+                "<svg><script>alert(1)</script></svg>",
+                Some("a <script> element"),
+            ),
+            (
+                "<svg><g onload=\"alert(1)\"/></svg>",
+                Some("an 'onload' handler"),
+            ),
+            (
+                "<svg><a xlink:href=\" JavaScript:alert(1)\">x</a></svg>",
+                Some("a javascript: URL"),
+            ),
+            (
+                "<svg><foreignObject><p>x</p></foreignObject></svg>",
+                Some("a <foreignobject> element"),
+            ),
+        ];
+        for (svg, expected) in cases {
+            assert_eq!(svg_script(svg).as_deref(), expected, "{}", svg);
+        }
+    }
+
+    #[test]
+    fn test_validate_assets() {
+        let script_svg = b"<?xml version=\"1.0\"?><svg onload=\"alert(1)\"></svg>";
+        let css = "body { color: red; }\n".repeat(2000);
+        let files = vec![
+            asset("assets/logo.png", &png(400, 200)),
+            asset("assets/logo-2.png", script_svg),
+            asset("assets/hero.jpg", &png(6000, 4000)),
+            asset("assets/icon.svg", b"<svg viewBox=\"0 0 1 1\"/>"),
+            asset("assets/theme.css", css.as_bytes()),
+            asset("assets/font.woff2", b"not a font"),
+            asset("assets/data.bin", b"\x00\x01"),
+            asset(
+                "sections/icon.liquid",
+                b"<div class=\"icon\">\n  <svg viewBox=\"0 0 1 1\"><path d=\"M0 0\"/></svg>\n  \
+                  <button onclick=\"toggle()\">x</button>\n</div>\n<script>function toggle() {}</script>",
+            ),
+            asset(
+                "snippets/icon-cart.liquid",
+                b"<svg class=\"{{ class }}\" onload=\"init()\"></svg>",
+            ),
+        ];
+        let budget = AssetBudget {
+            max_css_size: Some(100),
+            ..Default::default()
+        };
+        let result = validate_assets(files, Some(budget));
+        let summary: Vec<(&str, &str, Vec<&str>)> = result
+            .assets
+            .iter()
+            .map(|a| (a.path.as_str(), a.content_type.as_str(), codes(a)))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("assets/logo.png", "png", vec![]),
+                (
+                    "assets/logo-2.png",
+                    "svg",
+                    vec!["type-mismatch", "unsafe-svg"]
+                ),
+                (
+                    "assets/hero.jpg",
+                    "png",
+                    vec!["type-mismatch", "image-dimensions"]
+                ),
+                ("assets/icon.svg", "svg", vec![]),
+                ("assets/theme.css", "text", vec!["over-budget"]),
+                ("assets/font.woff2", "text", vec!["type-mismatch"]),
+                ("assets/data.bin", "binary", vec![]),
+                ("sections/icon.liquid", "text", vec![]),
+                ("snippets/icon-cart.liquid", "svg", vec![]),
+            ]
+        );
+        assert_eq!((result.error_count, result.warning_count), (4, 2));
+
+        let logo = &result.assets[0];
+        assert_eq!((logo.width, logo.height), (Some(400), Some(200)));
+        assert_eq!(
+            result.assets[1].issues[0].message,
+            "The .png extension doesn't match the content, which is svg"
+        );
+        let theme = &result.assets[4];
+        assert_eq!(theme.size, 42000);
+        let (gzip, brotli) = (theme.gzip_size.unwrap(), theme.brotli_size.unwrap());
+        assert!(
+            brotli > 0 && brotli <= gzip && gzip < 200,
+            "{} {}",
+            gzip,
+            brotli
+        );
+        assert_eq!(
+            theme.issues[0].message,
+            format!(
+                "Stylesheet is {} B gzipped, over the 100 B budget; consider splitting it",
+                gzip
+            )
+        );
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(1023), "1023 B");
+        assert_eq!(format_size(1536), "1.5 KB");
+        assert_eq!(format_size(3 * 1024 * 1024), "3.00 MB");
+    }
+
+    #[test]
+    fn test_size_limits() {
+        let budget = AssetBudget {
+            max_asset_size: Some(1000),
+            max_image_size: Some(10),
+            ..Default::default()
+        };
+        let result = validate_assets(
+            vec![asset("a.gif", &[b'G'; 1001][..])],
+            Some(budget.clone()),
+        );
+        assert_eq!(
+            codes(&result.assets[0]),
+            ["type-mismatch", "file-too-large"]
+        );
+        assert_eq!(
+            result.assets[0].issues[1].message,
+            "1001 B is over the 1000 B limit"
+        );
+
+        let result = validate_assets(vec![asset("a.png", &png(10, 10))], Some(budget));
+        assert_eq!(codes(&result.assets[0]), ["large-image"]);
+    }
+
+    #[test]
+    fn test_example_theme_assets() {
+        let files = example_theme_files("")
+            .into_iter()
+            .map(|path| asset(&path.to_string_lossy(), &std::fs::read(&path).unwrap()))
+            .collect();
+        let result = validate_assets(files, None);
+        let mismatches: Vec<&AssetReport> = result
+            .assets
+            .iter()
+            .filter(|asset| asset.issues.iter().any(|i| i.severity == "error"))
+            .collect();
+        assert!(mismatches.is_empty(), "{:#?}", mismatches);
+    }
+}
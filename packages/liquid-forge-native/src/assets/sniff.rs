@@ -0,0 +1,387 @@
+/*
+ * Copyright 2025 Fasttify LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Content types from magic bytes, and image dimensions from headers.
+
+use memchr::memmem;
+
+/// A format recognized from the content of a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Format {
+    Jpeg,
+    Png,
+    Gif,
+    WebP,
+    Avif,
+    Svg,
+    Ico,
+    Woff,
+    Woff2,
+    Ttf,
+    Otf,
+    Eot,
+    /// UTF-8 text that isn't SVG.
+    Text,
+    /// Anything else.
+    Binary,
+}
+
+impl Format {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Format::Jpeg => "jpeg",
+            Format::Png => "png",
+            Format::Gif => "gif",
+            Format::WebP => "webp",
+            Format::Avif => "avif",
+            Format::Svg => "svg",
+            Format::Ico => "ico",
+            Format::Woff => "woff",
+            Format::Woff2 => "woff2",
+            Format::Ttf => "ttf",
+            Format::Otf => "otf",
+            Format::Eot => "eot",
+            Format::Text => "text",
+            Format::Binary => "binary",
+        }
+    }
+
+    /// Whether the format is a bitmap image with dimensions in its header.
+    pub fn is_raster(self) -> bool {
+        matches!(
+            self,
+            Format::Jpeg | Format::Png | Format::Gif | Format::WebP | Format::Avif | Format::Ico
+        )
+    }
+
+    /// The formats a file extension may hold, or `None` for extensions
+    /// that aren't checked.
+    pub fn expected(extension: &str) -> Option<&'static [Format]> {
+        Some(match extension {
+            "jpg" | "jpeg" => &[Format::Jpeg],
+            "png" => &[Format::Png],
+            "gif" => &[Format::Gif],
+            "webp" => &[Format::WebP],
+            "avif" => &[Format::Avif],
+            "svg" => &[Format::Svg],
+            "ico" => &[Format::Ico, Format::Png],
+            "woff" => &[Format::Woff],
+            "woff2" => &[Format::Woff2],
+            "ttf" | "otf" => &[Format::Ttf, Format::Otf],
+            "eot" => &[Format::Eot],
+            "liquid" | "json" | "css" | "js" | "md" | "txt" | "html" | "xml" => &[Format::Text],
+            _ => return None,
+        })
+    }
+}
+
+/// How many bytes of text are searched for the `<svg` root.
+const SVG_PREFIX: usize = 4096;
+
+/// Whether the document's root element is `<svg>`: only whitespace, the XML
+/// declaration, processing instructions, comments and a doctype may come
+/// before it. Markup that merely contains an inline `<svg>` is not SVG.
+fn is_svg(bytes: &[u8]) -> bool {
+    let head = &bytes[..bytes.len().min(SVG_PREFIX)];
+    let mut rest = head.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(head);
+    loop {
+        let start = rest
+            .iter()
+            .position(|b| !b.is_ascii_whitespace())
+            .unwrap_or(rest.len());
+        rest = &rest[start..];
+        let end = if rest.starts_with(b"<?") {
+            memmem::find(rest, b"?>").map(|end| end + 2)
+        } else if rest.starts_with(b"<!--") {
+            memmem::find(rest, b"-->").map(|end| end + 3)
+        } else if rest.starts_with(b"<!") {
+            // A doctype, whose internal subset may contain `>`
+            let from = match rest.iter().position(|&b| b == b'>' || b == b'[') {
+                Some(open) if rest[open] == b'[' => {
+                    memchr::memchr(b']', &rest[open..]).map(|end| open + end)
+                }
+                close => close,
+            };
+            from.and_then(|from| memchr::memchr(b'>', &rest[from..]).map(|end| from + end + 1))
+        } else {
+            break;
+        };
+        match end {
+            Some(end) => rest = &rest[end..],
+            None => return false,
+        }
+    }
+    rest.strip_prefix(b"<svg").is_some_and(|after| {
+        after
+            .first()
+            .is_some_and(|&b| b.is_ascii_whitespace() || b == b'>' || b == b'/')
+    })
+}
+
+/// Whether the content is text rather than a binary format: valid UTF-8
+/// without NUL bytes. A multi-byte character cut by the end of a
+/// truncated file doesn't count against it.
+fn is_text(bytes: &[u8]) -> bool {
+    if bytes.contains(&0) {
+        return false;
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(_) => true,
+        Err(error) => error.error_len().is_none(),
+    }
+}
+
+/// Recognizes the format of `bytes` from its signature.
+pub(crate) fn sniff(bytes: &[u8]) -> Format {
+    let at = |offset: usize, magic: &[u8]| bytes.get(offset..offset + magic.len()) == Some(magic);
+    if at(0, b"\xFF\xD8\xFF") {
+        Format::Jpeg
+    } else if at(0, b"\x89PNG\r\n\x1A\n") {
+        Format::Png
+    } else if at(0, b"GIF87a") || at(0, b"GIF89a") {
+        Format::Gif
+    } else if at(0, b"RIFF") && at(8, b"WEBP") {
+        Format::WebP
+    } else if at(4, b"ftyp") && is_avif(bytes) {
+        Format::Avif
+    } else if at(0, b"\x00\x00\x01\x00") && bytes.get(4..6).is_some_and(|count| count != [0, 0]) {
+        Format::Ico
+    } else if at(0, b"wOFF") {
+        Format::Woff
+    } else if at(0, b"wOF2") {
+        Format::Woff2
+    } else if at(0, b"\x00\x01\x00\x00") || (at(0, b"true") && !is_text(bytes)) {
+        Format::Ttf
+    } else if at(0, b"OTTO") {
+        Format::Otf
+    } else if at(34, b"LP") && !is_text(bytes) {
+        Format::Eot
+    } else if is_svg(bytes) {
+        Format::Svg
+    } else if is_text(bytes) {
+        Format::Text
+    } else {
+        Format::Binary
+    }
+}
+
+/// Whether the `ftyp` box names an AVIF brand, as major or compatible brand.
+fn is_avif(bytes: &[u8]) -> bool {
+    let size = read_u32_be(bytes, 0).unwrap_or(0) as usize;
+    let brands = bytes.get(8..size.min(bytes.len())).unwrap_or_default();
+    brands
+        .chunks_exact(4)
+        .any(|brand| brand == b"avif" || brand == b"avis")
+}
+
+fn read_u16_be(bytes: &[u8], offset: usize) -> Option<u32> {
+    let b = bytes.get(offset..offset + 2)?;
+    Some(u16::from_be_bytes([b[0], b[1]]) as u32)
+}
+
+fn read_u16_le(bytes: &[u8], offset: usize) -> Option<u32> {
+    let b = bytes.get(offset..offset + 2)?;
+    Some(u16::from_le_bytes([b[0], b[1]]) as u32)
+}
+
+fn read_u24_le(bytes: &[u8], offset: usize) -> Option<u32> {
+    let b = bytes.get(offset..offset + 3)?;
+    Some(u32::from_le_bytes([b[0], b[1], b[2], 0]))
+}
+
+fn read_u32_be(bytes: &[u8], offset: usize) -> Option<u32> {
+    let b = bytes.get(offset..offset + 4)?;
+    Some(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+/// Width and height from the first start-of-frame segment.
+fn jpeg_dimensions(bytes: &[u8]) -> Option<(u32, u32)> {
+    let mut pos = 2;
+    loop {
+        while *bytes.get(pos)? != 0xFF {
+            pos += 1;
+        }
+        while *bytes.get(pos)? == 0xFF {
+            pos += 1;
+        }
+        let marker = *bytes.get(pos)?;
+        pos += 1;
+        match marker {
+            // Markers without a segment
+            0x01 | 0xD0..=0xD8 => continue,
+            0xD9 | 0xDA => return None,
+            0xC0..=0xCF if !matches!(marker, 0xC4 | 0xC8 | 0xCC) => {
+                let height = read_u16_be(bytes, pos + 3)?;
+                let width = read_u16_be(bytes, pos + 5)?;
+                return Some((width, height));
+            }
+            _ => pos += read_u16_be(bytes, pos)? as usize,
+        }
+    }
+}
+
+fn webp_dimensions(bytes: &[u8]) -> Option<(u32, u32)> {
+    match bytes.get(12..16)? {
+        b"VP8 " => Some((
+            read_u16_le(bytes, 26)? & 0x3FFF,
+            read_u16_le(bytes, 28)? & 0x3FFF,
+        )),
+        b"VP8L" => {
+            let b = bytes.get(21..25)?;
+            let (b0, b1, b2, b3) = (b[0] as u32, b[1] as u32, b[2] as u32, b[3] as u32);
+            Some((
+                1 + (b0 | (b1 & 0x3F) << 8),
+                1 + (b1 >> 6 | b2 << 2 | (b3 & 0x0F) << 10),
+            ))
+        }
+        b"VP8X" => Some((1 + read_u24_le(bytes, 24)?, 1 + read_u24_le(bytes, 27)?)),
+        _ => None,
+    }
+}
+
+/// Width and height from the first image spatial extents (`ispe`) property.
+fn avif_dimensions(bytes: &[u8]) -> Option<(u32, u32)> {
+    let ispe = memmem::find(bytes, b"ispe")?;
+    // Box type, then version and flags
+    Some((
+        read_u32_be(bytes, ispe + 8)?,
+        read_u32_be(bytes, ispe + 12)?,
+    ))
+}
+
+/// Width and height of a raster image, from its header.
+pub(crate) fn dimensions(format: Format, bytes: &[u8]) -> Option<(u32, u32)> {
+    let dimensions = match format {
+        Format::Png if bytes.get(12..16) == Some(b"IHDR") => {
+            (read_u32_be(bytes, 16)?, read_u32_be(bytes, 20)?)
+        }
+        Format::Gif => (read_u16_le(bytes, 6)?, read_u16_le(bytes, 8)?),
+        Format::Jpeg => jpeg_dimensions(bytes)?,
+        Format::WebP => webp_dimensions(bytes)?,
+        Format::Avif => avif_dimensions(bytes)?,
+        // The first icon; 0 stands for 256
+        Format::Ico => {
+            let size = |offset: usize| {
+                bytes
+                    .get(offset)
+                    .map(|&b| if b == 0 { 256 } else { b as u32 })
+            };
+            (size(6)?, size(7)?)
+        }
+        _ => return None,
+    };
+    (dimensions.0 > 0 && dimensions.1 > 0).then_some(dimensions)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    pub(crate) fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut bytes = b"\x89PNG\r\n\x1A\n\x00\x00\x00\x0DIHDR".to_vec();
+        bytes.extend(width.to_be_bytes());
+        bytes.extend(height.to_be_bytes());
+        bytes.extend([8, 6, 0, 0, 0]);
+        bytes
+    }
+
+    fn jpeg(width: u16, height: u16) -> Vec<u8> {
+        let mut bytes = vec![0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x04, 0x4A, 0x46];
+        // Padding fill bytes, then a baseline frame header
+        bytes.extend([0xFF, 0xFF, 0xC0, 0x00, 0x11, 0x08]);
+        bytes.extend(height.to_be_bytes());
+        bytes.extend(width.to_be_bytes());
+        bytes.extend([0x03, 0x01]);
+        bytes
+    }
+
+    fn webp(chunk: &[u8], header: &[u8]) -> Vec<u8> {
+        let mut bytes = b"RIFF\x00\x00\x00\x00WEBP".to_vec();
+        bytes.extend(chunk);
+        bytes.extend(header);
+        bytes
+    }
+
+    #[test]
+    fn test_sniff() {
+        let avif = b"\x00\x00\x00\x1Cftypmif1\x00\x00\x00\x00mif1avifmiaf";
+        let cases: [(&[u8], Format); 20] = [
+            (&png(1, 1), Format::Png),
+            (&jpeg(1, 1), Format::Jpeg),
+            (b"GIF89a\x01\x00\x01\x00", Format::Gif),
+            (&webp(b"VP8 ", &[]), Format::WebP),
+            (avif, Format::Avif),
+            (b"\x00\x00\x00\x1Cftypheic\x00\x00\x00\x00mif1heicmiaf", Format::Binary),
+            (b"\x00\x00\x01\x00\x01\x00\x10\x10", Format::Ico),
+            (b"wOFF\x00\x01", Format::Woff),
+            (b"wOF2\x00\x01", Format::Woff2),
+            (b"OTTO\x00\x0A", Format::Otf),
+            (
+                b"\xEF\xBB\xBF<?xml version=\"1.0\"?>\n<!-- x -->\n<svg xmlns=\"http://www.w3.org/2000/svg\"/>",
+                Format::Svg,
+            ),
+            (b"<!doctype html><html><body><svg></svg>", Format::Text),
+            (b"<div>\n  <svg viewBox=\"0 0 1 1\"></svg>\n</div>", Format::Text),
+            (
+                b"<!DOCTYPE svg [<!ENTITY x \"<svg>\">]>\n<svg>&x;</svg>",
+                Format::Svg,
+            ),
+            (b"<svgfoo/>", Format::Text),
+            (b"body { color: red }", Format::Text),
+            (b"true story", Format::Text),
+            ("caf\u{e9}".as_bytes(), Format::Text),
+            (b"caf\xC3", Format::Text),
+            (b"\x00\x10binary\xFF", Format::Binary),
+        ];
+        for (bytes, expected) in cases {
+            assert_eq!(sniff(bytes), expected, "{:?}", bytes);
+        }
+    }
+
+    #[test]
+    fn test_dimensions() {
+        let vp8l = webp(b"VP8L\x00\x00\x00\x00", &[0x2F, 0x3F, 0xC0, 0x0F, 0x00]);
+        let mut ispe = b"\x00\x00\x00\x10ftypavif".to_vec();
+        ispe.extend(b"\x00\x00\x00\x14ispe\x00\x00\x00\x00\x00\x00\x07\x80\x00\x00\x04\x38");
+        type Case<'a> = (&'a [u8], Option<(u32, u32)>);
+        let cases: [Case; 9] = [
+            (&png(1920, 1080), Some((1920, 1080))),
+            (&jpeg(640, 480), Some((640, 480))),
+            (b"GIF89a\x20\x03\x58\x02", Some((800, 600))),
+            (
+                &webp(
+                    b"VP8 \x00\x00\x00\x00",
+                    b"\x00\x00\x00\x9D\x01\x2A\x80\x02\xE0\x01",
+                ),
+                Some((640, 480)),
+            ),
+            (&vp8l, Some((64, 64))),
+            (
+                &webp(
+                    b"VP8X\x00\x00\x00\x00",
+                    b"\x00\x00\x00\x00\x7F\x07\x00\x37\x04\x00",
+                ),
+                Some((1920, 1080)),
+            ),
+            (&ispe, Some((1920, 1080))),
+            (b"\x00\x00\x01\x00\x01\x00\x00\x00", Some((256, 256))),
+            (&png(1, 1)[..20], None),
+        ];
+        for (bytes, expected) in cases {
+            assert_eq!(dimensions(sniff(bytes), bytes), expected, "{:?}", bytes);
+        }
+    }
+}
@@ -0,0 +1,396 @@
+/*
+ * Copyright 2025 Fasttify LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Article filters: excerpts, word counts and reading time.
+
+use napi::{Error, Result, Status};
+
+use super::html::unescape_str;
+
+/// Tags whose content is not part of the readable text.
+const HIDDEN_CONTENT_TAGS: &[&str] = &["script", "style", "noscript", "template"];
+
+/// Characters that end a sentence.
+const SENTENCE_TERMINATORS: &[char] = &['.', '!', '?', '…'];
+
+/// Characters that may follow a terminator and still belong to the sentence.
+const SENTENCE_CLOSERS: &[char] = &['"', '\'', ')', ']', '»', '”', '’'];
+
+/// Characters that should not be left dangling before an ellipsis.
+/// Includes the Spanish opening marks, which start rather than end a clause.
+const TRAILING_PUNCTUATION: &[char] = &[',', ';', ':', '-', '–', '—', '(', '[', '«', '“', '¿', '¡'];
+
+/// Options accepted by the `excerpt` filter.
+#[napi(object)]
+#[derive(Default)]
+pub struct ExcerptOptions {
+    /// What `limit` counts: "words" (default) or "chars".
+    pub unit: Option<String>,
+    /// String appended when the excerpt does not end on a sentence
+    /// boundary (default: "…").
+    pub ellipsis: Option<String>,
+}
+
+/// Builds a plain-text excerpt from HTML.
+///
+/// The text is extracted first (tags removed, `<script>`/`<style>` content
+/// dropped, entities decoded, whitespace collapsed). If it is longer than
+/// `limit`, the excerpt ends on the last sentence boundary within the limit
+/// when that keeps at least half of it; otherwise it ends on a word
+/// boundary followed by the ellipsis.
+///
+/// # Arguments
+///
+/// * `html` - The article HTML
+/// * `limit` - Maximum number of words or characters (default: 30)
+/// * `options` - Unit and ellipsis
+///
+/// # Returns
+///
+/// The excerpt, or an error if the unit is unknown
+///
+/// # Examples
+///
+/// ```javascript
+/// excerpt("<p>Hola mundo. ¿Qué tal estás hoy?</p>", 4)
+/// // "Hola mundo."
+///
+/// excerpt("<p>Camisetas de algodón orgánico</p>", 14, { unit: "chars" })
+/// // "Camisetas de…"
+/// ```
+#[napi]
+pub fn excerpt(
+    html: Option<String>,
+    limit: Option<u32>,
+    options: Option<ExcerptOptions>,
+) -> Result<String> {
+    let options = options.unwrap_or_default();
+    let by_chars = match options.unit.as_deref().unwrap_or("words") {
+        "words" => false,
+        "chars" => true,
+        unit => {
+            return Err(Error::new(
+                Status::InvalidArg,
+                format!("Unknown excerpt unit '{}'", unit),
+            ))
+        }
+    };
+
+    let text = match html {
+        Some(h) if !h.is_empty() => html_to_text(&h),
+        _ => return Ok(String::new()),
+    };
+
+    let limit = limit.unwrap_or(30) as usize;
+    let cut = if by_chars {
+        char_cut(&text, limit)
+    } else {
+        word_cut(&text, limit)
+    };
+
+    let Some(cut) = cut else {
+        return Ok(text);
+    };
+
+    if let Some(end) = sentence_end(&text[..cut]) {
+        if text[..end].chars().count() * 2 >= text[..cut].chars().count() {
+            return Ok(text[..end].to_string());
+        }
+    }
+
+    let ellipsis = options.ellipsis.as_deref().unwrap_or("…");
+    let mut result = text[..cut]
+        .trim_end_matches(|c: char| c.is_whitespace() || TRAILING_PUNCTUATION.contains(&c))
+        .to_string();
+    result.push_str(ellipsis);
+    Ok(result)
+}
+
+/// Counts the words in HTML or plain text.
+///
+/// Markup, `<script>`/`<style>` content and standalone punctuation such as
+/// `¿`, `¡` or `—` are not counted.
+///
+/// # Examples
+///
+/// ```javascript
+/// wordCount("<p>¿Qué tal? <b>Bien</b> — gracias</p>") // 4
+/// ```
+#[napi]
+pub fn word_count(html: Option<String>) -> u32 {
+    match html {
+        Some(h) if !h.is_empty() => count_words(&html_to_text(&h)) as u32,
+        _ => 0,
+    }
+}
+
+/// Estimates the reading time of HTML content in whole minutes.
+///
+/// # Arguments
+///
+/// * `html` - The article HTML
+/// * `words_per_minute` - Reading speed (default: 200)
+///
+/// # Returns
+///
+/// Minutes rounded up; at least 1 for any non-empty text, 0 otherwise
+///
+/// # Examples
+///
+/// ```javascript
+/// readingTime(articleHtml)       // 5
+/// readingTime(articleHtml, 250)  // 4
+/// ```
+#[napi]
+pub fn reading_time(html: Option<String>, words_per_minute: Option<u32>) -> u32 {
+    let words = word_count(html);
+    let per_minute = words_per_minute.filter(|w| *w > 0).unwrap_or(200);
+    words.div_ceil(per_minute)
+}
+
+/// Extracts readable text from HTML.
+///
+/// Removes tags and the content of non-visible elements, decodes entities
+/// and collapses every whitespace run into a single space.
+pub(crate) fn html_to_text(html: &str) -> String {
+    let mut raw = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while let Some(start) = rest.find('<') {
+        raw.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let Some(end) = rest.find('>') else {
+            // Unterminated tag: keep it as text
+            break;
+        };
+        let tag = &rest[1..end];
+        rest = &rest[end + 1..];
+        raw.push(' ');
+
+        if let Some(name) = hidden_tag_name(tag) {
+            let close = format!("</{}", name);
+            match find_ascii_case_insensitive(rest, &close) {
+                Some(pos) => {
+                    rest = &rest[pos..];
+                    rest = rest.find('>').map_or("", |gt| &rest[gt + 1..]);
+                }
+                None => rest = "",
+            }
+        }
+    }
+    raw.push_str(rest);
+
+    let decoded = unescape_str(&raw);
+    let mut text = String::with_capacity(decoded.len());
+    for word in decoded.split_whitespace() {
+        if !text.is_empty() {
+            text.push(' ');
+        }
+        text.push_str(word);
+    }
+    text
+}
+
+/// Returns the tag name if `tag` opens an element whose content is hidden.
+fn hidden_tag_name(tag: &str) -> Option<&'static str> {
+    let name_end = tag
+        .find(|c: char| c.is_whitespace() || c == '/')
+        .unwrap_or(tag.len());
+    let name = &tag[..name_end];
+    HIDDEN_CONTENT_TAGS
+        .iter()
+        .find(|hidden| hidden.eq_ignore_ascii_case(name))
+        .copied()
+}
+
+fn find_ascii_case_insensitive(haystack: &str, needle: &str) -> Option<usize> {
+    haystack
+        .as_bytes()
+        .windows(needle.len())
+        .position(|window| window.eq_ignore_ascii_case(needle.as_bytes()))
+}
+
+/// A word is a whitespace-separated token with at least one alphanumeric
+/// character.
+fn is_word(token: &str) -> bool {
+    token.chars().any(char::is_alphanumeric)
+}
+
+fn count_words(text: &str) -> usize {
+    text.split_whitespace().filter(|t| is_word(t)).count()
+}
+
+/// Byte offset where `text` must be cut to keep `limit` words, or `None`
+/// if it already fits.
+fn word_cut(text: &str, limit: usize) -> Option<usize> {
+    let mut count = 0;
+    let mut offset = 0;
+
+    for token in text.split(' ') {
+        if is_word(token) {
+            if count == limit {
+                return Some(offset);
+            }
+            count += 1;
+        }
+        offset += token.len() + 1;
+    }
+
+    None
+}
+
+/// Byte offset where `text` must be cut to keep at most `limit` characters
+/// without splitting a word, or `None` if it already fits.
+fn char_cut(text: &str, limit: usize) -> Option<usize> {
+    let (pos, _) = text.char_indices().nth(limit)?;
+    if text[pos..].starts_with(' ') {
+        return Some(pos);
+    }
+    // Back up to the start of the word that straddles the limit
+    Some(text[..pos].rfind(' ').unwrap_or(pos))
+}
+
+/// Byte offset just past the last sentence terminator (and its closing
+/// quotes or brackets) that is followed by a space or the end of `text`.
+fn sentence_end(text: &str) -> Option<usize> {
+    let mut best = None;
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        if !SENTENCE_TERMINATORS.contains(&c) {
+            continue;
+        }
+
+        let mut end = i + c.len_utf8();
+        while let Some(&(j, next)) = chars.peek() {
+            if SENTENCE_TERMINATORS.contains(&next) || SENTENCE_CLOSERS.contains(&next) {
+                end = j + next.len_utf8();
+                chars.next();
+            } else {
+                break;
+            }
+        }
+
+        if text[end..].is_empty() || text[end..].starts_with(' ') {
+            best = Some(end);
+        }
+    }
+
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn excerpt_of(html: &str, limit: u32, unit: &str) -> String {
+        excerpt(
+            Some(html.to_string()),
+            Some(limit),
+            Some(ExcerptOptions {
+                unit: Some(unit.to_string()),
+                ellipsis: None,
+            }),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_html_to_text() {
+        assert_eq!(
+            html_to_text(
+                "<p>Hola&nbsp;<b>mundo</b></p>\n<script>var a = '<p>';</script><STYLE>p{}</style>Fin"
+            ),
+            "Hola mundo Fin"
+        );
+        assert_eq!(html_to_text("a < b"), "a < b");
+    }
+
+    #[test]
+    fn test_excerpt_words() {
+        assert_eq!(
+            excerpt_of("<p>Hola mundo. ¿Qué tal estás hoy?</p>", 4, "words"),
+            "Hola mundo."
+        );
+        assert_eq!(
+            excerpt_of(
+                "<p>Nuestra colección de verano, ¡ya disponible!</p>",
+                4,
+                "words"
+            ),
+            "Nuestra colección de verano…"
+        );
+        assert_eq!(
+            excerpt_of("<p>Compra hoy y recibe mañana. ¿Dudas?</p>", 3, "words"),
+            "Compra hoy y…"
+        );
+        assert_eq!(excerpt_of("<p>Corto.</p>", 30, "words"), "Corto.");
+    }
+
+    #[test]
+    fn test_excerpt_spanish_punctuation() {
+        assert_eq!(
+            excerpt_of("Envío gratis ¡Solo hoy! ¿Te lo vas a perder?", 3, "words"),
+            "Envío gratis ¡Solo…"
+        );
+        assert_eq!(
+            excerpt_of("Envío gratis, ¿sabías? Ahora más", 2, "words"),
+            "Envío gratis…"
+        );
+        assert_eq!(
+            excerpt_of("«Es perfecto.» Dijo la clienta feliz", 3, "words"),
+            "«Es perfecto.»"
+        );
+    }
+
+    #[test]
+    fn test_excerpt_chars() {
+        assert_eq!(
+            excerpt_of("<p>Camisetas de algodón orgánico</p>", 14, "chars"),
+            "Camisetas de…"
+        );
+        assert_eq!(excerpt_of("Supercalifragilístico", 5, "chars"), "Super…");
+        assert!(excerpt(
+            Some("x".to_string()),
+            None,
+            Some(ExcerptOptions {
+                unit: Some("lines".to_string()),
+                ellipsis: None,
+            })
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_word_count() {
+        assert_eq!(
+            word_count(Some("<p>¿Qué tal? <b>Bien</b> — gracias</p>".to_string())),
+            4
+        );
+        assert_eq!(word_count(Some("<style>a b c</style>".to_string())), 0);
+        assert_eq!(word_count(None), 0);
+    }
+
+    #[test]
+    fn test_reading_time() {
+        let article = "palabra ".repeat(1001);
+        assert_eq!(reading_time(Some(article.clone()), None), 6);
+        assert_eq!(reading_time(Some(article), Some(250)), 5);
+        assert_eq!(reading_time(Some("Hola".to_string()), None), 1);
+        assert_eq!(reading_time(None, None), 0);
+    }
+}
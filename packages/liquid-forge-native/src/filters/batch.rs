@@ -0,0 +1,139 @@
+/*
+ * Copyright 2025 Fasttify LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Batch variants of the text filters.
+//!
+//! Collection pages apply the same filter to every product, and each call
+//! is a NAPI round-trip. These functions take all inputs at once, either as
+//! an array or as a Buffer of UTF-8 strings separated by NUL bytes, and
+//! return an array in the same order.
+
+use napi::bindgen_prelude::{Buffer, Either};
+use napi::{Error, Result, Status};
+
+use super::{escape_into, handleize};
+
+/// Inputs of a batch filter: an array of strings (`null` counts as empty)
+/// or a Buffer of UTF-8 strings separated by NUL bytes.
+pub type BatchInput = Either<Vec<Option<String>>, Buffer>;
+
+/// Calls `f` on every input, in order.
+pub(crate) fn map_batch<T>(inputs: &BatchInput, mut f: impl FnMut(&str) -> T) -> Result<Vec<T>> {
+    match inputs {
+        Either::A(items) => Ok(items
+            .iter()
+            .map(|item| f(item.as_deref().unwrap_or_default()))
+            .collect()),
+        Either::B(buffer) => split_buffer(buffer)
+            .map(|item| {
+                item.map(&mut f)
+                    .map_err(|message| Error::new(Status::InvalidArg, message))
+            })
+            .collect(),
+    }
+}
+
+/// Splits a NUL-separated buffer without copying. An empty buffer holds
+/// no strings.
+pub(crate) fn split_buffer(
+    buffer: &[u8],
+) -> impl Iterator<Item = std::result::Result<&str, String>> {
+    let parts = (!buffer.is_empty()).then(|| buffer.split(|byte| *byte == 0));
+    parts.into_iter().flatten().enumerate().map(|(i, bytes)| {
+        std::str::from_utf8(bytes).map_err(|_| format!("Batch input {} is not valid UTF-8", i))
+    })
+}
+
+/// Converts many strings to URL-friendly handles in one call.
+///
+/// # Arguments
+///
+/// * `inputs` - The texts, as an array or a NUL-separated Buffer
+///
+/// # Returns
+///
+/// One handle per input, in order
+///
+/// # Examples
+///
+/// ```javascript
+/// handleizeMany(["Camisa Azul", "Ñoño & Friends", null])
+/// // ["camisa-azul", "nono-friends", ""]
+///
+/// handleizeMany(Buffer.from(titles.join("\0")))
+/// ```
+#[napi]
+pub fn handleize_many(inputs: BatchInput) -> Result<Vec<String>> {
+    map_batch(&inputs, |text| handleize(Some(text.to_string())))
+}
+
+/// Escapes many strings for HTML in one call.
+///
+/// # Arguments
+///
+/// * `inputs` - The texts, as an array or a NUL-separated Buffer
+///
+/// # Returns
+///
+/// One escaped string per input, in order
+///
+/// # Examples
+///
+/// ```javascript
+/// escapeMany(["Rock & Roll", "<b>"])
+/// // ["Rock &amp; Roll", "&lt;b&gt;"]
+/// ```
+#[napi]
+pub fn escape_many(inputs: BatchInput) -> Result<Vec<String>> {
+    map_batch(&inputs, |text| {
+        let mut out = String::with_capacity(text.len());
+        escape_into(text, &mut out);
+        out
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batch_inputs() {
+        let array = Either::A(vec![
+            Some("Camisa Azul".to_string()),
+            None,
+            Some("Ñoño & Friends".to_string()),
+        ]);
+        assert_eq!(
+            handleize_many(array).unwrap(),
+            ["camisa-azul", "", "nono-friends"]
+        );
+
+        let buffer = Either::B(Buffer::from(b"Rock & Roll\0\0<b>".to_vec()));
+        assert_eq!(
+            escape_many(buffer).unwrap(),
+            ["Rock &amp; Roll", "", "&lt;b&gt;"]
+        );
+
+        assert!(escape_many(Either::B(Buffer::from(Vec::new())))
+            .unwrap()
+            .is_empty());
+        let invalid = Either::B(Buffer::from(b"ok\0\xff".to_vec()));
+        assert_eq!(
+            handleize_many(invalid).unwrap_err().reason,
+            "Batch input 1 is not valid UTF-8"
+        );
+    }
+}
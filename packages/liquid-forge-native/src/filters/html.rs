@@ -16,6 +16,29 @@
 
 //! HTML manipulation filters.
 
+use std::collections::HashMap;
+
+use memchr::{memchr, memchr2, memchr2_iter, memchr3};
+use once_cell::sync::Lazy;
+
+/// Longest HTML5 entity name (`CounterClockwiseContourIntegral`) plus `#x`
+/// headroom for numeric references.
+const MAX_ENTITY_LEN: usize = 32;
+
+/// HTML5 named character references, keyed by name without `&` and `;`.
+///
+/// Only the semicolon-terminated forms are included; the legacy forms
+/// browsers accept without `;` (`&amp`, `&copy`) are left as text.
+static NAMED_ENTITIES: Lazy<HashMap<&'static str, &'static str>> = Lazy::new(|| {
+    entities::ENTITIES
+        .iter()
+        .filter_map(|e| {
+            let name = e.entity.strip_prefix('&')?.strip_suffix(';')?;
+            Some((name, e.characters))
+        })
+        .collect()
+});
+
 /// Escapes HTML special characters.
 ///
 /// Converts:
@@ -49,28 +72,159 @@ pub fn escape(text: Option<String>) -> String {
         _ => return String::new(),
     };
 
-    // Fast path: check if escaping is needed
-    if !text.contains(['&', '<', '>', '"', '\'']) {
+    // Fast path: nothing to escape, hand the original allocation back
+    let bytes = text.as_bytes();
+    if memchr3(b'&', b'<', b'>', bytes).is_none() && memchr2(b'"', b'\'', bytes).is_none() {
         return text;
     }
 
     // Pre-allocate with extra capacity for escape sequences
     let mut result = String::with_capacity(text.len() + (text.len() / 4));
+    escape_into(&text, &mut result);
+    result
+}
 
-    for c in text.chars() {
-        match c {
-            '&' => result.push_str("&amp;"),
-            '<' => result.push_str("&lt;"),
-            '>' => result.push_str("&gt;"),
-            '"' => result.push_str("&quot;"),
-            '\'' => result.push_str("&#x27;"),
-            _ => result.push(c),
+/// Appends the HTML-escaped form of `text` to `out`.
+///
+/// `memchr` searches for at most three needles at once, so the five special
+/// bytes are split across two vectorized scanners. Each scanner remembers its
+/// next hit and only advances once that hit is consumed, which keeps the
+/// whole pass linear while runs in between are copied as whole slices.
+pub(crate) fn escape_into(text: &str, out: &mut String) {
+    let bytes = text.as_bytes();
+    let find_markup = |from: usize| memchr3(b'&', b'<', b'>', &bytes[from..]).map(|i| from + i);
+    let find_quote = |from: usize| memchr2(b'"', b'\'', &bytes[from..]).map(|i| from + i);
+
+    let mut next_markup = find_markup(0);
+    let mut next_quote = find_quote(0);
+    let mut last = 0;
+
+    loop {
+        let pos = match (next_markup, next_quote) {
+            (Some(m), Some(q)) => m.min(q),
+            (Some(p), None) | (None, Some(p)) => p,
+            (None, None) => break,
+        };
+
+        out.push_str(&text[last..pos]);
+        out.push_str(match bytes[pos] {
+            b'&' => "&amp;",
+            b'<' => "&lt;",
+            b'>' => "&gt;",
+            b'"' => "&quot;",
+            _ => "&#x27;",
+        });
+        last = pos + 1;
+
+        if next_markup == Some(pos) {
+            next_markup = find_markup(last);
+        } else {
+            next_quote = find_quote(last);
+        }
+    }
+
+    out.push_str(&text[last..]);
+}
+
+/// Decodes HTML character references.
+///
+/// Decodes the full HTML5 named-entity table (`&ntilde;`, `&hellip;`...)
+/// plus decimal (`&#8217;`) and hexadecimal (`&#x27;`) numeric references.
+/// References that are unknown, unterminated or point to an invalid code
+/// point are left untouched. For every string `s`,
+/// `unescape_html(escape(s)) == s`.
+///
+/// # Arguments
+///
+/// * `text` - The text with character references
+///
+/// # Returns
+///
+/// Decoded text
+///
+/// # Examples
+///
+/// ```javascript
+/// unescapeHtml("Rock &amp; Roll")   // "Rock & Roll"
+/// unescapeHtml("Ni&ntilde;os")      // "Niños"
+/// unescapeHtml("It&#8217;s")        // "It’s"
+/// unescapeHtml("&bogus; &#xZZ;")    // "&bogus; &#xZZ;"
+/// ```
+#[napi]
+pub fn unescape_html(text: Option<String>) -> String {
+    let text = match text {
+        Some(t) if !t.is_empty() => t,
+        _ => return String::new(),
+    };
+
+    if memchr(b'&', text.as_bytes()).is_none() {
+        return text;
+    }
+
+    unescape_str(&text)
+}
+
+/// Decodes HTML character references in `text`. See [`unescape_html`].
+pub(crate) fn unescape_str(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut result = String::with_capacity(text.len());
+    let mut last = 0;
+    let mut from = 0;
+
+    while let Some(offset) = memchr(b'&', &bytes[from..]) {
+        let amp = from + offset;
+        from = amp + 1;
+
+        let Some((decoded, len)) = decode_reference(&text[amp + 1..]) else {
+            continue;
+        };
+
+        result.push_str(&text[last..amp]);
+        match decoded {
+            Decoded::Char(c) => result.push(c),
+            Decoded::Str(s) => result.push_str(s),
         }
+        last = amp + 1 + len;
+        from = last;
     }
 
+    result.push_str(&text[last..]);
     result
 }
 
+enum Decoded {
+    Char(char),
+    Str(&'static str),
+}
+
+/// Decodes the reference right after an `&`, returning the replacement and
+/// the number of bytes consumed (including the trailing `;`).
+fn decode_reference(rest: &str) -> Option<(Decoded, usize)> {
+    let window = &rest.as_bytes()[..rest.len().min(MAX_ENTITY_LEN + 1)];
+    let end = memchr(b';', window)?;
+    let body = &rest[..end];
+
+    let decoded = if let Some(number) = body.strip_prefix('#') {
+        let code = match number.strip_prefix(['x', 'X']) {
+            Some(hex) if !hex.is_empty() && hex.bytes().all(|b| b.is_ascii_hexdigit()) => {
+                u32::from_str_radix(hex, 16).ok()?
+            }
+            None if !number.is_empty() && number.bytes().all(|b| b.is_ascii_digit()) => {
+                number.parse().ok()?
+            }
+            _ => return None,
+        };
+        match char::from_u32(code) {
+            Some(c) if code != 0 => Decoded::Char(c),
+            _ => return None,
+        }
+    } else {
+        Decoded::Str(NAMED_ENTITIES.get(body)?)
+    };
+
+    Some((decoded, end + 1))
+}
+
 /// Strips HTML tags from a string.
 ///
 /// # Arguments
@@ -150,7 +304,22 @@ pub fn strip_newlines(text: Option<String>) -> String {
         _ => return String::new(),
     };
 
-    text.chars().filter(|c| *c != '\n' && *c != '\r').collect()
+    let bytes = text.as_bytes();
+    let mut newlines = memchr2_iter(b'\n', b'\r', bytes).peekable();
+    if newlines.peek().is_none() {
+        return text;
+    }
+
+    let mut result = String::with_capacity(text.len());
+    let mut last = 0;
+
+    for pos in newlines {
+        result.push_str(&text[last..pos]);
+        last = pos + 1;
+    }
+
+    result.push_str(&text[last..]);
+    result
 }
 
 /// Replaces newlines with HTML `<br>` tags.
@@ -176,7 +345,101 @@ pub fn newline_to_br(text: Option<String>) -> String {
         _ => return String::new(),
     };
 
-    text.replace("\r\n", "<br>").replace(['\n', '\r'], "<br>")
+    let bytes = text.as_bytes();
+    let mut newlines = memchr2_iter(b'\n', b'\r', bytes).peekable();
+    if newlines.peek().is_none() {
+        return text;
+    }
+
+    let mut result = String::with_capacity(text.len() + text.len() / 8);
+    let mut last = 0;
+
+    for pos in newlines {
+        // The `\n` of a `\r\n` pair was already consumed with its `\r`
+        if pos < last {
+            continue;
+        }
+
+        result.push_str(&text[last..pos]);
+        result.push_str("<br>");
+        last = if bytes[pos] == b'\r' && bytes.get(pos + 1) == Some(&b'\n') {
+            pos + 2
+        } else {
+            pos + 1
+        };
+    }
+
+    result.push_str(&text[last..]);
+    result
+}
+
+/// Formats plain text as HTML paragraphs.
+///
+/// The text is escaped, CRLF and CR line endings are normalized, blocks
+/// separated by one or more blank lines are wrapped in `<p>` tags and the
+/// remaining single newlines become `<br>`.
+///
+/// # Arguments
+///
+/// * `text` - The plain text
+/// * `class_name` - Optional CSS class for the `<p>` tags
+///
+/// # Returns
+///
+/// HTML paragraphs joined by newlines
+///
+/// # Examples
+///
+/// ```javascript
+/// simpleFormat("Hola\nmundo\r\n\r\nAdiós")
+/// // "<p>Hola<br>mundo</p>\n<p>Adiós</p>"
+///
+/// simpleFormat("Envío <24h>", "note")
+/// // '<p class="note">Envío &lt;24h&gt;</p>'
+/// ```
+#[napi]
+pub fn simple_format(text: Option<String>, class_name: Option<String>) -> String {
+    let text = match text {
+        Some(t) if !t.trim().is_empty() => t,
+        _ => return String::new(),
+    };
+
+    let mut open_tag = String::from("<p");
+    if let Some(class_name) = class_name.filter(|c| !c.is_empty()) {
+        open_tag.push_str(" class=\"");
+        escape_into(&class_name, &mut open_tag);
+        open_tag.push('"');
+    }
+    open_tag.push('>');
+
+    let normalized = text.replace("\r\n", "\n").replace('\r', "\n");
+    let mut result = String::with_capacity(normalized.len() + normalized.len() / 4);
+    let mut paragraph: Vec<&str> = Vec::new();
+
+    // A trailing blank line flushes the last paragraph
+    for line in normalized.split('\n').chain(std::iter::once("")) {
+        if !line.trim().is_empty() {
+            paragraph.push(line);
+            continue;
+        }
+        if paragraph.is_empty() {
+            continue;
+        }
+
+        if !result.is_empty() {
+            result.push('\n');
+        }
+        result.push_str(&open_tag);
+        for (i, line) in paragraph.drain(..).enumerate() {
+            if i > 0 {
+                result.push_str("<br>");
+            }
+            escape_into(line, &mut result);
+        }
+        result.push_str("</p>");
+    }
+
+    result
 }
 
 #[cfg(test)]
@@ -191,6 +454,42 @@ mod tests {
         );
         assert_eq!(escape(Some("Rock & Roll".to_string())), "Rock &amp; Roll");
         assert_eq!(escape(None), "");
+        assert_eq!(escape(Some("plain text".to_string())), "plain text");
+        assert_eq!(
+            escape(Some("\"a\" & 'b' <ñ>".to_string())),
+            "&quot;a&quot; &amp; &#x27;b&#x27; &lt;ñ&gt;"
+        );
+    }
+
+    #[test]
+    fn test_unescape_html() {
+        assert_eq!(
+            unescape_html(Some("Rock &amp; Roll &ntilde; &hellip;".to_string())),
+            "Rock & Roll ñ …"
+        );
+        assert_eq!(
+            unescape_html(Some("It&#8217;s &#X1F600; &#x27;".to_string())),
+            "It’s 😀 '"
+        );
+        assert_eq!(
+            unescape_html(Some("&bogus; &amp &#xZZ; &#0; &#xD800; & &;".to_string())),
+            "&bogus; &amp &#xZZ; &#0; &#xD800; & &;"
+        );
+        assert_eq!(unescape_html(Some("&&lt;".to_string())), "&<");
+        assert_eq!(unescape_html(None), "");
+    }
+
+    #[test]
+    fn test_unescape_html_inverts_escape() {
+        for input in [
+            "<script>alert('XSS')</script>",
+            "&amp; already escaped &lt;",
+            "Tom & \"Jerry\" ñ &#x27;",
+            "&",
+            "&&&;;;",
+        ] {
+            assert_eq!(unescape_html(Some(escape(Some(input.to_string())))), input);
+        }
     }
 
     #[test]
@@ -212,6 +511,7 @@ mod tests {
             strip_newlines(Some("Hello\nWorld\r\n!".to_string())),
             "HelloWorld!"
         );
+        assert_eq!(strip_newlines(Some("\n\r\n".to_string())), "");
         assert_eq!(strip_newlines(None), "");
     }
 
@@ -225,6 +525,31 @@ mod tests {
             newline_to_br(Some("Line1\r\nLine2\rLine3".to_string())),
             "Line1<br>Line2<br>Line3"
         );
+        assert_eq!(
+            newline_to_br(Some("\n\r\n\r\rEnd".to_string())),
+            "<br><br><br><br>End"
+        );
         assert_eq!(newline_to_br(None), "");
     }
+
+    #[test]
+    fn test_simple_format() {
+        assert_eq!(
+            simple_format(Some("Hola\nmundo\r\n\r\nAdiós".to_string()), None),
+            "<p>Hola<br>mundo</p>\n<p>Adiós</p>"
+        );
+        assert_eq!(
+            simple_format(Some("\r\rUno\r\r  \r\rDos\n\n".to_string()), None),
+            "<p>Uno</p>\n<p>Dos</p>"
+        );
+        assert_eq!(
+            simple_format(
+                Some("Envío <24h>".to_string()),
+                Some("note \"x\"".to_string())
+            ),
+            "<p class=\"note &quot;x&quot;\">Envío &lt;24h&gt;</p>"
+        );
+        assert_eq!(simple_format(Some(" \n ".to_string()), None), "");
+        assert_eq!(simple_format(None, None), "");
+    }
 }
@@ -0,0 +1,205 @@
+/*
+ * Copyright 2025 Fasttify LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Markdown rendering filter.
+
+use std::collections::HashMap;
+
+use napi::Result;
+use pulldown_cmark::{CowStr, Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+
+use super::sanitize::sanitize_with_policy;
+use super::text::handleize;
+
+/// Options accepted by the `markdown` filter.
+#[napi(object)]
+#[derive(Default)]
+pub struct MarkdownOptions {
+    /// Number of levels added to every heading (`# Title` becomes `<h3>`
+    /// with an offset of 2). Levels are capped at `h6`.
+    pub heading_offset: Option<u32>,
+    /// Sanitize policy applied to the rendered HTML (default: "rich").
+    pub policy: Option<String>,
+}
+
+/// Renders Markdown (CommonMark plus tables and strikethrough) to HTML.
+///
+/// Headings get an `id` built with `handleize` from their text, with a
+/// numeric suffix for repeated titles. The output always goes through
+/// `sanitize_html`, so raw HTML embedded in the Markdown is filtered too.
+///
+/// # Arguments
+///
+/// * `text` - The Markdown source
+/// * `options` - Heading offset and sanitize policy
+///
+/// # Returns
+///
+/// Sanitized HTML, or an error if the policy is unknown
+///
+/// # Examples
+///
+/// ```javascript
+/// markdown("# Envíos\n\nGratis desde **$100**")
+/// // '<h1 id="envios">Envíos</h1>\n<p>Gratis desde <strong>$100</strong></p>\n'
+///
+/// markdown("# Envíos", { headingOffset: 1 })
+/// // '<h2 id="envios">Envíos</h2>\n'
+/// ```
+#[napi]
+pub fn markdown(text: Option<String>, options: Option<MarkdownOptions>) -> Result<String> {
+    let options = options.unwrap_or_default();
+    let policy = options.policy.as_deref().unwrap_or("rich");
+
+    let text = match text {
+        Some(t) if !t.is_empty() => t,
+        _ => return sanitize_with_policy("", policy),
+    };
+
+    let html = render_markdown(&text, options.heading_offset.unwrap_or(0));
+    sanitize_with_policy(&html, policy)
+}
+
+/// Renders Markdown to unsanitized HTML, shifting headings by `offset`.
+fn render_markdown(text: &str, offset: u32) -> String {
+    let parser = Parser::new_ext(text, Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH);
+    let events = with_heading_ids(parser.collect(), offset);
+
+    let mut html = String::with_capacity(text.len() + text.len() / 2);
+    pulldown_cmark::html::push_html(&mut html, events.into_iter());
+    html
+}
+
+/// Assigns slug ids to headings and shifts their level.
+///
+/// The id depends on the heading text, which is only known once the closing
+/// event is reached, so the opening tag is patched after the fact.
+fn with_heading_ids(mut events: Vec<Event<'_>>, offset: u32) -> Vec<Event<'_>> {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    let mut open: Option<(usize, String)> = None;
+
+    for i in 0..events.len() {
+        match &mut events[i] {
+            Event::Start(Tag::Heading { level, .. }) => {
+                *level = shift_level(*level, offset);
+                open = Some((i, String::new()));
+            }
+            Event::Text(content) | Event::Code(content) => {
+                if let Some((_, title)) = open.as_mut() {
+                    title.push_str(content);
+                }
+            }
+            Event::End(TagEnd::Heading(level)) => {
+                *level = shift_level(*level, offset);
+                if let Some((start, title)) = open.take() {
+                    let id = unique_slug(&mut seen, &title);
+                    if let Event::Start(Tag::Heading { id: slot, .. }) = &mut events[start] {
+                        *slot = id.map(CowStr::from);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    events
+}
+
+/// Builds a unique slug for a heading title, or `None` if it has no
+/// sluggable characters.
+fn unique_slug(seen: &mut HashMap<String, usize>, title: &str) -> Option<String> {
+    let slug = handleize(Some(title.to_string()));
+    if slug.is_empty() {
+        return None;
+    }
+
+    let count = seen.entry(slug.clone()).or_insert(0);
+    *count += 1;
+    if *count == 1 {
+        Some(slug)
+    } else {
+        Some(format!("{}-{}", slug, *count - 1))
+    }
+}
+
+fn shift_level(level: HeadingLevel, offset: u32) -> HeadingLevel {
+    let shifted = (level as usize + offset as usize).min(6);
+    HeadingLevel::try_from(shifted).unwrap_or(HeadingLevel::H6)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(text: &str, offset: Option<u32>) -> String {
+        markdown(
+            Some(text.to_string()),
+            Some(MarkdownOptions {
+                heading_offset: offset,
+                policy: None,
+            }),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_markdown_basic() {
+        assert_eq!(
+            render("# Envíos\n\nGratis desde **$100**", None),
+            "<h1 id=\"envios\">Envíos</h1>\n<p>Gratis desde <strong>$100</strong></p>\n"
+        );
+        assert_eq!(render("~~antes~~", None), "<p><del>antes</del></p>\n");
+        assert_eq!(markdown(None, None).unwrap(), "");
+    }
+
+    #[test]
+    fn test_markdown_tables() {
+        assert_eq!(
+            render("| Talla | Pecho |\n|---|---|\n| M | 96 |", None),
+            "<table><thead><tr><th>Talla</th><th>Pecho</th></tr></thead><tbody>\n\
+             <tr><td>M</td><td>96</td></tr>\n</tbody></table>\n"
+        );
+    }
+
+    #[test]
+    fn test_markdown_heading_ids_and_offset() {
+        assert_eq!(
+            render("## Guía `rápida`\n\n## Guía rápida", Some(1)),
+            "<h3 id=\"guia-rapida\">Guía <code>rápida</code></h3>\n\
+             <h3 id=\"guia-rapida-1\">Guía rápida</h3>\n"
+        );
+        assert_eq!(render("##### Nota", Some(3)), "<h6 id=\"nota\">Nota</h6>\n");
+    }
+
+    #[test]
+    fn test_markdown_is_sanitized() {
+        assert_eq!(
+            render(
+                "Hola <script>alert(1)</script> [x](javascript:alert(1))",
+                None
+            ),
+            "<p>Hola  <a>x</a></p>\n"
+        );
+        assert!(markdown(
+            Some("x".to_string()),
+            Some(MarkdownOptions {
+                heading_offset: None,
+                policy: Some("nope".to_string()),
+            }),
+        )
+        .is_err());
+    }
+}
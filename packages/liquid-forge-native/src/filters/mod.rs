@@ -19,8 +19,16 @@
 //! This module contains high-performance implementations of common
 //! text processing operations used in Liquid templates.
 
+mod article;
+mod batch;
 mod html;
+mod markdown;
+mod sanitize;
 mod text;
 
+pub use article::*;
+pub use batch::*;
 pub use html::*;
+pub use markdown::*;
+pub use sanitize::*;
 pub use text::*;
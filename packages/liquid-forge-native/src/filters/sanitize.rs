@@ -0,0 +1,404 @@
+/*
+ * Copyright 2025 Fasttify LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Allowlist-based HTML sanitization for merchant-authored rich text.
+
+use std::borrow::Cow;
+use std::collections::HashSet;
+
+use ammonia::Builder;
+use napi::{Error, Result, Status};
+use once_cell::sync::Lazy;
+
+/// `rel` value added to links pointing outside the store.
+const EXTERNAL_LINK_REL: &str = "noopener noreferrer";
+
+/// URL schemes allowed in `href`/`src` attributes. Anything else
+/// (`javascript:`, `data:`, `vbscript:`...) is dropped.
+const URL_SCHEMES: &[&str] = &["http", "https", "mailto", "tel"];
+
+/// Iframe sources allowed by the `rich` policy (video embeds only).
+const IFRAME_SOURCES: &[&str] = &[
+    "https://www.youtube.com/embed/",
+    "https://www.youtube-nocookie.com/embed/",
+    "https://player.vimeo.com/video/",
+];
+
+const BASIC_TAGS: &[&str] = &[
+    "a",
+    "b",
+    "blockquote",
+    "br",
+    "em",
+    "i",
+    "li",
+    "ol",
+    "p",
+    "s",
+    "span",
+    "strong",
+    "u",
+    "ul",
+];
+
+const RICH_TAGS: &[&str] = &[
+    "abbr",
+    "caption",
+    "code",
+    "del",
+    "div",
+    "figcaption",
+    "figure",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+    "iframe",
+    "img",
+    "ins",
+    "mark",
+    "pre",
+    "small",
+    "sub",
+    "sup",
+    "table",
+    "tbody",
+    "td",
+    "tfoot",
+    "th",
+    "thead",
+    "tr",
+];
+
+const EMAIL_TAGS: &[&str] = &[
+    "center", "div", "font", "h1", "h2", "h3", "h4", "hr", "img", "table", "tbody", "td", "tfoot",
+    "th", "thead", "tr",
+];
+
+/// CSS properties kept in `style` attributes by the `email` policy.
+const EMAIL_STYLE_PROPERTIES: &[&str] = &[
+    "background-color",
+    "border",
+    "color",
+    "font-family",
+    "font-size",
+    "font-style",
+    "font-weight",
+    "line-height",
+    "margin",
+    "padding",
+    "text-align",
+    "text-decoration",
+    "vertical-align",
+    "width",
+];
+
+static BASIC: Lazy<Builder<'static>> = Lazy::new(|| {
+    let mut builder = base_builder(BASIC_TAGS);
+    builder.add_tag_attributes("a", ["href", "title"]);
+    builder
+});
+
+static RICH: Lazy<Builder<'static>> = Lazy::new(|| {
+    let mut builder = base_builder(BASIC_TAGS);
+    builder
+        .add_tags(RICH_TAGS)
+        .add_generic_attributes(["class", "id"])
+        .add_tag_attributes("a", ["href", "title", "target"])
+        .add_tag_attributes("abbr", ["title"])
+        .add_tag_attributes("img", ["src", "alt", "title", "width", "height", "loading"])
+        .add_tag_attributes("td", ["colspan", "rowspan"])
+        .add_tag_attributes("th", ["colspan", "rowspan", "scope"])
+        .add_tag_attributes(
+            "iframe",
+            [
+                "src",
+                "width",
+                "height",
+                "title",
+                "allow",
+                "allowfullscreen",
+                "frameborder",
+            ],
+        )
+        .rm_clean_content_tags(["iframe"])
+        .attribute_filter(filter_iframe_source);
+    builder
+});
+
+static EMAIL: Lazy<Builder<'static>> = Lazy::new(|| {
+    let mut builder = base_builder(BASIC_TAGS);
+    builder
+        .add_tags(EMAIL_TAGS)
+        .add_generic_attributes(["style", "align"])
+        .filter_style_properties(EMAIL_STYLE_PROPERTIES.iter().copied().collect())
+        .add_tag_attributes("a", ["href", "title", "target"])
+        .add_tag_attributes("font", ["color", "face", "size"])
+        .add_tag_attributes("img", ["src", "alt", "width", "height"])
+        .add_tag_attributes(
+            "table",
+            ["bgcolor", "border", "cellpadding", "cellspacing", "width"],
+        )
+        .add_tag_attributes("td", ["bgcolor", "colspan", "rowspan", "valign", "width"])
+        .add_tag_attributes("th", ["bgcolor", "colspan", "rowspan", "valign", "width"]);
+    builder
+});
+
+/// Builds the settings shared by every policy: allowed URL schemes,
+/// no automatic `rel` (added later for external links only) and removal
+/// of the content of scripts, styles and embeds.
+fn base_builder(tags: &[&'static str]) -> Builder<'static> {
+    let mut builder = Builder::empty();
+    builder
+        .tags(tags.iter().copied().collect())
+        .url_schemes(URL_SCHEMES.iter().copied().collect())
+        .clean_content_tags(HashSet::from([
+            "script", "style", "iframe", "object", "noscript", "template",
+        ]))
+        .link_rel(None)
+        .strip_comments(true);
+    builder
+}
+
+/// Drops iframe `src` values that are not a known video embed.
+fn filter_iframe_source<'u>(
+    element: &str,
+    attribute: &str,
+    value: &'u str,
+) -> Option<Cow<'u, str>> {
+    if element == "iframe" && attribute == "src" {
+        return IFRAME_SOURCES
+            .iter()
+            .any(|prefix| value.starts_with(prefix))
+            .then_some(Cow::Borrowed(value));
+    }
+    Some(Cow::Borrowed(value))
+}
+
+/// Looks up a named sanitization policy.
+fn policy_builder(policy: &str) -> Option<&'static Builder<'static>> {
+    match policy {
+        "basic" => Some(&BASIC),
+        "rich" => Some(&RICH),
+        "email" => Some(&EMAIL),
+        _ => None,
+    }
+}
+
+/// Sanitizes merchant-authored HTML against an allowlist policy.
+///
+/// Tags and attributes outside the policy are removed (their text is kept,
+/// except for `<script>`, `<style>` and embeds whose content is dropped),
+/// event handlers and `javascript:`/`data:` URLs never survive, and links
+/// to absolute URLs get `rel="noopener noreferrer"`.
+///
+/// Policies:
+/// - `basic` (default): inline formatting, paragraphs, lists and links
+/// - `rich`: `basic` plus headings, images, tables, code, classes and
+///   YouTube/Vimeo iframes
+/// - `email`: `basic` plus table layout and a safe subset of inline styles
+///
+/// # Arguments
+///
+/// * `html` - The HTML to sanitize
+/// * `policy` - Policy name (default: "basic")
+///
+/// # Returns
+///
+/// Sanitized HTML, or an error if the policy is unknown
+///
+/// # Examples
+///
+/// ```javascript
+/// sanitizeHtml('<p onclick="x()">Hi <script>alert(1)</script></p>')
+/// // "<p>Hi </p>"
+///
+/// sanitizeHtml('<a href="https://example.com">Ver</a>', 'rich')
+/// // '<a href="https://example.com" rel="noopener noreferrer">Ver</a>'
+/// ```
+#[napi]
+pub fn sanitize_html(html: Option<String>, policy: Option<String>) -> Result<String> {
+    sanitize_with_policy(
+        html.as_deref().unwrap_or_default(),
+        policy.as_deref().unwrap_or("basic"),
+    )
+}
+
+/// Sanitizes `html` with the named policy, failing on unknown names.
+pub(crate) fn sanitize_with_policy(html: &str, policy: &str) -> Result<String> {
+    let builder = policy_builder(policy).ok_or_else(|| {
+        Error::new(
+            Status::InvalidArg,
+            format!("Unknown sanitize policy '{}'", policy),
+        )
+    })?;
+
+    if html.is_empty() {
+        return Ok(String::new());
+    }
+
+    Ok(sanitize_with(builder, html))
+}
+
+fn sanitize_with(builder: &Builder<'static>, html: &str) -> String {
+    add_external_link_rel(builder.clean(html).to_string())
+}
+
+/// Adds `rel="noopener noreferrer"` to every `<a>` whose `href` is absolute.
+///
+/// Runs on serializer output only: tag names are lowercase, attribute values
+/// are double-quoted and literal `<` in text is already escaped, so a
+/// byte scan is enough to find anchor start tags.
+fn add_external_link_rel(html: String) -> String {
+    if !html.contains("<a ") {
+        return html;
+    }
+
+    let mut result = String::with_capacity(html.len() + 32);
+    let mut rest = html.as_str();
+
+    while let Some(start) = rest.find("<a ") {
+        let Some(len) = start_tag_len(&rest[start..]) else {
+            break;
+        };
+        let tag = &rest[start..start + len];
+
+        result.push_str(&rest[..start]);
+        if is_external_href(tag) {
+            result.push_str(&tag[..tag.len() - 1]);
+            result.push_str(" rel=\"");
+            result.push_str(EXTERNAL_LINK_REL);
+            result.push_str("\">");
+        } else {
+            result.push_str(tag);
+        }
+        rest = &rest[start + len..];
+    }
+
+    result.push_str(rest);
+    result
+}
+
+/// Length of the start tag at the beginning of `html`, including `>`.
+fn start_tag_len(html: &str) -> Option<usize> {
+    let mut in_quotes = false;
+    for (i, b) in html.bytes().enumerate() {
+        match b {
+            b'"' => in_quotes = !in_quotes,
+            b'>' if !in_quotes => return Some(i + 1),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn is_external_href(tag: &str) -> bool {
+    let Some(start) = tag.find(" href=\"") else {
+        return false;
+    };
+    let href = &tag[start + 7..];
+    let href = href.trim_start().to_ascii_lowercase();
+    href.starts_with("http://") || href.starts_with("https://") || href.starts_with("//")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sanitize(html: &str, policy: &str) -> String {
+        sanitize_html(Some(html.to_string()), Some(policy.to_string())).unwrap()
+    }
+
+    #[test]
+    fn test_sanitize_html_removes_scripts_and_handlers() {
+        assert_eq!(
+            sanitize(
+                "<p onclick=\"steal()\">Hola <script>alert(1)</script><b>mundo</b></p>",
+                "basic"
+            ),
+            "<p>Hola <b>mundo</b></p>"
+        );
+        assert_eq!(
+            sanitize("<a href=\"javascript:alert(1)\">x</a>", "rich"),
+            "<a>x</a>"
+        );
+        assert_eq!(
+            sanitize(
+                "<img src=\"data:image/svg+xml;base64,AAAA\" alt=\"x\">",
+                "rich"
+            ),
+            "<img alt=\"x\">"
+        );
+        assert_eq!(sanitize_html(None, None).unwrap(), "");
+    }
+
+    #[test]
+    fn test_sanitize_html_policies() {
+        assert_eq!(sanitize("<h2 class=\"t\">Title</h2>", "basic"), "Title");
+        assert_eq!(
+            sanitize("<h2 class=\"t\">Title</h2>", "rich"),
+            "<h2 class=\"t\">Title</h2>"
+        );
+        assert_eq!(
+            sanitize(
+                "<table><tr><td style=\"color: red; position: fixed\">x</td></tr></table>",
+                "email"
+            ),
+            "<table><tbody><tr><td style=\"color:red\">x</td></tr></tbody></table>"
+        );
+        assert!(sanitize_html(Some("x".to_string()), Some("nope".to_string())).is_err());
+    }
+
+    #[test]
+    fn test_sanitize_html_iframes() {
+        assert_eq!(
+            sanitize(
+                "<iframe src=\"https://www.youtube.com/embed/abc\" onload=\"x()\"></iframe>",
+                "rich"
+            ),
+            "<iframe src=\"https://www.youtube.com/embed/abc\"></iframe>"
+        );
+        assert_eq!(
+            sanitize("<iframe src=\"https://evil.example/\"></iframe>", "rich"),
+            "<iframe></iframe>"
+        );
+        assert_eq!(
+            sanitize(
+                "<iframe src=\"https://www.youtube.com/embed/abc\"></iframe>",
+                "basic"
+            ),
+            ""
+        );
+    }
+
+    #[test]
+    fn test_sanitize_html_external_links() {
+        assert_eq!(
+            sanitize(
+                "<a href=\"https://example.com\" rel=\"opener\">Ver</a>",
+                "basic"
+            ),
+            "<a href=\"https://example.com\" rel=\"noopener noreferrer\">Ver</a>"
+        );
+        assert_eq!(
+            sanitize("<a href=\"/collections/all\">Ver</a>", "basic"),
+            "<a href=\"/collections/all\">Ver</a>"
+        );
+    }
+}
@@ -19,13 +19,29 @@
 //! High-performance Rust implementations of Liquid template filters.
 //!
 //! This library provides native Rust implementations of text manipulation
-//! filters that are significantly faster than their JavaScript counterparts.
+//! filters that are significantly faster than their JavaScript counterparts,
+//! along with:
+//!
+//! - a Liquid tokenizer, parser and renderer for template tooling;
+//! - minifiers for templates and stylesheets;
+//! - a content-sniffing validator and a collector for theme assets;
+//! - a security scanner for theme uploads.
 
 #![deny(clippy::all)]
 
 #[macro_use]
 extern crate napi_derive;
 
+mod assets;
 mod filters;
+mod liquid;
+mod minify;
+mod security;
+#[cfg(test)]
+mod test_support;
 
+pub use assets::*;
 pub use filters::*;
+pub use liquid::*;
+pub use minify::*;
+pub use security::*;
@@ -0,0 +1,798 @@
+/*
+ * Copyright 2025 Fasttify LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Data requirements of templates.
+//!
+//! Which store data a page needs is read from the variables its templates
+//! look up in the parsed tree, so commented-out code is ignored and
+//! bracket access such as `collections['ofertas']` is found. Loop
+//! variables shadow global objects, and `assign` aliases are followed one
+//! level deep.
+
+use std::collections::{BTreeMap, HashMap};
+
+use super::ast::{Condition, Expr, Filtered, Node, PathSegment};
+use super::dependencies::{json_dependencies, template_dependencies, theme_path, ThemeFile};
+use super::parser::parse_template;
+
+/// Products loaded for `collections.<handle>.products` when no loop limit
+/// says otherwise.
+const DEFAULT_COLLECTION_PRODUCTS_LIMIT: u32 = 8;
+const DEFAULT_RELATED_PRODUCTS_LIMIT: u32 = 4;
+const DEFAULT_PAGES_LIMIT: u32 = 10;
+/// Collections loaded for the home page when the template does not use
+/// them itself.
+const INDEX_COLLECTIONS_LIMIT: u32 = 6;
+
+/// How to load one kind of data, in the shape of the TypeScript
+/// `DataLoadOptions`. Route values (`handle`, `id`, `nextToken`...) are
+/// added by the caller.
+#[napi(object)]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DataLoadOptions {
+    /// Items to load, from the largest `limit:` of the loops over them.
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+    /// Collection of `collection_products`.
+    pub collection_handle: Option<String>,
+    /// Handles of `specific_*` and `products_by_collection` requirements.
+    pub handles: Option<Vec<String>>,
+    /// Page size of the `{% paginate %}` over this data.
+    pub page_size: Option<u32>,
+}
+
+impl DataLoadOptions {
+    fn add_handle(&mut self, handle: &str) {
+        let handles = self.handles.get_or_insert_with(Vec::new);
+        if !handles.iter().any(|h| h == handle) {
+            handles.push(handle.to_string());
+        }
+    }
+
+    /// Overwrites the values `other` sets, as an object spread does, but
+    /// keeps the handles of both.
+    fn merge(&mut self, other: &DataLoadOptions) {
+        self.limit = other.limit.or(self.limit);
+        self.offset = other.offset.or(self.offset);
+        self.collection_handle = other
+            .collection_handle
+            .clone()
+            .or(self.collection_handle.take());
+        self.page_size = other.page_size.or(self.page_size);
+        for handle in other.handles.iter().flatten() {
+            self.add_handle(handle);
+        }
+    }
+}
+
+/// Data a template needs, in the shape of the TypeScript
+/// `TemplateAnalysis`.
+#[napi(object)]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TemplateAnalysis {
+    /// Options per `DataRequirement` ("products", "collection_products",
+    /// "specific_collection", "pagination"...).
+    pub required_data: BTreeMap<String, DataLoadOptions>,
+    pub has_pagination: bool,
+    /// Names of the sections rendered with `{% section %}` or listed in a
+    /// JSON template.
+    pub used_sections: Vec<String>,
+    /// Requirements found in the template itself, before those inferred
+    /// from its path, in source order.
+    pub liquid_objects: Vec<String>,
+    /// Files the template references, such as `snippets/card.liquid`.
+    pub dependencies: Vec<String>,
+}
+
+impl TemplateAnalysis {
+    fn merge(&mut self, other: TemplateAnalysis) {
+        for (requirement, options) in other.required_data {
+            self.required_data
+                .entry(requirement)
+                .or_default()
+                .merge(&options);
+        }
+        self.has_pagination |= other.has_pagination;
+        push_unique(&mut self.used_sections, other.used_sections);
+        push_unique(&mut self.liquid_objects, other.liquid_objects);
+        push_unique(&mut self.dependencies, other.dependencies);
+    }
+}
+
+fn push_unique(list: &mut Vec<String>, items: Vec<String>) {
+    for item in items {
+        if !list.contains(&item) {
+            list.push(item);
+        }
+    }
+}
+
+/// One step of a lookup, as far as it can be known without data.
+#[derive(Clone, Copy)]
+enum Segment<'a> {
+    Key(&'a str),
+    Position,
+    Dynamic,
+}
+
+fn segment(segment: &PathSegment) -> Segment<'_> {
+    match segment {
+        PathSegment::Key { name } => Segment::Key(name),
+        PathSegment::Index {
+            expr: Expr::String { value },
+        } => Segment::Key(value),
+        PathSegment::Index {
+            expr: Expr::Int { .. },
+        } => Segment::Position,
+        PathSegment::Index { .. } => Segment::Dynamic,
+    }
+}
+
+/// Whether a lookup reads the list itself rather than one of its items.
+fn is_list_access(segment: Option<Segment>) -> bool {
+    matches!(
+        segment,
+        None | Some(Segment::Position)
+            | Some(Segment::Dynamic)
+            | Some(Segment::Key("size" | "first" | "last"))
+    )
+}
+
+fn literal(expr: &Option<Expr>) -> Option<u32> {
+    match expr {
+        Some(Expr::Int { value }) => u32::try_from(*value).ok(),
+        _ => None,
+    }
+}
+
+/// Bounds of a lookup that loads a list.
+#[derive(Clone, Copy, Default)]
+struct Bounds {
+    limit: Option<u32>,
+    offset: Option<u32>,
+    page_size: Option<u32>,
+}
+
+#[derive(Default)]
+struct Analyzer {
+    analysis: TemplateAnalysis,
+    /// Names bound by `assign`, `capture`, `increment` and `decrement`,
+    /// with the variable they alias, if any.
+    assigned: HashMap<String, Option<Expr>>,
+    /// Variables of the loops being walked.
+    locals: Vec<String>,
+}
+
+impl Analyzer {
+    fn require(&mut self, requirement: &str) -> &mut DataLoadOptions {
+        if !self.analysis.required_data.contains_key(requirement) {
+            self.analysis.liquid_objects.push(requirement.to_string());
+        }
+        self.analysis
+            .required_data
+            .entry(requirement.to_string())
+            .or_default()
+    }
+
+    /// Records a lookup bounded by `bounds`.
+    fn bounded(&mut self, requirement: &str, bounds: Bounds) -> &mut DataLoadOptions {
+        let options = self.require(requirement);
+        options.limit = options.limit.max(bounds.limit);
+        options.offset = options.offset.or(bounds.offset);
+        options.page_size = options.page_size.max(bounds.page_size);
+        options
+    }
+
+    fn nodes(&mut self, nodes: &[Node]) {
+        for node in nodes {
+            self.node(node);
+        }
+    }
+
+    fn node(&mut self, node: &Node) {
+        match node {
+            Node::Output { value, .. } | Node::Echo { value, .. } => self.filtered(value),
+            Node::Assign { name, value, .. } => {
+                self.filtered(value);
+                let alias = matches!(value.expr, Expr::Variable { .. }).then(|| value.expr.clone());
+                self.assigned.insert(name.clone(), alias);
+            }
+            Node::Capture { name, body, .. } => {
+                self.nodes(body);
+                self.assigned.insert(name.clone(), None);
+            }
+            Node::Increment { name, .. } | Node::Decrement { name, .. } => {
+                self.assigned.insert(name.clone(), None);
+            }
+            Node::If {
+                branches,
+                else_body,
+                ..
+            }
+            | Node::Unless {
+                branches,
+                else_body,
+                ..
+            } => {
+                for branch in branches {
+                    self.condition(&branch.condition);
+                    self.nodes(&branch.body);
+                }
+                self.nodes(else_body.as_deref().unwrap_or_default());
+            }
+            Node::Case {
+                subject,
+                whens,
+                else_body,
+                ..
+            } => {
+                self.expr(subject, Bounds::default());
+                for when in whens {
+                    for value in &when.values {
+                        self.expr(value, Bounds::default());
+                    }
+                    self.nodes(&when.body);
+                }
+                self.nodes(else_body.as_deref().unwrap_or_default());
+            }
+            Node::For {
+                variable,
+                collection,
+                limit,
+                offset,
+                body,
+                else_body,
+                ..
+            } => {
+                self.iterate(variable, collection, limit, offset, body);
+                self.nodes(else_body.as_deref().unwrap_or_default());
+            }
+            Node::Tablerow {
+                variable,
+                collection,
+                limit,
+                offset,
+                body,
+                ..
+            } => self.iterate(variable, collection, limit, offset, body),
+            Node::Paginate {
+                collection,
+                per_page,
+                body,
+                ..
+            } => {
+                let bounds = Bounds {
+                    page_size: literal(per_page),
+                    ..Bounds::default()
+                };
+                self.analysis.has_pagination = true;
+                self.expr(collection, bounds);
+                self.bounded("pagination", bounds);
+                self.nodes(body);
+            }
+            Node::Render(partial) | Node::Include(partial) => {
+                if let Some(variable) = &partial.variable {
+                    self.expr(variable, Bounds::default());
+                }
+                for arg in &partial.args {
+                    self.filtered(&arg.value);
+                }
+            }
+            Node::Cycle { group, values, .. } => {
+                for value in group.iter().chain(values) {
+                    self.expr(value, Bounds::default());
+                }
+            }
+            Node::Form {
+                args, kwargs, body, ..
+            } => {
+                for arg in args.iter().chain(kwargs.iter().map(|arg| &arg.value)) {
+                    self.expr(arg, Bounds::default());
+                }
+                self.nodes(body);
+            }
+            _ => node.for_each_body(|body| self.nodes(body)),
+        }
+    }
+
+    /// A `for` or `tablerow` loop. Its variable shadows any global of the
+    /// same name inside the body.
+    fn iterate(
+        &mut self,
+        variable: &str,
+        collection: &Expr,
+        limit: &Option<Expr>,
+        offset: &Option<Expr>,
+        body: &[Node],
+    ) {
+        let bounds = Bounds {
+            limit: literal(limit),
+            offset: literal(offset),
+            page_size: None,
+        };
+        self.expr(collection, bounds);
+        self.locals.push(variable.to_string());
+        self.nodes(body);
+        self.locals.pop();
+    }
+
+    fn filtered(&mut self, filtered: &Filtered) {
+        self.expr(&filtered.expr, Bounds::default());
+        for filter in &filtered.filters {
+            for arg in &filter.args {
+                self.expr(arg, Bounds::default());
+            }
+            for arg in &filter.kwargs {
+                self.expr(&arg.value, Bounds::default());
+            }
+        }
+    }
+
+    fn condition(&mut self, condition: &Condition) {
+        match condition {
+            Condition::Test { expr } => self.expr(expr, Bounds::default()),
+            Condition::Compare { left, right, .. } => {
+                self.expr(left, Bounds::default());
+                self.expr(right, Bounds::default());
+            }
+            Condition::And { left, right } | Condition::Or { left, right } => {
+                self.condition(left);
+                self.condition(right);
+            }
+            Condition::Filtered { condition, .. } => self.condition(condition),
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr, bounds: Bounds) {
+        match expr {
+            Expr::Range { start, end } => {
+                self.expr(start, Bounds::default());
+                self.expr(end, Bounds::default());
+            }
+            Expr::Variable { name, path } => {
+                for step in path {
+                    if let PathSegment::Index { expr } = step {
+                        self.expr(expr, Bounds::default());
+                    }
+                }
+                if self.locals.contains(name) {
+                    return;
+                }
+                match self.assigned.get(name) {
+                    Some(Some(Expr::Variable {
+                        name: target,
+                        path: target_path,
+                    })) => {
+                        let (target, mut full_path) = (target.clone(), target_path.clone());
+                        full_path.extend(path.iter().cloned());
+                        self.lookup(&target, &full_path, bounds);
+                    }
+                    Some(_) => {}
+                    None => self.lookup(name, path, bounds),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Records what a lookup of a global object needs.
+    fn lookup(&mut self, name: &str, path: &[PathSegment], bounds: Bounds) {
+        let first = path.first().map(segment);
+        match name {
+            "products" => match first {
+                Some(Segment::Key(handle)) if !is_list_access(first) => {
+                    self.require("specific_product").add_handle(handle)
+                }
+                _ => {
+                    self.bounded("products", bounds);
+                }
+            },
+            "collections" => match first {
+                Some(Segment::Key(handle)) if !is_list_access(first) => {
+                    self.require("specific_collection").add_handle(handle);
+                    if let Some(Segment::Key("products")) = path.get(1).map(segment) {
+                        let options = self.bounded("collection_products", bounds);
+                        options
+                            .collection_handle
+                            .get_or_insert_with(|| handle.to_string());
+                        self.bounded("products_by_collection", bounds)
+                            .add_handle(handle);
+                    }
+                }
+                _ => {
+                    self.bounded("collections", bounds);
+                }
+            },
+            "pages" => match first {
+                Some(Segment::Key(handle)) if !is_list_access(first) => {
+                    self.require("specific_page").add_handle(handle)
+                }
+                _ => {
+                    self.bounded("pages", bounds);
+                }
+            },
+            "collection" => {
+                self.bounded("collection", bounds);
+            }
+            "related_products" => {
+                self.bounded("related_products", bounds);
+            }
+            "product" | "linklists" | "shop" | "page" | "blog" | "policies" | "checkout" => {
+                self.require(name);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Fills the limits the data loader expects when the template sets none.
+fn apply_default_limits(analysis: &mut TemplateAnalysis) {
+    let defaults = [
+        ("collection_products", DEFAULT_COLLECTION_PRODUCTS_LIMIT),
+        ("products_by_collection", DEFAULT_COLLECTION_PRODUCTS_LIMIT),
+        ("related_products", DEFAULT_RELATED_PRODUCTS_LIMIT),
+        ("pages", DEFAULT_PAGES_LIMIT),
+    ];
+    for (requirement, limit) in defaults {
+        if let Some(options) = analysis.required_data.get_mut(requirement) {
+            options.limit.get_or_insert(limit);
+        }
+    }
+}
+
+/// Adds what the page type implies, as `inferDataFromTemplatePath` does.
+/// Sections and snippets are shared by every page type, so nothing is
+/// inferred from their paths.
+fn infer_from_path(path: &str, analysis: &mut TemplateAnalysis) {
+    let shared = theme_path(path)
+        .is_some_and(|path| path.starts_with("sections/") || path.starts_with("snippets/"));
+    let required = &mut analysis.required_data;
+    let page = if shared {
+        None
+    } else if path.contains("index") {
+        Some((
+            "collections",
+            DataLoadOptions {
+                limit: Some(INDEX_COLLECTIONS_LIMIT),
+                ..DataLoadOptions::default()
+            },
+        ))
+    } else if path.contains("product") {
+        (!required.contains_key("products")).then(|| ("product", DataLoadOptions::default()))
+    } else if path.contains("collection") {
+        Some(("collection", DataLoadOptions::default()))
+    } else if path.contains("page") {
+        Some(("page", DataLoadOptions::default()))
+    } else if path.contains("checkout_confirmation") {
+        Some(("checkout_confirmation", DataLoadOptions::default()))
+    } else if path.contains("checkout") {
+        Some(("checkout", DataLoadOptions::default()))
+    } else {
+        None
+    };
+
+    // Navigation and store details are on every page
+    let always = [
+        ("linklists", DataLoadOptions::default()),
+        ("shop", DataLoadOptions::default()),
+    ];
+    for (requirement, options) in page.into_iter().chain(always) {
+        required.entry(requirement.to_string()).or_insert(options);
+    }
+}
+
+fn analyze(source: &str, path: &str) -> TemplateAnalysis {
+    let mut analysis = if path.ends_with(".json") {
+        let (sections, _) = json_dependencies(source);
+        let mut analysis = TemplateAnalysis::default();
+        for section in sections {
+            push_unique(
+                &mut analysis.dependencies,
+                vec![format!("sections/{}.liquid", section)],
+            );
+            push_unique(&mut analysis.used_sections, vec![section]);
+        }
+        analysis
+    } else {
+        let mut analyzer = Analyzer::default();
+        analyzer.nodes(&parse_template(source).nodes);
+        let mut analysis = analyzer.analysis;
+        for reference in template_dependencies(source).references {
+            if reference.kind == "section" {
+                push_unique(&mut analysis.used_sections, vec![reference.name]);
+            }
+            push_unique(&mut analysis.dependencies, vec![reference.path]);
+        }
+        analysis
+    };
+    apply_default_limits(&mut analysis);
+    infer_from_path(path, &mut analysis);
+    analysis
+}
+
+/// Finds the store data a template needs.
+///
+/// # Arguments
+///
+/// * `template` - The Liquid source, or a JSON template
+/// * `path` - The template path, such as `templates/product.liquid`, used
+///   to infer the data of its page type
+///
+/// # Returns
+///
+/// The requirements with their load options, whether the template
+/// paginates, and the sections and files it references
+///
+/// # Examples
+///
+/// ```javascript
+/// analyzeDataRequirements(
+///   "{% assign sale = collections['ofertas'] %}{% for p in sale.products limit: 4 %}{{ p.title }}{% endfor %}",
+///   "templates/index.liquid"
+/// )
+/// // {
+/// //   requiredData: {
+/// //     collection_products: { collectionHandle: "ofertas", limit: 4 },
+/// //     collections: { limit: 6 },
+/// //     linklists: {},
+/// //     products_by_collection: { handles: ["ofertas"], limit: 4 },
+/// //     shop: {},
+/// //     specific_collection: { handles: ["ofertas"] }
+/// //   },
+/// //   hasPagination: false,
+/// //   ...
+/// // }
+/// ```
+#[napi]
+pub fn analyze_data_requirements(template: String, path: String) -> TemplateAnalysis {
+    analyze(&template, &path)
+}
+
+/// Finds the store data a page needs across all the files that render it,
+/// such as its layout, its template and their sections.
+///
+/// # Arguments
+///
+/// * `files` - The files, each analyzed with its own path
+///
+/// # Returns
+///
+/// The analyses merged in order as `analyzeTemplateSet` does: load options
+/// set by later files win, handles and lists are joined without repeats
+///
+/// # Examples
+///
+/// ```javascript
+/// analyzeTemplateSet([
+///   { path: "layout/theme.liquid", content: layout },
+///   { path: "templates/collection.json", content: template },
+///   { path: "sections/main-collection.liquid", content: section },
+/// ])
+/// ```
+#[napi]
+pub fn analyze_template_set(files: Vec<ThemeFile>) -> TemplateAnalysis {
+    files
+        .iter()
+        .fold(TemplateAnalysis::default(), |mut merged, file| {
+            merged.merge(analyze(&file.content, &file.path));
+            merged
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(limit: Option<u32>, handles: &[&str]) -> DataLoadOptions {
+        DataLoadOptions {
+            limit,
+            handles: (!handles.is_empty()).then(|| handles.iter().map(|h| h.to_string()).collect()),
+            ..DataLoadOptions::default()
+        }
+    }
+
+    #[test]
+    fn test_requirements() {
+        let cases: [(&str, &[(&str, DataLoadOptions)]); 10] = [
+            ("{{ products | size }}", &[("products", options(None, &[]))]),
+            (
+                "{% for p in products limit: 3 %}{% endfor %}{% for p in products limit: 12 %}{% endfor %}",
+                &[("products", options(Some(12), &[]))],
+            ),
+            (
+                "{{ collections['ofertas'].title }}{{ collections.verano.url }}",
+                &[("specific_collection", options(None, &["ofertas", "verano"]))],
+            ),
+            (
+                "{% for c in collections limit: 5 offset: 2 %}{% endfor %}",
+                &[(
+                    "collections",
+                    DataLoadOptions {
+                        offset: Some(2),
+                        ..options(Some(5), &[])
+                    },
+                )],
+            ),
+            (
+                "{% if products['camisa'] %}{{ pages.about.content }}{% endif %}",
+                &[
+                    ("specific_page", options(None, &["about"])),
+                    ("specific_product", options(None, &["camisa"])),
+                ],
+            ),
+            (
+                "{% comment %}{{ products }}{% endcomment %}{% # for c in collections %}",
+                &[],
+            ),
+            (
+                "{% for product in collection.products %}{{ product.title }}{% endfor %}",
+                &[("collection", options(None, &[]))],
+            ),
+            (
+                "{% assign title = 'x' %}{% capture shop %}x{% endcapture %}{{ title }}{{ shop }}",
+                &[],
+            ),
+            (
+                "{% for p in related_products %}{% endfor %}{{ pages | size }}",
+                &[
+                    ("pages", options(Some(10), &[])),
+                    ("related_products", options(Some(4), &[])),
+                ],
+            ),
+            (
+                "{% liquid\n  echo blog.title\n  if checkout.email and policies\n  endif\n%}",
+                &[
+                    ("blog", options(None, &[])),
+                    ("checkout", options(None, &[])),
+                    ("policies", options(None, &[])),
+                ],
+            ),
+        ];
+        for (source, expected) in cases {
+            let mut analysis = analyze(source, "sections/test.liquid");
+            analysis.required_data.remove("linklists");
+            analysis.required_data.remove("shop");
+            let expected: BTreeMap<String, DataLoadOptions> = expected
+                .iter()
+                .map(|(name, options)| (name.to_string(), options.clone()))
+                .collect();
+            assert_eq!(analysis.required_data, expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn test_assign_aliases() {
+        let source = "{% assign sale = collections['ofertas'] %}\
+            {% assign picks = sale %}\
+            {% for p in sale.products limit: 4 %}{{ p.title }}{% endfor %}\
+            {{ picks.products }}";
+        let analysis = analyze(source, "sections/sale.liquid");
+        let required = &analysis.required_data;
+
+        assert_eq!(
+            required["collection_products"],
+            DataLoadOptions {
+                collection_handle: Some("ofertas".into()),
+                ..options(Some(4), &[])
+            }
+        );
+        assert_eq!(
+            required["products_by_collection"],
+            options(Some(4), &["ofertas"])
+        );
+        assert_eq!(required["specific_collection"], options(None, &["ofertas"]));
+        // `picks` aliases `sale`, which is not followed any further
+        assert!(!required.contains_key("collections"));
+        assert_eq!(
+            analysis.liquid_objects,
+            [
+                "specific_collection",
+                "collection_products",
+                "products_by_collection"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pagination() {
+        let source = "{% paginate collection.products by 24 %}\
+            {% for product in collection.products %}{% endfor %}{% endpaginate %}";
+        let analysis = analyze(source, "templates/collection.liquid");
+        assert!(analysis.has_pagination);
+        let page = DataLoadOptions {
+            page_size: Some(24),
+            ..DataLoadOptions::default()
+        };
+        assert_eq!(analysis.required_data["collection"], page);
+        assert_eq!(analysis.required_data["pagination"], page);
+    }
+
+    #[test]
+    fn test_path_inference() {
+        let cases = [
+            ("templates/index.liquid", "collections"),
+            ("templates/product.liquid", "product"),
+            ("templates/collection.json", "collection"),
+            ("templates/page.contact.liquid", "page"),
+            (
+                "templates/checkout_confirmation.liquid",
+                "checkout_confirmation",
+            ),
+            ("templates/checkout.liquid", "checkout"),
+        ];
+        for (path, requirement) in cases {
+            let analysis = analyze("", path);
+            let names: Vec<&str> = analysis.required_data.keys().map(String::as_str).collect();
+            let mut expected = vec![requirement, "linklists", "shop"];
+            expected.sort();
+            assert_eq!(names, expected, "{}", path);
+            assert!(analysis.liquid_objects.is_empty());
+        }
+
+        let analysis = analyze("", "templates/index.liquid");
+        assert_eq!(analysis.required_data["collections"].limit, Some(6));
+        let analysis = analyze("{{ products }}", "templates/product.liquid");
+        assert!(!analysis.required_data.contains_key("product"));
+        let analysis = analyze("", "sections/product-card.liquid");
+        assert!(!analysis.required_data.contains_key("product"));
+    }
+
+    #[test]
+    fn test_template_set() {
+        let files = vec![
+            ThemeFile {
+                path: "layout/theme.liquid".into(),
+                content: "{% section 'header' %}{{ content_for_layout }}".into(),
+            },
+            ThemeFile {
+                path: "templates/index.json".into(),
+                content: r#"{"sections": {"a": {"type": "featured"}, "b": {"type": "header"}}}"#
+                    .into(),
+            },
+            ThemeFile {
+                path: "sections/featured.liquid".into(),
+                content: "{% for p in collections.verano.products limit: 6 %}\
+                    {% render 'card', product: p %}{% endfor %}"
+                    .into(),
+            },
+            ThemeFile {
+                path: "sections/more.liquid".into(),
+                content: "{% for p in collections.ofertas.products limit: 2 %}{% endfor %}".into(),
+            },
+        ];
+        let analysis = analyze_template_set(files);
+
+        assert_eq!(analysis.used_sections, ["header", "featured"]);
+        assert_eq!(
+            analysis.dependencies,
+            [
+                "sections/header.liquid",
+                "sections/featured.liquid",
+                "snippets/card.liquid"
+            ]
+        );
+        assert_eq!(
+            analysis.required_data["products_by_collection"],
+            options(Some(2), &["verano", "ofertas"])
+        );
+        assert_eq!(
+            analysis.required_data["collection_products"].collection_handle,
+            Some("ofertas".into())
+        );
+        assert_eq!(analysis.required_data["collections"].limit, Some(6));
+        assert!(analysis.required_data.contains_key("linklists"));
+    }
+}
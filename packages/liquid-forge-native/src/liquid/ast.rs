@@ -0,0 +1,456 @@
+/*
+ * Copyright 2025 Fasttify LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Liquid abstract syntax tree.
+//!
+//! Every type serializes to plain JSON objects tagged with a `type` field,
+//! which is how the tree is handed to JavaScript.
+
+use serde::{Deserialize, Serialize};
+
+/// Byte range of a node in the template source (end exclusive).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Span {
+    pub start: u32,
+    pub end: u32,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self {
+            start: start as u32,
+            end: end as u32,
+        }
+    }
+}
+
+/// A value expression: literal, variable lookup or range.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Expr {
+    #[default]
+    Nil,
+    Empty,
+    Blank,
+    Bool {
+        value: bool,
+    },
+    Int {
+        value: i64,
+    },
+    Float {
+        value: f64,
+    },
+    String {
+        value: String,
+    },
+    /// `product.variants[0].title`
+    Variable {
+        name: String,
+        path: Vec<PathSegment>,
+    },
+    /// `(1..limit)`
+    Range {
+        start: Box<Expr>,
+        end: Box<Expr>,
+    },
+}
+
+impl Expr {
+    /// The string value of a string literal.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Expr::String { value } => Some(value),
+            _ => None,
+        }
+    }
+}
+
+/// One step of a variable lookup.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum PathSegment {
+    /// `.name`
+    Key { name: String },
+    /// `[expr]`
+    Index { expr: Expr },
+}
+
+/// A `name: value` argument.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Arg {
+    pub name: String,
+    pub value: Expr,
+}
+
+/// A filter application: `| name: arg, key: value`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Filter {
+    pub name: String,
+    pub args: Vec<Expr>,
+    pub kwargs: Vec<Arg>,
+    pub span: Span,
+}
+
+/// An expression followed by a filter chain.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Filtered {
+    pub expr: Expr,
+    pub filters: Vec<Filter>,
+}
+
+/// A `name: value | filters` argument of `render` and `include`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FilteredArg {
+    pub name: String,
+    pub value: Filtered,
+}
+
+/// Comparison operators.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CompareOp {
+    #[serde(rename = "==")]
+    Eq,
+    #[serde(rename = "!=")]
+    Ne,
+    #[serde(rename = "<")]
+    Lt,
+    #[serde(rename = ">")]
+    Gt,
+    #[serde(rename = "<=")]
+    Le,
+    #[serde(rename = ">=")]
+    Ge,
+    #[serde(rename = "contains")]
+    Contains,
+}
+
+/// An `if`/`unless`/`elsif` condition.
+///
+/// `and`/`or` are right-associative and share one precedence level, so
+/// `a or b and c` is `a or (b and c)` and `a and b or c` is `a and (b or c)`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Condition {
+    /// A bare value tested for truthiness.
+    Test { expr: Expr },
+    Compare {
+        left: Expr,
+        op: CompareOp,
+        right: Expr,
+    },
+    And {
+        left: Box<Condition>,
+        right: Box<Condition>,
+    },
+    Or {
+        left: Box<Condition>,
+        right: Box<Condition>,
+    },
+    /// Filters written after a condition. They apply to its boolean result,
+    /// as LiquidJS does, and the filtered value is tested for truthiness.
+    Filtered {
+        condition: Box<Condition>,
+        filters: Vec<Filter>,
+    },
+}
+
+/// One `if`/`elsif` branch.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Branch {
+    pub condition: Condition,
+    pub body: Vec<Node>,
+}
+
+/// One `when` clause of a `case`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct When {
+    pub values: Vec<Expr>,
+    pub body: Vec<Node>,
+}
+
+/// `render` and `include` arguments.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Partial {
+    /// Snippet name: a string literal, or a variable for dynamic includes.
+    pub template: Expr,
+    /// Value passed with `with` or iterated with `for`.
+    pub variable: Option<Expr>,
+    /// Name given with `as`.
+    pub alias: Option<String>,
+    /// Whether `variable` was passed with `for`.
+    pub for_loop: bool,
+    pub args: Vec<FilteredArg>,
+    pub span: Span,
+}
+
+/// A template node.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Node {
+    /// Literal text, already trimmed by whitespace control.
+    Text {
+        value: String,
+        span: Span,
+    },
+    /// `{{ expr | filters }}`
+    Output {
+        value: Filtered,
+        span: Span,
+    },
+    /// `{% echo expr | filters %}`
+    Echo {
+        value: Filtered,
+        span: Span,
+    },
+    Assign {
+        name: String,
+        value: Filtered,
+        span: Span,
+    },
+    Capture {
+        name: String,
+        body: Vec<Node>,
+        span: Span,
+    },
+    Increment {
+        name: String,
+        span: Span,
+    },
+    Decrement {
+        name: String,
+        span: Span,
+    },
+    #[serde(rename_all = "camelCase")]
+    If {
+        branches: Vec<Branch>,
+        else_body: Option<Vec<Node>>,
+        span: Span,
+    },
+    /// The first branch holds the negated condition; later ones are
+    /// `elsif` branches evaluated normally.
+    #[serde(rename_all = "camelCase")]
+    Unless {
+        branches: Vec<Branch>,
+        else_body: Option<Vec<Node>>,
+        span: Span,
+    },
+    #[serde(rename_all = "camelCase")]
+    Case {
+        subject: Expr,
+        whens: Vec<When>,
+        else_body: Option<Vec<Node>>,
+        span: Span,
+    },
+    #[serde(rename_all = "camelCase")]
+    For {
+        variable: String,
+        collection: Expr,
+        reversed: bool,
+        limit: Option<Expr>,
+        offset: Option<Expr>,
+        body: Vec<Node>,
+        else_body: Option<Vec<Node>>,
+        span: Span,
+    },
+    Tablerow {
+        variable: String,
+        collection: Expr,
+        cols: Option<Expr>,
+        limit: Option<Expr>,
+        offset: Option<Expr>,
+        body: Vec<Node>,
+        span: Span,
+    },
+    Cycle {
+        group: Option<Expr>,
+        values: Vec<Expr>,
+        span: Span,
+    },
+    Break {
+        span: Span,
+    },
+    Continue {
+        span: Span,
+    },
+    Render(Partial),
+    Include(Partial),
+    Section {
+        name: Expr,
+        span: Span,
+    },
+    Sections {
+        name: Expr,
+        span: Span,
+    },
+    /// `{% layout 'name' %}`; `name` is `None` for `{% layout none %}`.
+    Layout {
+        name: Option<Expr>,
+        span: Span,
+    },
+    #[serde(rename_all = "camelCase")]
+    Paginate {
+        collection: Expr,
+        /// `by` value; `None` when the page size comes from the context.
+        per_page: Option<Expr>,
+        body: Vec<Node>,
+        span: Span,
+    },
+    #[serde(rename_all = "camelCase")]
+    Form {
+        form_type: Expr,
+        args: Vec<Expr>,
+        kwargs: Vec<Arg>,
+        body: Vec<Node>,
+        span: Span,
+    },
+    /// `{% schema %}` with its JSON body exactly as written.
+    Schema {
+        body: String,
+        span: Span,
+    },
+    Style {
+        body: Vec<Node>,
+        span: Span,
+    },
+    Javascript {
+        body: Vec<Node>,
+        span: Span,
+    },
+    /// `{% liquid %}` with one tag per line.
+    Liquid {
+        body: Vec<Node>,
+        span: Span,
+    },
+    Raw {
+        body: String,
+        span: Span,
+    },
+    /// `{% comment %}` blocks and `{% # %}` inline comments.
+    Comment {
+        body: String,
+        span: Span,
+    },
+    /// Other known block tags (`stylesheet`, `script` and the `block` of
+    /// LiquidJS layouts) kept with their unparsed markup.
+    Block {
+        name: String,
+        markup: String,
+        body: Vec<Node>,
+        span: Span,
+    },
+    /// Tags the grammar does not know, kept as written.
+    Tag {
+        name: String,
+        markup: String,
+        span: Span,
+    },
+}
+
+impl Node {
+    pub fn span(&self) -> Span {
+        match self {
+            Node::Render(partial) | Node::Include(partial) => partial.span,
+            Node::Text { span, .. }
+            | Node::Output { span, .. }
+            | Node::Echo { span, .. }
+            | Node::Assign { span, .. }
+            | Node::Capture { span, .. }
+            | Node::Increment { span, .. }
+            | Node::Decrement { span, .. }
+            | Node::If { span, .. }
+            | Node::Unless { span, .. }
+            | Node::Case { span, .. }
+            | Node::For { span, .. }
+            | Node::Tablerow { span, .. }
+            | Node::Cycle { span, .. }
+            | Node::Break { span }
+            | Node::Continue { span }
+            | Node::Section { span, .. }
+            | Node::Sections { span, .. }
+            | Node::Layout { span, .. }
+            | Node::Paginate { span, .. }
+            | Node::Form { span, .. }
+            | Node::Schema { span, .. }
+            | Node::Style { span, .. }
+            | Node::Javascript { span, .. }
+            | Node::Liquid { span, .. }
+            | Node::Raw { span, .. }
+            | Node::Comment { span, .. }
+            | Node::Block { span, .. }
+            | Node::Tag { span, .. } => *span,
+        }
+    }
+
+    /// Calls `f` for every child node list, in source order.
+    pub fn for_each_body<'a>(&'a self, mut f: impl FnMut(&'a [Node])) {
+        match self {
+            Node::If {
+                branches,
+                else_body,
+                ..
+            }
+            | Node::Unless {
+                branches,
+                else_body,
+                ..
+            } => {
+                for branch in branches {
+                    f(&branch.body);
+                }
+                if let Some(body) = else_body {
+                    f(body);
+                }
+            }
+            Node::Case {
+                whens, else_body, ..
+            } => {
+                for when in whens {
+                    f(&when.body);
+                }
+                if let Some(body) = else_body {
+                    f(body);
+                }
+            }
+            Node::For {
+                body, else_body, ..
+            } => {
+                f(body);
+                if let Some(body) = else_body {
+                    f(body);
+                }
+            }
+            Node::Capture { body, .. }
+            | Node::Tablerow { body, .. }
+            | Node::Paginate { body, .. }
+            | Node::Form { body, .. }
+            | Node::Style { body, .. }
+            | Node::Javascript { body, .. }
+            | Node::Liquid { body, .. }
+            | Node::Block { body, .. } => f(body),
+            _ => {}
+        }
+    }
+}
+
+/// Calls `f` for every node in `nodes` and their descendants, depth first.
+pub fn walk<'a>(nodes: &'a [Node], f: &mut impl FnMut(&'a Node)) {
+    for node in nodes {
+        f(node);
+        node.for_each_body(|body| walk(body, f));
+    }
+}
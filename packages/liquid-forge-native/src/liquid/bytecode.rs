@@ -0,0 +1,323 @@
+/*
+ * Copyright 2025 Fasttify LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Precompiled templates.
+//!
+//! A compiled template is a binary blob that can be cached (S3, Redis...)
+//! and rendered without parsing again. Layout, little endian:
+//!
+//! | bytes | content                                        |
+//! |-------|------------------------------------------------|
+//! | 4     | magic `LQFB`                                   |
+//! | 2     | format version                                 |
+//! | 4     | FNV-1a hash of the AST definitions             |
+//! | 1     | length `n` of the crate version                |
+//! | n     | crate version that wrote the blob              |
+//! | 4     | body length                                    |
+//! | 4     | FNV-1a checksum of the body                    |
+//! | ...   | body: MessagePack of the source and the AST    |
+//!
+//! Blobs written by another format, AST or crate version are rejected, so
+//! an upgrade invalidates every cached blob instead of misreading it. The
+//! header is checked on the borrowed buffer before anything is decoded.
+
+use napi::bindgen_prelude::{Buffer, Function};
+use napi::{Error, Result, Status};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::ast::Node;
+use super::render::{render_js, RenderOptions, Template};
+
+const MAGIC: &[u8; 4] = b"LQFB";
+
+/// Bumped whenever the header layout changes.
+const FORMAT_VERSION: u16 = 2;
+
+/// Hash of the source defining the AST, so that any change to `ast::Node`
+/// and the types in it invalidates blobs even when the crate version stays
+/// the same. Edits that don't change the encoding invalidate them too,
+/// which only costs a recompile.
+const AST_HASH: u32 = fnv1a(include_bytes!("ast.rs"));
+
+/// Blobs are also tied to the crate version that wrote them, in case the
+/// meaning of an unchanged AST does.
+const CRATE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// The source is kept so render errors can still report lines and
+/// columns.
+#[derive(Serialize)]
+struct BodyRef<'a> {
+    source: &'a str,
+    nodes: &'a [Node],
+}
+
+#[derive(Deserialize)]
+struct Body {
+    source: String,
+    nodes: Vec<Node>,
+}
+
+/// Why a blob was rejected.
+#[derive(Debug, PartialEq, Eq)]
+enum BlobError {
+    NotCompiled,
+    Stale,
+    Corrupt,
+}
+
+impl BlobError {
+    fn into_error(self) -> Error {
+        let message = match self {
+            BlobError::NotCompiled => "Buffer is not a compiled Liquid template",
+            BlobError::Stale => {
+                "Compiled template was written by another version; compile it again"
+            }
+            BlobError::Corrupt => "Compiled template is truncated or corrupt",
+        };
+        Error::new(Status::InvalidArg, message.to_string())
+    }
+}
+
+/// Serializes a parsed template into a blob.
+fn encode(source: &str, nodes: &[Node]) -> Result<Vec<u8>> {
+    let body = rmp_serde::to_vec_named(&BodyRef { source, nodes })
+        .map_err(|e| Error::new(Status::GenericFailure, e.to_string()))?;
+
+    let mut blob = Vec::with_capacity(body.len() + 20 + CRATE_VERSION.len());
+    blob.extend_from_slice(MAGIC);
+    blob.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    blob.extend_from_slice(&AST_HASH.to_le_bytes());
+    blob.push(CRATE_VERSION.len() as u8);
+    blob.extend_from_slice(CRATE_VERSION.as_bytes());
+    blob.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    blob.extend_from_slice(&fnv1a(&body).to_le_bytes());
+    blob.extend_from_slice(&body);
+    Ok(blob)
+}
+
+/// Validates the header and returns the body, without copying.
+fn body(blob: &[u8]) -> std::result::Result<&[u8], BlobError> {
+    let rest = blob.strip_prefix(MAGIC).ok_or(BlobError::NotCompiled)?;
+    let (version, rest) = split(rest, 2).ok_or(BlobError::Corrupt)?;
+    if u16::from_le_bytes([version[0], version[1]]) != FORMAT_VERSION {
+        return Err(BlobError::Stale);
+    }
+    let (ast_hash, rest) = split(rest, 4).ok_or(BlobError::Corrupt)?;
+    if u32::from_le_bytes(ast_hash.try_into().unwrap()) != AST_HASH {
+        return Err(BlobError::Stale);
+    }
+
+    let (&len, rest) = rest.split_first().ok_or(BlobError::Corrupt)?;
+    let (crate_version, rest) = split(rest, len as usize).ok_or(BlobError::Corrupt)?;
+    if crate_version != CRATE_VERSION.as_bytes() {
+        return Err(BlobError::Stale);
+    }
+
+    let (len, rest) = split(rest, 4).ok_or(BlobError::Corrupt)?;
+    let (checksum, body) = split(rest, 4).ok_or(BlobError::Corrupt)?;
+    let len = u32::from_le_bytes(len.try_into().unwrap()) as usize;
+    let checksum = u32::from_le_bytes(checksum.try_into().unwrap());
+    if body.len() != len || fnv1a(body) != checksum {
+        return Err(BlobError::Corrupt);
+    }
+    Ok(body)
+}
+
+fn split(bytes: &[u8], at: usize) -> Option<(&[u8], &[u8])> {
+    (bytes.len() >= at).then(|| bytes.split_at(at))
+}
+
+/// Decodes a blob back into a renderable template.
+fn decode(blob: &[u8]) -> Result<Template> {
+    let body = body(blob).map_err(BlobError::into_error)?;
+    let Body { source, nodes } =
+        rmp_serde::from_slice(body).map_err(|_| BlobError::Corrupt.into_error())?;
+    Ok(Template::from_parts(source, nodes))
+}
+
+/// 32-bit FNV-1a, enough to catch truncated or damaged cache entries.
+/// `const` so the AST hash is computed at compile time.
+const fn fnv1a(bytes: &[u8]) -> u32 {
+    let mut hash: u32 = 0x811c_9dc5;
+    let mut i = 0;
+    while i < bytes.len() {
+        hash = (hash ^ bytes[i] as u32).wrapping_mul(0x0100_0193);
+        i += 1;
+    }
+    hash
+}
+
+/// Compiles a Liquid template into a cacheable binary blob.
+///
+/// # Arguments
+///
+/// * `template` - The Liquid source
+///
+/// # Returns
+///
+/// A Buffer for `renderCompiled`, or the first syntax error with its line
+/// and column
+///
+/// # Examples
+///
+/// ```javascript
+/// const blob = compileTemplate(source)
+/// await s3.putObject({ Key: `${key}.lqfb`, Body: blob })
+/// ```
+#[napi]
+pub fn compile_template(template: String) -> Result<Buffer> {
+    let template = Template::parse(None, template)?;
+    encode(&template.source, &template.nodes).map(Buffer::from)
+}
+
+/// Checks whether a blob was compiled by this version of the crate and is
+/// intact, without decoding it.
+///
+/// # Examples
+///
+/// ```javascript
+/// const blob = await cache.get(key)
+/// const compiled = blob && isCompiledTemplateCurrent(blob) ? blob : compileTemplate(source)
+/// ```
+#[napi]
+pub fn is_compiled_template_current(blob: Buffer) -> bool {
+    body(&blob).is_ok()
+}
+
+/// Renders a template compiled with `compileTemplate`.
+///
+/// Takes the same context, options and host as `render`. Fails if the
+/// blob is not a compiled template, was written by another crate version
+/// or is damaged.
+///
+/// # Examples
+///
+/// ```javascript
+/// renderCompiled(blob, JSON.stringify(context), { hostFilters: ["t"] }, host)
+/// ```
+#[napi]
+pub fn render_compiled(
+    blob: Buffer,
+    context_json: Option<String>,
+    options: Option<RenderOptions>,
+    host: Option<Function<'_, Value, Option<Value>>>,
+) -> Result<String> {
+    render_js(decode(&blob)?, context_json, options, host)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+
+    use serde_json::Map;
+
+    use crate::liquid::render::{render_template, Renderer};
+    use crate::test_support::{example_theme_files, example_themes};
+
+    const SOURCE: &str = "{%- for p in products -%}\n\
+        {% if p.price > 10 %}{{ p.title | upcase }}{% else %}{{ p.title }}{% endif %} \
+        {%- endfor %}{% comment %}x{% endcomment %}{{ 2.5 | times: 2 }}";
+
+    fn compile(source: &str) -> Vec<u8> {
+        let template = Template::parse(None, source.to_string()).unwrap();
+        encode(&template.source, &template.nodes).unwrap()
+    }
+
+    fn render_blob(blob: &[u8]) -> Result<String> {
+        let context = serde_json::json!({"products": [{"title": "a", "price": 20}, {"title": "b", "price": 5}]});
+        let Value::Object(context) = context else {
+            unreachable!()
+        };
+        let template = Rc::new(decode(blob)?);
+        Renderer::new(context, RenderOptions::default(), None).render(template)
+    }
+
+    #[test]
+    fn test_round_trip_renders_like_source() {
+        let blob = compile(SOURCE);
+        assert_eq!(&blob[..4], MAGIC);
+        let context = serde_json::json!({"products": [{"title": "a", "price": 20}, {"title": "b", "price": 5}]});
+        let Value::Object(context) = context else {
+            unreachable!()
+        };
+        let expected =
+            render_template(SOURCE.to_string(), context, RenderOptions::default(), None).unwrap();
+        assert_eq!(render_blob(&blob).unwrap(), expected);
+        assert_eq!(expected, "Ab5");
+    }
+
+    #[test]
+    fn test_rejects_stale_and_damaged_blobs() {
+        let blob = compile("{{ x }}");
+        assert!(body(&blob).is_ok());
+
+        let mut stale = blob.clone();
+        stale[4] = stale[4].wrapping_add(1);
+        assert_eq!(body(&stale), Err(BlobError::Stale));
+
+        let mut other_ast = blob.clone();
+        other_ast[6] ^= 0xff;
+        assert_eq!(body(&other_ast), Err(BlobError::Stale));
+
+        let mut other_crate = blob.clone();
+        other_crate[11] = b'9';
+        assert_eq!(body(&other_crate), Err(BlobError::Stale));
+
+        let mut flipped = blob.clone();
+        *flipped.last_mut().unwrap() ^= 0xff;
+        assert_eq!(body(&flipped), Err(BlobError::Corrupt));
+
+        assert_eq!(body(&blob[..blob.len() - 1]), Err(BlobError::Corrupt));
+        assert_eq!(body(&blob[..6]), Err(BlobError::Corrupt));
+        assert_eq!(body(b"{{ x }}"), Err(BlobError::NotCompiled));
+        assert!(render_blob(b"").is_err());
+    }
+
+    #[test]
+    fn test_compiled_errors_keep_positions() {
+        let blob = compile("ok\n{{ x | divided_by }}{{ y }}");
+        let Body { source, .. } = rmp_serde::from_slice(body(&blob).unwrap()).unwrap();
+        assert!(source.starts_with("ok\n"));
+
+        let blob = compile("a\n  {{ missing }}");
+        let options = RenderOptions {
+            strict_variables: Some(true),
+            ..Default::default()
+        };
+        let template = Rc::new(decode(&blob).unwrap());
+        let error = Renderer::new(Map::new(), options, None)
+            .render(template)
+            .unwrap_err();
+        assert_eq!(
+            error.reason,
+            "Undefined variable 'missing' (line 2, column 3)"
+        );
+    }
+
+    #[test]
+    fn test_compiles_example_themes() {
+        let sections = example_themes().join("shopify/theme/sections");
+        let files = example_theme_files("liquid");
+        for path in files.iter().filter(|path| path.starts_with(&sections)) {
+            let source = std::fs::read_to_string(path).unwrap();
+            let template = Template::parse(None, source).unwrap();
+            let blob = encode(&template.source, &template.nodes).unwrap();
+            assert_eq!(decode(&blob).unwrap().nodes, template.nodes);
+        }
+    }
+}
@@ -0,0 +1,315 @@
+/*
+ * Copyright 2025 Fasttify LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Condition semantics.
+//!
+//! `if`, `unless`, `elsif` and `when` follow Shopify's Liquid rather than
+//! LiquidJS, which differs on several edge cases:
+//!
+//! - only `nil` and `false` are falsy;
+//! - `==` compares numbers by value, strings and numbers are never equal,
+//!   and the `empty`/`blank` literals test the other side;
+//! - `<`, `>`, `<=` and `>=` compare numbers with numbers and strings with
+//!   strings. A string against a number is an error; any other pair
+//!   (`nil`, booleans, arrays, objects, ranges) is simply false;
+//! - `contains` needs both sides truthy and checks substrings, array
+//!   elements, object keys or range bounds;
+//! - ranges are compared by their bounds and never expanded.
+
+use std::rc::Rc;
+
+use napi::{Error, Result, Status};
+use serde_json::Value;
+
+use super::ast::{CompareOp, Expr};
+use super::expr::parse_condition;
+use super::render::{parse_context, RenderOptions, Renderer, Template};
+use super::span::LineIndex;
+use super::value::{equals, is_blank, is_empty, is_truthy, to_text};
+
+/// One evaluated side of a comparison.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operand {
+    Value(Value),
+    /// `(start..end)`, with its bounds already converted to integers.
+    Range(i64, i64),
+    /// The `empty` literal.
+    Empty,
+    /// The `blank` literal.
+    Blank,
+}
+
+impl Operand {
+    /// The operand for an expression whose value is already known.
+    /// `empty`, `blank` and ranges keep their literal meaning.
+    pub fn literal(expr: &Expr, value: Value) -> Self {
+        match expr {
+            Expr::Empty => Operand::Empty,
+            Expr::Blank => Operand::Blank,
+            _ => Operand::Value(value),
+        }
+    }
+
+    fn is_truthy(&self) -> bool {
+        match self {
+            Operand::Value(value) => is_truthy(value),
+            _ => true,
+        }
+    }
+}
+
+/// `==` as `case`/`when` and conditions use it.
+pub fn operands_equal(left: &Operand, right: &Operand) -> bool {
+    match (left, right) {
+        (Operand::Empty | Operand::Blank, Operand::Empty | Operand::Blank) => false,
+        (Operand::Empty, Operand::Value(value)) | (Operand::Value(value), Operand::Empty) => {
+            is_empty(value)
+        }
+        (Operand::Blank, Operand::Value(value)) | (Operand::Value(value), Operand::Blank) => {
+            is_blank(value)
+        }
+        (Operand::Range(a, b), Operand::Range(c, d)) => a == c && b == d,
+        (Operand::Value(a), Operand::Value(b)) => equals(a, b),
+        _ => false,
+    }
+}
+
+/// Applies a comparison operator.
+///
+/// # Returns
+///
+/// The result, or the message Shopify raises for a string compared with a
+/// number
+pub fn compare_operands(
+    left: &Operand,
+    op: CompareOp,
+    right: &Operand,
+) -> std::result::Result<bool, String> {
+    let ordering = match op {
+        CompareOp::Eq => return Ok(operands_equal(left, right)),
+        CompareOp::Ne => return Ok(!operands_equal(left, right)),
+        CompareOp::Contains => return Ok(contains(left, right)),
+        _ => match (left, right) {
+            (Operand::Value(Value::Number(a)), Operand::Value(Value::Number(b))) => {
+                a.as_f64().partial_cmp(&b.as_f64())
+            }
+            (Operand::Value(Value::String(a)), Operand::Value(Value::String(b))) => Some(a.cmp(b)),
+            (Operand::Value(Value::String(_)), Operand::Value(Value::Number(n))) => {
+                return Err(format!("comparison of String with {} failed", n));
+            }
+            (Operand::Value(Value::Number(n)), Operand::Value(Value::String(_))) => {
+                let class = if n.is_f64() { "Float" } else { "Integer" };
+                return Err(format!("comparison of {} with String failed", class));
+            }
+            _ => None,
+        },
+    };
+
+    Ok(ordering.is_some_and(|ordering| match op {
+        CompareOp::Lt => ordering.is_lt(),
+        CompareOp::Gt => ordering.is_gt(),
+        CompareOp::Le => ordering.is_le(),
+        _ => ordering.is_ge(),
+    }))
+}
+
+fn contains(left: &Operand, right: &Operand) -> bool {
+    if !left.is_truthy() || !right.is_truthy() {
+        return false;
+    }
+    match (left, right) {
+        (Operand::Range(start, end), Operand::Value(Value::Number(n))) => n
+            .as_f64()
+            .is_some_and(|n| *start as f64 <= n && n <= *end as f64),
+        (Operand::Value(Value::String(s)), Operand::Value(needle)) => {
+            s.contains(to_text(needle).as_ref())
+        }
+        (Operand::Value(Value::String(s)), Operand::Range(start, end)) => {
+            s.contains(&format!("{}..{}", start, end))
+        }
+        (Operand::Value(Value::Array(items)), Operand::Value(needle)) => {
+            items.iter().any(|item| equals(item, needle))
+        }
+        (Operand::Value(Value::Object(map)), Operand::Value(Value::String(key))) => {
+            map.contains_key(key)
+        }
+        _ => false,
+    }
+}
+
+/// Evaluates a Liquid condition against a context, with Shopify's
+/// truthiness and comparison rules.
+///
+/// # Arguments
+///
+/// * `expr` - The condition, as written after `if`
+/// * `context_json` - The variables, as a JSON object
+///
+/// # Returns
+///
+/// Whether the condition holds, or an error for invalid syntax or a
+/// string compared with a number
+///
+/// # Examples
+///
+/// ```javascript
+/// evaluateCondition("product.tags contains 'sale' and product.price > 10", JSON.stringify({ product }))
+/// // true
+/// ```
+#[napi]
+pub fn evaluate_condition(expr: String, context_json: Option<String>) -> Result<bool> {
+    let context = parse_context(context_json.as_deref())?;
+    let condition = parse_condition(&expr, 0).map_err(|error| {
+        let (line, column) = LineIndex::new(&expr).position(error.start);
+        Error::new(
+            Status::InvalidArg,
+            format!("{} (line {}, column {})", error.message, line, column),
+        )
+    })?;
+    let template = Rc::new(Template::from_parts(expr, Vec::new()));
+    Renderer::new(context, RenderOptions::default(), None).evaluate(template, &condition)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn evaluate(expr: &str) -> Result<bool> {
+        let context = json!({
+            "zero": 0,
+            "one": 1,
+            "half": 0.5,
+            "text": "10",
+            "title": "Camisa azul",
+            "empty_string": "",
+            "spaces": "  ",
+            "tags": ["sale", "new", 1],
+            "none": [],
+            "product": {"title": "x", "price": 10.0, "tags": ["a"]},
+            "object": {},
+            "yes": true,
+            "no": false,
+            "nothing": null,
+        });
+        evaluate_condition(expr.to_string(), Some(context.to_string()))
+    }
+
+    #[test]
+    fn test_condition_semantics() {
+        let cases = [
+            // Truthiness: only nil and false are falsy
+            ("zero", true),
+            ("empty_string", true),
+            ("none", true),
+            ("object", true),
+            ("no", false),
+            ("nothing", false),
+            ("undefined", false),
+            ("product.missing.deeper", false),
+            ("empty", true),
+            // Equality
+            ("one == 1.0", true),
+            ("product.price == 10", true),
+            ("text == 10", false),
+            ("one == '1'", false),
+            ("one != '1'", true),
+            ("one <> 2", true),
+            ("yes == true", true),
+            ("nothing == nil", true),
+            ("undefined == nil", true),
+            ("undefined != nil", false),
+            ("zero == nil", false),
+            ("tags == tags", true),
+            ("product == product", true),
+            ("(1..3) == (1..3)", true),
+            ("(1..3) == (1..4)", false),
+            // empty and blank
+            ("empty_string == empty", true),
+            ("empty == none", true),
+            ("object == empty", true),
+            ("spaces == empty", false),
+            ("spaces == blank", true),
+            ("nothing == blank", true),
+            ("no == blank", true),
+            ("nothing == empty", false),
+            ("zero == blank", false),
+            ("tags != empty", true),
+            ("empty == empty", false),
+            // Ordering
+            ("one < 2", true),
+            ("half < one", true),
+            ("one >= 1.0", true),
+            ("'b' > 'a'", true),
+            ("'B' < 'a'", true),
+            ("nothing < 1", false),
+            ("nothing > 1", false),
+            ("undefined >= 0", false),
+            ("yes > no", false),
+            ("tags > none", false),
+            ("product > 1", false),
+            ("(1..3) < 4", false),
+            // contains
+            ("title contains 'azul'", true),
+            ("title contains 'Azul'", false),
+            ("text contains 1", true),
+            ("tags contains 'sale'", true),
+            ("tags contains 1.0", true),
+            ("tags contains 'sal'", false),
+            ("product contains 'title'", true),
+            ("product contains 'x'", false),
+            ("title contains nothing", false),
+            ("nothing contains 'a'", false),
+            ("one contains 1", false),
+            ("(1..5) contains 3", true),
+            ("(1..5) contains 2.5", true),
+            ("(1..5) contains 6", false),
+            ("(1..one) contains 1", true),
+            ("(1..100000000) contains 99999999", true),
+            // and/or are right-associative with equal precedence
+            ("yes or no and no", true),
+            ("no and no or yes", false),
+            ("yes and no or yes", true),
+            ("no or yes and yes", true),
+            ("nothing or zero", true),
+        ];
+        for (expr, expected) in cases {
+            assert_eq!(evaluate(expr).unwrap(), expected, "{}", expr);
+        }
+    }
+
+    #[test]
+    fn test_condition_errors() {
+        let error = evaluate("text > 5").unwrap_err();
+        assert_eq!(
+            error.reason,
+            "comparison of String with 5 failed (line 1, column 1)"
+        );
+        let error = evaluate("half < '1'").unwrap_err();
+        assert!(error
+            .reason
+            .starts_with("comparison of Float with String failed"));
+
+        let error = evaluate("one ==").unwrap_err();
+        assert!(
+            error.reason.ends_with("(line 1, column 7)"),
+            "{}",
+            error.reason
+        );
+        assert!(evaluate_condition("x".into(), Some("[]".into())).is_err());
+        assert!(evaluate_condition("x".into(), None).is_ok());
+    }
+}
@@ -0,0 +1,629 @@
+/*
+ * Copyright 2025 Fasttify LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Template dependencies.
+//!
+//! References are read from the parsed tree, so every form of `render`,
+//! `include`, `section`, `sections` and `layout` is found wherever it
+//! appears, including inside `{% liquid %}` tags. JSON templates and
+//! section groups reference sections through their `type` keys.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use serde_json::Value;
+
+use super::ast::{walk, Expr, Node, Span};
+use super::parser::parse_template;
+use super::span::{LineIndex, SourceSpan};
+
+/// Layout every template uses unless it names another one.
+const DEFAULT_LAYOUT: &str = "theme";
+
+/// A theme file.
+#[napi(object)]
+#[derive(Debug, Clone)]
+pub struct ThemeFile {
+    /// Path inside the theme, such as `snippets/card.liquid`. Anything
+    /// before the theme directory (`layout/`, `templates/`, `sections/`,
+    /// `snippets/`...) is ignored.
+    pub path: String,
+    pub content: String,
+}
+
+/// A reference to a snippet, section, section group or layout by name.
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct DependencyReference {
+    /// "snippet", "section", "section_group" or "layout".
+    pub kind: String,
+    /// Tag that makes the reference: "render", "include", "section",
+    /// "sections" or "layout".
+    pub tag: String,
+    pub name: String,
+    /// File the reference resolves to, such as `snippets/card.liquid`.
+    pub path: String,
+    pub span: SourceSpan,
+}
+
+/// A reference whose name is only known at render time, such as
+/// `{% include block.settings.snippet %}`.
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct DynamicReference {
+    pub kind: String,
+    pub tag: String,
+    /// The tag as written.
+    pub source: String,
+    pub span: SourceSpan,
+}
+
+/// Dependencies of one template.
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemplateDependencies {
+    /// Static references, in source order.
+    pub references: Vec<DependencyReference>,
+    pub dynamic: Vec<DynamicReference>,
+    /// Whether the template opts out of its layout with `{% layout none %}`.
+    pub layout_none: bool,
+}
+
+#[derive(Clone, Copy)]
+enum Kind {
+    Snippet,
+    Section,
+    SectionGroup,
+    Layout,
+}
+
+impl Kind {
+    fn as_str(self) -> &'static str {
+        match self {
+            Kind::Snippet => "snippet",
+            Kind::Section => "section",
+            Kind::SectionGroup => "section_group",
+            Kind::Layout => "layout",
+        }
+    }
+
+    fn path(self, name: &str) -> String {
+        match self {
+            Kind::Snippet => format!("snippets/{}.liquid", name),
+            Kind::Section => format!("sections/{}.liquid", name),
+            Kind::SectionGroup => format!("sections/{}.json", name),
+            Kind::Layout => format!("layout/{}.liquid", name),
+        }
+    }
+}
+
+/// Finds the references of a Liquid template.
+pub(crate) fn template_dependencies(source: &str) -> TemplateDependencies {
+    let index = LineIndex::new(source);
+    let mut dependencies = TemplateDependencies {
+        references: Vec::new(),
+        dynamic: Vec::new(),
+        layout_none: false,
+    };
+
+    let mut add = |kind: Kind, tag: &str, name: &Expr, span: Span| match name {
+        Expr::String { value } => dependencies.references.push(DependencyReference {
+            kind: kind.as_str().to_string(),
+            tag: tag.to_string(),
+            name: value.clone(),
+            path: kind.path(value),
+            span: index.span(span.start as usize, span.end as usize),
+        }),
+        _ => dependencies.dynamic.push(DynamicReference {
+            kind: kind.as_str().to_string(),
+            tag: tag.to_string(),
+            source: source[span.start as usize..span.end as usize].to_string(),
+            span: index.span(span.start as usize, span.end as usize),
+        }),
+    };
+
+    let parsed = parse_template(source);
+    let mut layout_none = false;
+    walk(&parsed.nodes, &mut |node| match node {
+        Node::Render(partial) => add(Kind::Snippet, "render", &partial.template, partial.span),
+        Node::Include(partial) => add(Kind::Snippet, "include", &partial.template, partial.span),
+        Node::Section { name, span } => add(Kind::Section, "section", name, *span),
+        Node::Sections { name, span } => add(Kind::SectionGroup, "sections", name, *span),
+        Node::Layout {
+            name: Some(name),
+            span,
+        } => add(Kind::Layout, "layout", name, *span),
+        Node::Layout { name: None, .. } => layout_none = true,
+        _ => {}
+    });
+    dependencies.layout_none = layout_none;
+    dependencies
+}
+
+/// Extracts the snippets, sections, section groups and layouts a template
+/// references.
+///
+/// # Arguments
+///
+/// * `template` - The Liquid source
+///
+/// # Returns
+///
+/// Static references with their spans, and references with dynamic names
+/// separately
+///
+/// # Examples
+///
+/// ```javascript
+/// extractDependencies("{%- render 'card' for products as product -%}{% include block.settings.snippet %}")
+/// // {
+/// //   references: [{ kind: "snippet", tag: "render", name: "card", path: "snippets/card.liquid", span: {...} }],
+/// //   dynamic: [{ kind: "snippet", tag: "include", source: "{% include block.settings.snippet %}", span: {...} }],
+/// //   layoutNone: false
+/// // }
+/// ```
+#[napi]
+pub fn extract_dependencies(template: String) -> TemplateDependencies {
+    template_dependencies(&template)
+}
+
+/// An edge of the theme graph.
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct DependencyEdge {
+    pub from: String,
+    pub to: String,
+    /// "snippet", "section", "section_group" or "layout".
+    pub kind: String,
+    /// Where the reference is made, for Liquid files. References from JSON
+    /// files and the implicit `theme` layout have none.
+    pub span: Option<SourceSpan>,
+    /// Whether `to` is one of the given files.
+    pub exists: bool,
+}
+
+/// A reference with a dynamic name, and the file making it.
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileDynamicReference {
+    pub file: String,
+    pub reference: DynamicReference,
+}
+
+/// Dependency graph of a whole theme.
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThemeDependencyGraph {
+    /// Files in the graph: Liquid files, JSON templates and section groups.
+    pub files: Vec<String>,
+    pub edges: Vec<DependencyEdge>,
+    /// Referenced files that were not given, sorted.
+    pub missing: Vec<String>,
+    pub dynamic: Vec<FileDynamicReference>,
+    /// Groups of files that reference each other in a loop. Each group is
+    /// sorted, as is the list.
+    pub cycles: Vec<Vec<String>>,
+    /// Snippets and sections no template or layout reaches, sorted.
+    /// References with dynamic names are not followed, so files used only
+    /// through them are listed too.
+    pub unreferenced: Vec<String>,
+}
+
+/// The path from the theme directory on, or `None` for files outside the
+/// graph (assets, config, locales).
+pub(crate) fn theme_path(path: &str) -> Option<String> {
+    let path = path.replace('\\', "/");
+    let start = ["layout/", "templates/", "sections/", "snippets/"]
+        .iter()
+        .filter_map(|dir| {
+            if path.starts_with(dir) {
+                Some(0)
+            } else {
+                path.find(&format!("/{}", dir)).map(|i| i + 1)
+            }
+        })
+        .min()?;
+    let path = &path[start..];
+    (path.ends_with(".liquid") || path.ends_with(".json")).then(|| path.to_string())
+}
+
+/// Section types and layout of a JSON template or section group. Shopify
+/// prefixes generated files with a `/* ... */` comment, which is skipped.
+pub(crate) fn json_dependencies(content: &str) -> (Vec<String>, Option<Value>) {
+    let content = content.trim_start();
+    let content = match content.strip_prefix("/*") {
+        Some(rest) => rest.find("*/").map_or("", |end| &rest[end + 2..]),
+        None => content,
+    };
+    let Ok(Value::Object(json)) = serde_json::from_str::<Value>(content) else {
+        return (Vec::new(), None);
+    };
+    let sections = match json.get("sections") {
+        Some(Value::Object(sections)) => sections
+            .values()
+            .filter_map(|section| section.get("type")?.as_str().map(str::to_string))
+            .collect(),
+        _ => Vec::new(),
+    };
+    (sections, json.get("layout").cloned())
+}
+
+/// Groups of nodes in a cycle, with Tarjan's strongly connected components.
+fn cycles(files: &[String], edges: &[DependencyEdge]) -> Vec<Vec<String>> {
+    let ids: HashMap<&str, usize> = files
+        .iter()
+        .enumerate()
+        .map(|(i, file)| (file.as_str(), i))
+        .collect();
+    let mut graph = vec![Vec::new(); files.len()];
+    let mut self_loops = BTreeSet::new();
+    for edge in edges {
+        if let (Some(&from), Some(&to)) = (ids.get(edge.from.as_str()), ids.get(edge.to.as_str())) {
+            graph[from].push(to);
+            if from == to {
+                self_loops.insert(from);
+            }
+        }
+    }
+
+    struct Tarjan<'g> {
+        graph: &'g [Vec<usize>],
+        index: Vec<Option<usize>>,
+        low: Vec<usize>,
+        on_stack: Vec<bool>,
+        stack: Vec<usize>,
+        next: usize,
+        components: Vec<Vec<usize>>,
+    }
+
+    impl Tarjan<'_> {
+        fn visit(&mut self, node: usize) {
+            self.index[node] = Some(self.next);
+            self.low[node] = self.next;
+            self.next += 1;
+            self.stack.push(node);
+            self.on_stack[node] = true;
+
+            for i in 0..self.graph[node].len() {
+                let next = self.graph[node][i];
+                match self.index[next] {
+                    None => {
+                        self.visit(next);
+                        self.low[node] = self.low[node].min(self.low[next]);
+                    }
+                    Some(index) if self.on_stack[next] => {
+                        self.low[node] = self.low[node].min(index)
+                    }
+                    Some(_) => {}
+                }
+            }
+
+            if Some(self.low[node]) == self.index[node] {
+                let mut component = Vec::new();
+                while let Some(member) = self.stack.pop() {
+                    self.on_stack[member] = false;
+                    component.push(member);
+                    if member == node {
+                        break;
+                    }
+                }
+                self.components.push(component);
+            }
+        }
+    }
+
+    let mut tarjan = Tarjan {
+        graph: &graph,
+        index: vec![None; files.len()],
+        low: vec![0; files.len()],
+        on_stack: vec![false; files.len()],
+        stack: Vec::new(),
+        next: 0,
+        components: Vec::new(),
+    };
+    for node in 0..files.len() {
+        if tarjan.index[node].is_none() {
+            tarjan.visit(node);
+        }
+    }
+
+    let mut cycles: Vec<Vec<String>> = tarjan
+        .components
+        .into_iter()
+        .filter(|component| component.len() > 1 || self_loops.contains(&component[0]))
+        .map(|component| {
+            let mut names: Vec<String> = component.into_iter().map(|i| files[i].clone()).collect();
+            names.sort();
+            names
+        })
+        .collect();
+    cycles.sort();
+    cycles
+}
+
+/// Builds the dependency graph of a theme.
+///
+/// # Arguments
+///
+/// * `files` - The theme files. Assets, config and locales are ignored
+///
+/// # Returns
+///
+/// Every reference as an edge, plus missing files, dynamic references,
+/// cycles and files no template or layout reaches. Templates use
+/// `layout/theme.liquid` unless they name another layout or opt out
+///
+/// # Examples
+///
+/// ```javascript
+/// const graph = buildDependencyGraph([
+///   { path: "templates/index.json", content: indexJson },
+///   { path: "sections/hero.liquid", content: "{% render 'button' %}" },
+///   { path: "snippets/button.liquid", content: "..." },
+///   { path: "snippets/unused.liquid", content: "..." },
+/// ])
+/// // graph.unreferenced: ["snippets/unused.liquid"], graph.missing: ["layout/theme.liquid"]
+/// ```
+#[napi]
+pub fn build_dependency_graph(files: Vec<ThemeFile>) -> ThemeDependencyGraph {
+    // Sorted by path, so the graph does not depend on the input order
+    let files: BTreeMap<String, String> = files
+        .into_iter()
+        .filter_map(|file| Some((theme_path(&file.path)?, file.content)))
+        .collect();
+
+    let mut edges = Vec::new();
+    let mut dynamic = Vec::new();
+    for (path, content) in &files {
+        let mut edge = |to: String, kind: Kind, span: Option<SourceSpan>| {
+            edges.push(DependencyEdge {
+                from: path.clone(),
+                exists: files.contains_key(&to),
+                to,
+                kind: kind.as_str().to_string(),
+                span,
+            })
+        };
+        let is_template = path.starts_with("templates/");
+
+        if path.ends_with(".json") {
+            let (sections, layout) = json_dependencies(content);
+            for section in sections {
+                edge(Kind::Section.path(&section), Kind::Section, None);
+            }
+            if is_template {
+                match layout {
+                    Some(Value::String(name)) => edge(Kind::Layout.path(&name), Kind::Layout, None),
+                    Some(Value::Bool(false)) => {}
+                    _ => edge(Kind::Layout.path(DEFAULT_LAYOUT), Kind::Layout, None),
+                }
+            }
+            continue;
+        }
+
+        let dependencies = template_dependencies(content);
+        let names_layout = dependencies
+            .references
+            .iter()
+            .any(|reference| reference.tag == "layout");
+        for reference in dependencies.references {
+            let kind = match reference.tag.as_str() {
+                "render" | "include" => Kind::Snippet,
+                "section" => Kind::Section,
+                "sections" => Kind::SectionGroup,
+                _ => Kind::Layout,
+            };
+            edge(reference.path, kind, Some(reference.span));
+        }
+        if is_template && !names_layout && !dependencies.layout_none {
+            edge(Kind::Layout.path(DEFAULT_LAYOUT), Kind::Layout, None);
+        }
+        dynamic.extend(
+            dependencies
+                .dynamic
+                .into_iter()
+                .map(|reference| FileDynamicReference {
+                    file: path.clone(),
+                    reference,
+                }),
+        );
+    }
+
+    let paths: Vec<String> = files.keys().cloned().collect();
+    let missing: BTreeSet<String> = edges
+        .iter()
+        .filter(|edge| !edge.exists)
+        .map(|edge| edge.to.clone())
+        .collect();
+
+    // Reachability from templates and layouts
+    let mut reached: BTreeSet<&str> = paths
+        .iter()
+        .filter(|path| path.starts_with("templates/") || path.starts_with("layout/"))
+        .map(String::as_str)
+        .collect();
+    let mut pending: Vec<&str> = reached.iter().copied().collect();
+    while let Some(file) = pending.pop() {
+        for edge in edges.iter().filter(|edge| edge.from == file && edge.exists) {
+            if reached.insert(edge.to.as_str()) {
+                pending.push(edge.to.as_str());
+            }
+        }
+    }
+    let unreferenced = paths
+        .iter()
+        .filter(|path| !reached.contains(path.as_str()))
+        .cloned()
+        .collect();
+
+    ThemeDependencyGraph {
+        cycles: cycles(&paths, &edges),
+        files: paths,
+        edges,
+        missing: missing.into_iter().collect(),
+        dynamic,
+        unreferenced,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test_support::{example_theme_files, example_themes};
+
+    fn file(path: &str, content: &str) -> ThemeFile {
+        ThemeFile {
+            path: path.to_string(),
+            content: content.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_extract_dependencies() {
+        let source = "{%- render 'card' for products as product -%}\n\
+            {% include 'legacy' %}{% render \"icon\", name: 'x' | upcase %}\n\
+            {% liquid\n  render 'in-liquid'\n  section 'header'\n%}\n\
+            {% if x %}{% sections 'footer-group' %}{% endif %}\
+            {% include block.settings.snippet %}{% layout 'checkout' %}";
+        let dependencies = extract_dependencies(source.to_string());
+
+        let found: Vec<(&str, &str, &str)> = dependencies
+            .references
+            .iter()
+            .map(|r| (r.kind.as_str(), r.tag.as_str(), r.path.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("snippet", "render", "snippets/card.liquid"),
+                ("snippet", "include", "snippets/legacy.liquid"),
+                ("snippet", "render", "snippets/icon.liquid"),
+                ("snippet", "render", "snippets/in-liquid.liquid"),
+                ("section", "section", "sections/header.liquid"),
+                ("section_group", "sections", "sections/footer-group.json"),
+                ("layout", "layout", "layout/checkout.liquid"),
+            ]
+        );
+        let card = &dependencies.references[0];
+        assert_eq!(
+            (card.span.start, card.span.line, card.span.column),
+            (0, 1, 1)
+        );
+        assert_eq!(
+            &source[..card.span.end as usize],
+            "{%- render 'card' for products as product -%}"
+        );
+        assert_eq!(dependencies.references[3].span.line, 4);
+
+        assert_eq!(dependencies.dynamic.len(), 1);
+        assert_eq!(
+            dependencies.dynamic[0].source,
+            "{% include block.settings.snippet %}"
+        );
+        assert!(!dependencies.layout_none);
+        assert!(extract_dependencies("{% layout none %}".to_string()).layout_none);
+    }
+
+    #[test]
+    fn test_dependency_graph() {
+        let graph = build_dependency_graph(vec![
+            file(
+                "theme/layout/theme.liquid",
+                "{% sections 'header-group' %}{{ content_for_layout }}",
+            ),
+            file(
+                "theme/templates/index.json",
+                "/* generated */ {\"sections\": {\"a\": {\"type\": \"hero\"}}}",
+            ),
+            file(
+                "theme/templates/gift_card.liquid",
+                "{% layout none %}{% render 'price' %}",
+            ),
+            file("theme/templates/page.liquid", "{% section 'missing' %}"),
+            file(
+                "theme/sections/header-group.json",
+                "{\"sections\": {\"h\": {\"type\": \"header\"}}}",
+            ),
+            file("theme/sections/header.liquid", "{% render 'a' %}"),
+            file(
+                "theme/sections/hero.liquid",
+                "{% render 'button' %}{% render name %}",
+            ),
+            file("theme/sections/orphan.liquid", "{% render 'self' %}"),
+            file("theme/snippets/a.liquid", "{% render 'b' %}"),
+            file("theme/snippets/b.liquid", "{% include 'a' %}"),
+            file("theme/snippets/button.liquid", "<button></button>"),
+            file("theme/snippets/price.liquid", ""),
+            file("theme/snippets/self.liquid", "{% render 'self' %}"),
+            file("theme/assets/app.js", "{% render 'ignored' %}"),
+        ]);
+
+        assert_eq!(graph.files.len(), 13);
+        assert_eq!(graph.missing, ["sections/missing.liquid"]);
+        assert_eq!(
+            graph.cycles,
+            [
+                vec!["snippets/a.liquid", "snippets/b.liquid"],
+                vec!["snippets/self.liquid"]
+            ]
+        );
+        assert_eq!(
+            graph.unreferenced,
+            ["sections/orphan.liquid", "snippets/self.liquid"]
+        );
+        assert_eq!(graph.dynamic.len(), 1);
+        assert_eq!(graph.dynamic[0].file, "sections/hero.liquid");
+
+        // Templates get the theme layout unless they opt out
+        let layouts: Vec<&str> = graph
+            .edges
+            .iter()
+            .filter(|edge| edge.kind == "layout")
+            .map(|edge| edge.from.as_str())
+            .collect();
+        assert_eq!(layouts, ["templates/index.json", "templates/page.liquid"]);
+        assert!(graph
+            .edges
+            .iter()
+            .all(|edge| edge.exists || edge.to == "sections/missing.liquid"));
+    }
+
+    #[test]
+    fn test_example_theme_graph() {
+        let root = example_themes().join("shopify/theme");
+        let files = example_theme_files("")
+            .into_iter()
+            .filter_map(|path| {
+                let relative = path.strip_prefix(&root).ok()?;
+                let content = std::fs::read_to_string(&path).ok()?;
+                Some(file(&relative.to_string_lossy(), &content))
+            })
+            .collect();
+        let graph = build_dependency_graph(files);
+
+        assert!(graph.files.contains(&"layout/theme.liquid".to_string()));
+        assert!(graph
+            .edges
+            .iter()
+            .any(|edge| edge.from == "layout/theme.liquid"
+                && edge.to == "sections/header-group.json"
+                && edge.exists));
+        assert!(graph.cycles.is_empty(), "{:?}", graph.cycles);
+        assert!(!graph
+            .unreferenced
+            .contains(&"snippets/card-product.liquid".to_string()));
+    }
+}
@@ -0,0 +1,756 @@
+/*
+ * Copyright 2025 Fasttify LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Parser for the expressions inside `{{ }}` and tag markup.
+
+use super::ast::{
+    Arg, CompareOp, Condition, Expr, Filter, Filtered, FilteredArg, PathSegment, Span,
+};
+
+/// A lexical element of tag or output markup.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Tok<'a> {
+    Ident(&'a str),
+    /// String literal contents, without quotes.
+    Str(&'a str),
+    Number(&'a str),
+    Dot,
+    DotDot,
+    LBracket,
+    RBracket,
+    LParen,
+    RParen,
+    Pipe,
+    Colon,
+    Comma,
+    Equals,
+    Compare(CompareOp),
+    Invalid(char),
+    /// A string literal missing its closing quote.
+    Unterminated,
+}
+
+/// A token with its byte range in the template.
+#[derive(Debug, Clone, Copy)]
+pub struct Lexeme<'a> {
+    pub tok: Tok<'a>,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// An expression syntax error at a byte range of the template.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExprError {
+    pub code: &'static str,
+    pub message: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Splits markup into lexemes. `base` is the byte offset of `markup` in
+/// the template and is added to every position.
+pub fn lex_markup(markup: &str, base: usize) -> Vec<Lexeme<'_>> {
+    let bytes = markup.as_bytes();
+    let mut lexemes = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let b = bytes[i];
+        let start = i;
+
+        let tok = match b {
+            b if b.is_ascii_whitespace() => {
+                i += 1;
+                continue;
+            }
+            b'"' | b'\'' => match markup[i + 1..].find(b as char) {
+                Some(len) => {
+                    i += len + 2;
+                    Tok::Str(&markup[start + 1..start + 1 + len])
+                }
+                None => {
+                    i = bytes.len();
+                    Tok::Unterminated
+                }
+            },
+            b'0'..=b'9' => {
+                i = scan_number(bytes, i);
+                Tok::Number(&markup[start..i])
+            }
+            b'-' if bytes.get(i + 1).is_some_and(u8::is_ascii_digit) => {
+                i = scan_number(bytes, i + 1);
+                Tok::Number(&markup[start..i])
+            }
+            b'.' if bytes.get(i + 1) == Some(&b'.') => {
+                i += 2;
+                Tok::DotDot
+            }
+            b'.' => {
+                i += 1;
+                Tok::Dot
+            }
+            b'[' | b']' | b'(' | b')' | b'|' | b':' | b',' => {
+                i += 1;
+                match b {
+                    b'[' => Tok::LBracket,
+                    b']' => Tok::RBracket,
+                    b'(' => Tok::LParen,
+                    b')' => Tok::RParen,
+                    b'|' => Tok::Pipe,
+                    b':' => Tok::Colon,
+                    _ => Tok::Comma,
+                }
+            }
+            b'=' | b'!' | b'<' | b'>' => {
+                let two = bytes.get(i + 1).copied();
+                let (tok, len) = match (b, two) {
+                    (b'=', Some(b'=')) => (Tok::Compare(CompareOp::Eq), 2),
+                    (b'!', Some(b'=')) => (Tok::Compare(CompareOp::Ne), 2),
+                    (b'<', Some(b'>')) => (Tok::Compare(CompareOp::Ne), 2),
+                    (b'<', Some(b'=')) => (Tok::Compare(CompareOp::Le), 2),
+                    (b'>', Some(b'=')) => (Tok::Compare(CompareOp::Ge), 2),
+                    (b'<', _) => (Tok::Compare(CompareOp::Lt), 1),
+                    (b'>', _) => (Tok::Compare(CompareOp::Gt), 1),
+                    (b'=', _) => (Tok::Equals, 1),
+                    _ => (Tok::Invalid('!'), 1),
+                };
+                i += len;
+                tok
+            }
+            _ if b.is_ascii_alphabetic() || b == b'_' || b >= 0x80 => {
+                i = scan_identifier(markup, i);
+                if i == start {
+                    // Non-alphanumeric Unicode such as `…` outside quotes
+                    let c = markup[start..].chars().next().unwrap_or('\u{fffd}');
+                    i += c.len_utf8();
+                    Tok::Invalid(c)
+                } else {
+                    Tok::Ident(&markup[start..i])
+                }
+            }
+            _ => {
+                i += 1;
+                Tok::Invalid(b as char)
+            }
+        };
+
+        lexemes.push(Lexeme {
+            tok,
+            start: base + start,
+            end: base + i,
+        });
+    }
+
+    lexemes
+}
+
+fn scan_number(bytes: &[u8], mut i: usize) -> usize {
+    while i < bytes.len() && bytes[i].is_ascii_digit() {
+        i += 1;
+    }
+    // A single dot followed by a digit is a decimal point; `..` is a range
+    if bytes.get(i) == Some(&b'.') && bytes.get(i + 1).is_some_and(u8::is_ascii_digit) {
+        i += 1;
+        while i < bytes.len() && bytes[i].is_ascii_digit() {
+            i += 1;
+        }
+    }
+    i
+}
+
+/// Identifiers may contain hyphens and end with `?`, as in Shopify Liquid.
+fn scan_identifier(markup: &str, start: usize) -> usize {
+    let mut end = start;
+    for (offset, c) in markup[start..].char_indices() {
+        if c.is_alphanumeric() || c == '_' || c == '-' {
+            end = start + offset + c.len_utf8();
+        } else if c == '?' {
+            end = start + offset + 1;
+            break;
+        } else {
+            break;
+        }
+    }
+    end
+}
+
+/// How deep blocks, lookups, ranges and `and`/`or` chains may nest before
+/// parsing stops with `nesting-too-deep`, so that no template can exhaust
+/// the stack. Shopify's Liquid has the same limit.
+pub const MAX_NESTING_DEPTH: usize = 100;
+
+/// Recursive-descent parser over the lexemes of one piece of markup.
+pub struct ExprParser<'a> {
+    lexemes: Vec<Lexeme<'a>>,
+    pos: usize,
+    /// Byte offset just past the markup, used for end-of-input errors.
+    end: usize,
+    /// Expressions and conditions being parsed, outermost first.
+    depth: usize,
+}
+
+impl<'a> ExprParser<'a> {
+    pub fn new(markup: &'a str, base: usize) -> Self {
+        Self {
+            lexemes: lex_markup(markup, base),
+            pos: 0,
+            end: base + markup.len(),
+            depth: 0,
+        }
+    }
+
+    pub fn peek(&self) -> Option<Tok<'a>> {
+        self.lexemes.get(self.pos).map(|l| l.tok)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<Tok<'a>> {
+        self.lexemes.get(self.pos + offset).map(|l| l.tok)
+    }
+
+    pub fn at_end(&self) -> bool {
+        self.pos >= self.lexemes.len()
+    }
+
+    /// Byte offset of the next lexeme, or of the end of the markup.
+    pub fn offset(&self) -> usize {
+        self.lexemes.get(self.pos).map_or(self.end, |l| l.start)
+    }
+
+    /// Byte offset just past the last consumed lexeme.
+    fn consumed_end(&self) -> usize {
+        self.pos
+            .checked_sub(1)
+            .and_then(|i| self.lexemes.get(i))
+            .map_or(self.end, |l| l.end)
+    }
+
+    fn advance(&mut self) -> Option<Lexeme<'a>> {
+        let lexeme = self.lexemes.get(self.pos).copied();
+        self.pos += 1;
+        lexeme
+    }
+
+    /// Consumes the next lexeme if it equals `tok`.
+    pub fn eat(&mut self, tok: Tok<'_>) -> bool {
+        if self.peek() == Some(tok) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Consumes the identifier `word` if it is next.
+    pub fn eat_keyword(&mut self, word: &str) -> bool {
+        self.eat(Tok::Ident(word))
+    }
+
+    /// Builds an error pointing at the next lexeme.
+    pub fn error(&self, message: impl Into<String>) -> ExprError {
+        let (start, end, code) = match self.lexemes.get(self.pos) {
+            Some(l) if l.tok == Tok::Unterminated => (l.start, l.end, "unterminated-string"),
+            Some(l) => (l.start, l.end, "invalid-syntax"),
+            None => (self.end, self.end, "invalid-syntax"),
+        };
+        ExprError {
+            code,
+            message: message.into(),
+            start,
+            end,
+        }
+    }
+
+    /// Runs `parse` one level deeper, failing past [`MAX_NESTING_DEPTH`].
+    fn nested<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<T, ExprError>,
+    ) -> Result<T, ExprError> {
+        if self.depth >= MAX_NESTING_DEPTH {
+            return Err(ExprError {
+                code: "nesting-too-deep",
+                ..self.error(format!(
+                    "Expression is nested more than {} levels deep",
+                    MAX_NESTING_DEPTH
+                ))
+            });
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn expect(&mut self, tok: Tok<'_>, what: &str) -> Result<(), ExprError> {
+        if self.eat(tok) {
+            Ok(())
+        } else {
+            Err(self.error(format!("Expected {}", what)))
+        }
+    }
+
+    /// Fails unless every lexeme was consumed.
+    pub fn finish(&self) -> Result<(), ExprError> {
+        if self.at_end() {
+            Ok(())
+        } else {
+            Err(self.error("Unexpected content"))
+        }
+    }
+
+    /// Parses an identifier (`assign` targets, loop variables...).
+    pub fn identifier(&mut self) -> Result<String, ExprError> {
+        match self.peek() {
+            Some(Tok::Ident(name)) => {
+                self.pos += 1;
+                Ok(name.to_string())
+            }
+            _ => Err(self.error("Expected a variable name")),
+        }
+    }
+
+    /// Parses a value: literal, variable lookup or range.
+    pub fn expression(&mut self) -> Result<Expr, ExprError> {
+        self.nested(Self::value)
+    }
+
+    fn value(&mut self) -> Result<Expr, ExprError> {
+        let Some(lexeme) = self.lexemes.get(self.pos).copied() else {
+            return Err(self.error("Expected a value"));
+        };
+
+        let expr = match lexeme.tok {
+            Tok::Str(value) => {
+                self.pos += 1;
+                Expr::String {
+                    value: value.to_string(),
+                }
+            }
+            Tok::Number(number) => {
+                self.pos += 1;
+                if number.contains('.') {
+                    Expr::Float {
+                        value: number.parse().unwrap_or(0.0),
+                    }
+                } else {
+                    match number.parse() {
+                        Ok(value) => Expr::Int { value },
+                        Err(_) => Expr::Float {
+                            value: number.parse().unwrap_or(0.0),
+                        },
+                    }
+                }
+            }
+            Tok::LParen => {
+                self.pos += 1;
+                let start = self.expression()?;
+                self.expect(Tok::DotDot, "'..' in range")?;
+                let end = self.expression()?;
+                self.expect(Tok::RParen, "')' to close range")?;
+                Expr::Range {
+                    start: Box::new(start),
+                    end: Box::new(end),
+                }
+            }
+            Tok::Ident(name) => {
+                self.pos += 1;
+                match name {
+                    "nil" | "null" => Expr::Nil,
+                    "empty" => Expr::Empty,
+                    "blank" => Expr::Blank,
+                    "true" => Expr::Bool { value: true },
+                    "false" => Expr::Bool { value: false },
+                    _ => self.variable_path(name.to_string())?,
+                }
+            }
+            // `['key']` at the root looks the key up in the global scope
+            Tok::LBracket => match self.peek_at(1) {
+                Some(Tok::Str(name)) if self.peek_at(2) == Some(Tok::RBracket) => {
+                    self.pos += 3;
+                    self.variable_path(name.to_string())?
+                }
+                _ => return Err(self.error("Expected a quoted name after '['")),
+            },
+            _ => return Err(self.error("Expected a value")),
+        };
+
+        Ok(expr)
+    }
+
+    fn variable_path(&mut self, name: String) -> Result<Expr, ExprError> {
+        let mut path = Vec::new();
+        loop {
+            if self.peek() == Some(Tok::Dot) {
+                self.pos += 1;
+                match self.peek() {
+                    Some(Tok::Ident(key)) => {
+                        self.pos += 1;
+                        path.push(PathSegment::Key {
+                            name: key.to_string(),
+                        });
+                    }
+                    _ => return Err(self.error("Expected a property name after '.'")),
+                }
+            } else if self.peek() == Some(Tok::LBracket) {
+                self.pos += 1;
+                let expr = self.expression()?;
+                self.expect(Tok::RBracket, "']'")?;
+                path.push(PathSegment::Index { expr });
+            } else {
+                return Ok(Expr::Variable { name, path });
+            }
+        }
+    }
+
+    /// Parses `expr | filter: args | ...`.
+    pub fn filtered(&mut self) -> Result<Filtered, ExprError> {
+        let expr = if self.at_end() || self.peek() == Some(Tok::Pipe) {
+            Expr::Nil
+        } else {
+            self.expression()?
+        };
+        let filters = self.filters()?;
+        Ok(Filtered { expr, filters })
+    }
+
+    /// Parses a (possibly empty) chain of `| filter` applications.
+    pub fn filters(&mut self) -> Result<Vec<Filter>, ExprError> {
+        let mut filters = Vec::new();
+
+        while self.eat(Tok::Pipe) {
+            let start = self.offset();
+            let name = match self.peek() {
+                Some(Tok::Ident(name)) => {
+                    self.pos += 1;
+                    name.to_string()
+                }
+                _ => return Err(self.error("Expected a filter name after '|'")),
+            };
+
+            let mut args = Vec::new();
+            let mut kwargs = Vec::new();
+            if self.eat(Tok::Colon) {
+                loop {
+                    self.argument(&mut args, &mut kwargs)?;
+                    if !self.eat(Tok::Comma) {
+                        break;
+                    }
+                }
+            }
+
+            filters.push(Filter {
+                name,
+                args,
+                kwargs,
+                span: Span::new(start, self.consumed_end()),
+            });
+        }
+
+        Ok(filters)
+    }
+
+    /// Parses one positional (`value`) or keyword (`name: value`) argument.
+    pub fn argument(
+        &mut self,
+        args: &mut Vec<Expr>,
+        kwargs: &mut Vec<Arg>,
+    ) -> Result<(), ExprError> {
+        if let (Some(Tok::Ident(name)), Some(Tok::Colon)) = (self.peek(), self.peek_at(1)) {
+            self.pos += 2;
+            kwargs.push(Arg {
+                name: name.to_string(),
+                value: self.expression()?,
+            });
+        } else {
+            args.push(self.expression()?);
+        }
+        Ok(())
+    }
+
+    /// Parses keyword arguments separated by optional commas until the end
+    /// of the markup (`render` and `include` style). Values may carry
+    /// filters.
+    pub fn keyword_arguments(&mut self) -> Result<Vec<FilteredArg>, ExprError> {
+        let mut kwargs = Vec::new();
+        loop {
+            self.eat(Tok::Comma);
+            if self.at_end() {
+                return Ok(kwargs);
+            }
+            let name = match (self.peek(), self.peek_at(1)) {
+                (Some(Tok::Ident(name)), Some(Tok::Colon)) => name,
+                _ => return Err(self.error("Expected 'name: value' argument")),
+            };
+            self.pos += 2;
+            let expr = self.expression()?;
+            kwargs.push(FilteredArg {
+                name: name.to_string(),
+                value: Filtered {
+                    expr,
+                    filters: self.filters()?,
+                },
+            });
+        }
+    }
+
+    /// Parses a condition with right-associative `and`/`or`.
+    pub fn condition(&mut self) -> Result<Condition, ExprError> {
+        self.nested(Self::logical)
+    }
+
+    fn logical(&mut self) -> Result<Condition, ExprError> {
+        let left = self.comparison()?;
+        if self.eat_keyword("and") {
+            Ok(Condition::And {
+                left: Box::new(left),
+                right: Box::new(self.condition()?),
+            })
+        } else if self.eat_keyword("or") {
+            Ok(Condition::Or {
+                left: Box::new(left),
+                right: Box::new(self.condition()?),
+            })
+        } else {
+            Ok(left)
+        }
+    }
+
+    fn comparison(&mut self) -> Result<Condition, ExprError> {
+        let left = self.expression()?;
+        let op = match self.peek() {
+            Some(Tok::Compare(op)) => op,
+            Some(Tok::Ident("contains")) => CompareOp::Contains,
+            _ => return Ok(Condition::Test { expr: left }),
+        };
+        self.advance();
+        let right = self.expression()?;
+        Ok(Condition::Compare { left, op, right })
+    }
+}
+
+/// Parses a complete filtered expression (`{{ }}` and `echo` markup).
+pub fn parse_filtered(markup: &str, base: usize) -> Result<Filtered, ExprError> {
+    let mut parser = ExprParser::new(markup, base);
+    let filtered = parser.filtered()?;
+    parser.finish()?;
+    Ok(filtered)
+}
+
+/// Parses a complete condition (`if`/`unless`/`elsif` markup).
+pub fn parse_condition(markup: &str, base: usize) -> Result<Condition, ExprError> {
+    let mut parser = ExprParser::new(markup, base);
+    let mut condition = parser.condition()?;
+    let filters = parser.filters()?;
+    if !filters.is_empty() {
+        condition = Condition::Filtered {
+            condition: Box::new(condition),
+            filters,
+        };
+    }
+    parser.finish()?;
+    Ok(condition)
+}
+
+/// Parses a complete single expression.
+pub fn parse_expression(markup: &str, base: usize) -> Result<Expr, ExprError> {
+    let mut parser = ExprParser::new(markup, base);
+    let expr = parser.expression()?;
+    parser.finish()?;
+    Ok(expr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn var(name: &str, keys: &[&str]) -> Expr {
+        Expr::Variable {
+            name: name.to_string(),
+            path: keys
+                .iter()
+                .map(|k| PathSegment::Key {
+                    name: k.to_string(),
+                })
+                .collect(),
+        }
+    }
+
+    fn string(value: &str) -> Expr {
+        Expr::String {
+            value: value.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_lex_markup() {
+        let toks: Vec<_> = lex_markup("a.b[0] | x: 'y', -1.5 <> (1..n) == c? ", 0)
+            .into_iter()
+            .map(|l| l.tok)
+            .collect();
+        assert_eq!(
+            toks,
+            vec![
+                Tok::Ident("a"),
+                Tok::Dot,
+                Tok::Ident("b"),
+                Tok::LBracket,
+                Tok::Number("0"),
+                Tok::RBracket,
+                Tok::Pipe,
+                Tok::Ident("x"),
+                Tok::Colon,
+                Tok::Str("y"),
+                Tok::Comma,
+                Tok::Number("-1.5"),
+                Tok::Compare(CompareOp::Ne),
+                Tok::LParen,
+                Tok::Number("1"),
+                Tok::DotDot,
+                Tok::Ident("n"),
+                Tok::RParen,
+                Tok::Compare(CompareOp::Eq),
+                Tok::Ident("c?"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_expression_values() {
+        assert_eq!(parse_expression("nil", 0), Ok(Expr::Nil));
+        assert_eq!(parse_expression("42", 0), Ok(Expr::Int { value: 42 }));
+        assert_eq!(parse_expression("-0.5", 0), Ok(Expr::Float { value: -0.5 }));
+        assert_eq!(parse_expression("\"a b\"", 0), Ok(string("a b")));
+        assert_eq!(
+            parse_expression("collections['ofertas'].products[i]", 0),
+            Ok(Expr::Variable {
+                name: "collections".to_string(),
+                path: vec![
+                    PathSegment::Index {
+                        expr: string("ofertas")
+                    },
+                    PathSegment::Key {
+                        name: "products".to_string()
+                    },
+                    PathSegment::Index {
+                        expr: var("i", &[])
+                    },
+                ],
+            })
+        );
+        assert_eq!(
+            parse_expression("(1..product.size)", 0),
+            Ok(Expr::Range {
+                start: Box::new(Expr::Int { value: 1 }),
+                end: Box::new(var("product", &["size"])),
+            })
+        );
+        assert_eq!(
+            parse_expression("['settings']", 0),
+            Ok(var("settings", &[]))
+        );
+    }
+
+    #[test]
+    fn test_parse_filtered() {
+        let filtered =
+            parse_filtered("title | truncate: 40, '…' | link_to: url, class: 'x'", 10).unwrap();
+        assert_eq!(filtered.expr, var("title", &[]));
+        assert_eq!(filtered.filters.len(), 2);
+        assert_eq!(filtered.filters[0].name, "truncate");
+        assert_eq!(
+            filtered.filters[0].args,
+            vec![Expr::Int { value: 40 }, string("…")]
+        );
+        assert_eq!(filtered.filters[0].span, Span::new(18, 37));
+        assert_eq!(filtered.filters[1].args, vec![var("url", &[])]);
+        assert_eq!(
+            filtered.filters[1].kwargs,
+            vec![Arg {
+                name: "class".to_string(),
+                value: string("x")
+            }]
+        );
+        assert_eq!(parse_filtered("", 0).unwrap().expr, Expr::Nil);
+    }
+
+    #[test]
+    fn test_parse_condition_is_right_associative() {
+        let a = || Condition::Test {
+            expr: var("a", &[]),
+        };
+        let b = || Condition::Test {
+            expr: var("b", &[]),
+        };
+        let c = || Condition::Test {
+            expr: var("c", &[]),
+        };
+        assert_eq!(
+            parse_condition("a and b or c", 0),
+            Ok(Condition::And {
+                left: Box::new(a()),
+                right: Box::new(Condition::Or {
+                    left: Box::new(b()),
+                    right: Box::new(c()),
+                }),
+            })
+        );
+        assert_eq!(
+            parse_condition("tags contains 'sale' or x != empty", 0),
+            Ok(Condition::Or {
+                left: Box::new(Condition::Compare {
+                    left: var("tags", &[]),
+                    op: CompareOp::Contains,
+                    right: string("sale"),
+                }),
+                right: Box::new(Condition::Compare {
+                    left: var("x", &[]),
+                    op: CompareOp::Ne,
+                    right: Expr::Empty,
+                }),
+            })
+        );
+        assert!(matches!(
+            parse_condition("a or b | minus: 1", 0),
+            Ok(Condition::Filtered { condition, filters })
+                if matches!(*condition, Condition::Or { .. }) && filters[0].name == "minus"
+        ));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = parse_filtered("a | ", 5).unwrap_err();
+        assert_eq!(err.message, "Expected a filter name after '|'");
+        assert_eq!((err.start, err.end), (9, 9));
+
+        let err = parse_filtered("'open", 0).unwrap_err();
+        assert_eq!(err.code, "unterminated-string");
+
+        let err = parse_expression("a b", 0).unwrap_err();
+        assert_eq!(err.message, "Unexpected content");
+        assert_eq!((err.start, err.end), (2, 3));
+    }
+
+    #[test]
+    fn test_parse_nesting_limit() {
+        let lookup = |depth: usize| format!("{}a{}", "a[".repeat(depth), "]".repeat(depth));
+        assert!(parse_expression(&lookup(MAX_NESTING_DEPTH - 1), 0).is_ok());
+        let err = parse_expression(&lookup(100_000), 0).unwrap_err();
+        assert_eq!(err.code, "nesting-too-deep");
+
+        let chain = vec!["a"; 100_000].join(" and ");
+        let err = parse_condition(&chain, 0).unwrap_err();
+        assert_eq!(err.code, "nesting-too-deep");
+    }
+}
@@ -0,0 +1,306 @@
+/*
+ * Copyright 2025 Fasttify LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! `section.fasttify_attributes` and `block.fasttify_attributes`: the
+//! attributes the theme editor uses to find sections and blocks in the
+//! rendered page.
+//!
+//! Usages are read from the parsed template, so whitespace control,
+//! filters and `{% liquid %}` / `{% echo %}` forms are all found.
+
+use super::ast::{walk, Expr, Filtered, Node, PathSegment};
+use super::html::{start_tags, Element};
+use super::parser::parse_template;
+use super::span::{LineIndex, SourceSpan};
+
+/// Elements that come before a section's content without wrapping it.
+const NON_ROOT_ELEMENTS: &[&str] = &["script", "style", "link", "meta", "noscript", "template"];
+
+/// What's inserted into a section's root element when it lacks it.
+const SECTION_ATTRIBUTES: &str = " {{ section.fasttify_attributes }}";
+
+/// An HTML start tag.
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct HtmlElementSpan {
+    /// Lowercased tag name.
+    pub name: String,
+    /// The start tag, `<` through `>`.
+    pub span: SourceSpan,
+}
+
+/// An output of `section.fasttify_attributes` or `block.fasttify_attributes`.
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct FasttifyAttributesUsage {
+    /// "section" or "block".
+    pub target: String,
+    /// The Liquid output or echo.
+    pub span: SourceSpan,
+    /// The start tag the output is in, if it's inside one.
+    pub element: Option<HtmlElementSpan>,
+}
+
+/// How a template uses the editor attributes.
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct FasttifyAttributesReport {
+    pub has_section_attributes: bool,
+    pub has_block_attributes: bool,
+    /// Every usage, in source order.
+    pub usages: Vec<FasttifyAttributesUsage>,
+    /// The first element of the section's content, skipping scripts,
+    /// styles and other elements that don't wrap it.
+    pub root_element: Option<HtmlElementSpan>,
+    /// Whether the root element exists and doesn't output
+    /// `section.fasttify_attributes`.
+    pub root_missing_attributes: bool,
+}
+
+/// `section` or `block` if `value` reads their `fasttify_attributes`.
+fn attributes_target(value: &Filtered) -> Option<&'static str> {
+    let Expr::Variable { name, path } = &value.expr else {
+        return None;
+    };
+    let target = match name.as_str() {
+        "section" => "section",
+        "block" => "block",
+        _ => return None,
+    };
+    let key = match path.as_slice() {
+        [PathSegment::Key { name }] => name,
+        [PathSegment::Index {
+            expr: Expr::String { value },
+        }] => value,
+        _ => return None,
+    };
+    (key == "fasttify_attributes").then_some(target)
+}
+
+/// Usages with their byte ranges, in source order.
+fn usages(source: &str) -> Vec<(&'static str, usize, usize)> {
+    let parsed = parse_template(source);
+    let mut found = Vec::new();
+    walk(&parsed.nodes, &mut |node| {
+        if let Node::Output { value, span } | Node::Echo { value, span } = node {
+            if let Some(target) = attributes_target(value) {
+                found.push((target, span.start as usize, span.end as usize));
+            }
+        }
+    });
+    found.sort_by_key(|&(_, start, _)| start);
+    found
+}
+
+fn root_element(elements: &[Element]) -> Option<&Element> {
+    elements
+        .iter()
+        .find(|element| !NON_ROOT_ELEMENTS.contains(&element.name.as_str()))
+}
+
+fn element_span(index: &LineIndex, element: &Element) -> HtmlElementSpan {
+    HtmlElementSpan {
+        name: element.name.clone(),
+        span: index.span(element.start, element.end),
+    }
+}
+
+/// Finds where a template outputs the theme editor attributes.
+///
+/// # Arguments
+///
+/// * `template` - The Liquid source of a section or block
+///
+/// # Returns
+///
+/// Section and block usages with the start tags they're in, and the
+/// section's root element with whether it lacks the attributes
+///
+/// # Examples
+///
+/// ```javascript
+/// const report = detectFasttifyAttributes('<div {{section.fasttify_attributes|default:""}}>');
+/// report.hasSectionAttributes // true
+/// report.usages[0].element.name // "div"
+/// ```
+#[napi]
+pub fn detect_fasttify_attributes(template: String) -> FasttifyAttributesReport {
+    let index = LineIndex::new(&template);
+    let elements = start_tags(&template);
+    let found = usages(&template);
+    let inside =
+        |element: &Element, start: usize, end: usize| element.start <= start && end <= element.end;
+
+    let usages: Vec<FasttifyAttributesUsage> = found
+        .iter()
+        .map(|&(target, start, end)| FasttifyAttributesUsage {
+            target: target.to_string(),
+            span: index.span(start, end),
+            element: elements
+                .iter()
+                .find(|element| inside(element, start, end))
+                .map(|element| element_span(&index, element)),
+        })
+        .collect();
+    let root = root_element(&elements);
+    let root_missing_attributes = root.is_some_and(|root| {
+        !found
+            .iter()
+            .any(|&(target, start, end)| target == "section" && inside(root, start, end))
+    });
+
+    FasttifyAttributesReport {
+        has_section_attributes: found.iter().any(|&(target, ..)| target == "section"),
+        has_block_attributes: found.iter().any(|&(target, ..)| target == "block"),
+        usages,
+        root_element: root.map(|root| element_span(&index, root)),
+        root_missing_attributes,
+    }
+}
+
+/// Adds `{{ section.fasttify_attributes }}` to a section's root element
+/// when it lacks it.
+///
+/// # Arguments
+///
+/// * `template` - The Liquid source of a section
+///
+/// # Returns
+///
+/// The template with the attributes after the root element's tag name, or
+/// unchanged if they're already there or there's no root element
+///
+/// # Examples
+///
+/// ```javascript
+/// injectFasttifyAttributes('<section class="hero">') // '<section {{ section.fasttify_attributes }} class="hero">'
+/// ```
+#[napi]
+pub fn inject_fasttify_attributes(template: String) -> String {
+    let elements = start_tags(&template);
+    let Some(root) = root_element(&elements) else {
+        return template;
+    };
+    let present = usages(&template)
+        .iter()
+        .any(|&(target, start, end)| target == "section" && root.start <= start && end <= root.end);
+    if present {
+        return template;
+    }
+    let at = root.start + 1 + root.name.len();
+    let mut injected = String::with_capacity(template.len() + SECTION_ATTRIBUTES.len());
+    injected.push_str(&template[..at]);
+    injected.push_str(SECTION_ATTRIBUTES);
+    injected.push_str(&template[at..]);
+    injected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(template: &str) -> Vec<(String, String, Option<String>)> {
+        detect_fasttify_attributes(template.to_string())
+            .usages
+            .into_iter()
+            .map(|usage| {
+                let text = template[usage.span.start as usize..usage.span.end as usize].to_string();
+                (usage.target, text, usage.element.map(|e| e.name))
+            })
+            .collect()
+    }
+
+    fn usage(target: &str, text: &str, element: Option<&str>) -> (String, String, Option<String>) {
+        (
+            target.to_string(),
+            text.to_string(),
+            element.map(str::to_string),
+        )
+    }
+
+    #[test]
+    fn test_detect_forms() {
+        let template = concat!(
+            "<section {{section.fasttify_attributes|default:''}} class=\"a\">",
+            "<div {{- block.fasttify_attributes -}}>",
+            "<p {% echo section['fasttify_attributes'] %}>",
+            "{% liquid\n  echo block.fasttify_attributes\n%}",
+            "<a {{ section.fasttify_attrs }} {{ product.fasttify_attributes }}>"
+        );
+        assert_eq!(
+            summary(template),
+            [
+                usage(
+                    "section",
+                    "{{section.fasttify_attributes|default:''}}",
+                    Some("section")
+                ),
+                usage("block", "{{- block.fasttify_attributes -}}", Some("div")),
+                usage(
+                    "section",
+                    "{% echo section['fasttify_attributes'] %}",
+                    Some("p")
+                ),
+                usage("block", "echo block.fasttify_attributes", None),
+            ]
+        );
+        let report = detect_fasttify_attributes(template.to_string());
+        assert!(report.has_section_attributes && report.has_block_attributes);
+        assert!(!report.root_missing_attributes);
+    }
+
+    #[test]
+    fn test_root_element() {
+        let cases = [
+            ("<div class=\"a\"><p {{ section.fasttify_attributes }}></p></div>", Some("div"), true),
+            (
+                "{% style %}.a {}{% endstyle %}<link rel=\"stylesheet\"><script></script>\n<section {{ section.fasttify_attributes }}>",
+                Some("section"),
+                false,
+            ),
+            ("{% schema %}{\"name\": \"<div>\"}{% endschema %}", None, false),
+            ("<!-- <div> -->{% comment %}<div>{% endcomment %}<article>", Some("article"), true),
+        ];
+        for (template, root, missing) in cases {
+            let report = detect_fasttify_attributes(template.to_string());
+            assert_eq!(
+                report.root_element.map(|e| e.name).as_deref(),
+                root,
+                "{}",
+                template
+            );
+            assert_eq!(report.root_missing_attributes, missing, "{}", template);
+        }
+        let report = detect_fasttify_attributes("x\n  <div>".to_string());
+        let span = report.root_element.unwrap().span;
+        assert_eq!((span.start, span.end, span.line, span.column), (4, 9, 2, 3));
+    }
+
+    #[test]
+    fn test_inject_fasttify_attributes() {
+        let cases = [
+            (
+                "{% style %}{% endstyle %}<DIV class=\"a\">x</DIV>",
+                "{% style %}{% endstyle %}<DIV {{ section.fasttify_attributes }} class=\"a\">x</DIV>",
+            ),
+            ("<div {{ section.fasttify_attributes }}>", "<div {{ section.fasttify_attributes }}>"),
+            ("no markup", "no markup"),
+        ];
+        for (template, expected) in cases {
+            assert_eq!(inject_fasttify_attributes(template.to_string()), expected);
+        }
+    }
+}
@@ -0,0 +1,487 @@
+/*
+ * Copyright 2025 Fasttify LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Filters available to the native renderer.
+//!
+//! The standard Liquid filters plus the crate's native text filters, all
+//! working on runtime values. Platform filters (`t`, `money`, `asset_url`,
+//! `inline_asset_content`...) stay in TypeScript and are reached through
+//! the render host.
+
+use std::borrow::Cow;
+
+use serde_json::Value;
+
+use super::value::{
+    equals, is_integer, is_truthy, iterate, number, property, size, to_number, to_text,
+};
+use crate::filters::{
+    append, escape_into, excerpt, handleize, markdown, newline_to_br, pluralize, prepend,
+    reading_time, sanitize_with_policy, simple_format, strip_html, strip_newlines, truncate,
+    unescape_str, word_count, ExcerptOptions, MarkdownOptions,
+};
+
+/// Applies the filter `name` to `input`.
+///
+/// Returns `None` if no filter with that name exists, and an error message
+/// if the arguments are invalid.
+pub fn apply_filter(
+    name: &str,
+    input: &Value,
+    args: &[Value],
+    kwargs: &[(String, Value)],
+) -> Option<Result<Value, String>> {
+    let text = || to_text(input);
+    let arg = |i: usize| args.get(i).unwrap_or(&Value::Null);
+    let text_arg = |i: usize| args.get(i).map_or(Cow::Borrowed(""), to_text);
+    let kwarg = |name: &str| kwargs.iter().find(|(k, _)| k == name).map(|(_, v)| v);
+
+    let value = match name {
+        // Strings
+        "append" => append(Some(text().into_owned()), Some(text_arg(0).into_owned())).into(),
+        "prepend" => prepend(Some(text().into_owned()), Some(text_arg(0).into_owned())).into(),
+        "upcase" => text().to_uppercase().into(),
+        "downcase" => text().to_lowercase().into(),
+        "capitalize" => {
+            let text = text();
+            let mut chars = text.chars();
+            match chars.next() {
+                Some(first) => (first.to_uppercase().collect::<String>()
+                    + &chars.as_str().to_lowercase())
+                    .into(),
+                None => String::new().into(),
+            }
+        }
+        "strip" => text().trim().into(),
+        "lstrip" => text().trim_start().into(),
+        "rstrip" => text().trim_end().into(),
+        "strip_html" => strip_html(Some(text().into_owned())).into(),
+        "strip_newlines" => strip_newlines(Some(text().into_owned())).into(),
+        "newline_to_br" => newline_to_br(Some(text().into_owned())).into(),
+        "escape" => {
+            let mut out = String::new();
+            escape_into(&text(), &mut out);
+            out.into()
+        }
+        "escape_once" => {
+            let mut out = String::new();
+            escape_into(&unescape_str(&text()), &mut out);
+            out.into()
+        }
+        "unescape_html" => unescape_str(&text()).into(),
+        "url_encode" => url_encode(&text()).into(),
+        "replace" => text().replace(text_arg(0).as_ref(), &text_arg(1)).into(),
+        "replace_first" => text()
+            .replacen(text_arg(0).as_ref(), &text_arg(1), 1)
+            .into(),
+        "remove" => text().replace(text_arg(0).as_ref(), "").into(),
+        "remove_first" => text().replacen(text_arg(0).as_ref(), "", 1).into(),
+        "split" => split(&text(), &text_arg(0)),
+        "truncate" => {
+            let length = args.first().map(|v| to_number(v).max(0.0) as u32);
+            let ellipsis = args.get(1).map(|v| to_text(v).into_owned());
+            truncate(Some(text().into_owned()), length, ellipsis).into()
+        }
+        "truncatewords" => {
+            let limit = args.first().map_or(15.0, to_number).max(1.0) as usize;
+            let ending = args.get(1).map_or(Cow::Borrowed("..."), to_text);
+            let text = text();
+            let words: Vec<&str> = text.split_whitespace().collect();
+            if words.len() <= limit {
+                text.into_owned().into()
+            } else {
+                (words[..limit].join(" ") + &ending).into()
+            }
+        }
+        "slice" => slice(
+            input,
+            to_number(arg(0)) as i64,
+            args.get(1).map(|v| to_number(v) as i64),
+        ),
+        "handleize" | "handle" => handleize(Some(text().into_owned())).into(),
+        "pluralize" => pluralize(
+            to_number(input) as i32,
+            text_arg(0).into_owned(),
+            args.get(1).map(|v| to_text(v).into_owned()),
+        )
+        .into(),
+        "default" => match input {
+            Value::Null => arg(0).clone(),
+            Value::String(s) if s.is_empty() => arg(0).clone(),
+            _ => input.clone(),
+        },
+        "json" => serde_json::to_string(input).unwrap_or_default().into(),
+
+        // Rich text
+        "markdown" => {
+            let options = MarkdownOptions {
+                heading_offset: kwarg("heading_offset").map(|v| to_number(v) as u32),
+                policy: kwarg("policy").map(|v| to_text(v).into_owned()),
+            };
+            return Some(
+                markdown(Some(text().into_owned()), Some(options))
+                    .map(Value::from)
+                    .map_err(|e| e.reason),
+            );
+        }
+        "sanitize_html" => {
+            let policy = args.first().map_or(Cow::Borrowed("basic"), to_text);
+            return Some(
+                sanitize_with_policy(&text(), &policy)
+                    .map(Value::from)
+                    .map_err(|e| e.reason),
+            );
+        }
+        "simple_format" => {
+            let class = args.first().map(|v| to_text(v).into_owned());
+            simple_format(Some(text().into_owned()), class).into()
+        }
+        "excerpt" => {
+            let options = ExcerptOptions {
+                unit: kwarg("unit").map(|v| to_text(v).into_owned()),
+                ellipsis: kwarg("ellipsis").map(|v| to_text(v).into_owned()),
+            };
+            let limit = args.first().map(|v| to_number(v).max(0.0) as u32);
+            return Some(
+                excerpt(Some(text().into_owned()), limit, Some(options))
+                    .map(Value::from)
+                    .map_err(|e| e.reason),
+            );
+        }
+        "word_count" => word_count(Some(text().into_owned())).into(),
+        "reading_time" => {
+            let wpm = args.first().map(|v| to_number(v).max(0.0) as u32);
+            reading_time(Some(text().into_owned()), wpm).into()
+        }
+
+        // Arrays
+        "size" => size(input).into(),
+        "first" => match input {
+            Value::Array(items) => items.first().cloned().unwrap_or(Value::Null),
+            _ => property(input, "first").map_or(Value::Null, Cow::into_owned),
+        },
+        "last" => match input {
+            Value::Array(items) => items.last().cloned().unwrap_or(Value::Null),
+            _ => property(input, "last").map_or(Value::Null, Cow::into_owned),
+        },
+        "join" => {
+            let separator = args.first().map_or(Cow::Borrowed(" "), to_text);
+            let items = iterate(input.clone());
+            let parts: Vec<Cow<str>> = items.iter().map(to_text).collect();
+            parts.join(separator.as_ref()).into()
+        }
+        "reverse" => match input {
+            Value::Array(items) => items.iter().rev().cloned().collect(),
+            _ => input.clone(),
+        },
+        "sort" | "sort_natural" => {
+            let mut items = iterate(input.clone());
+            let key = args.first().map(to_text);
+            let natural = name == "sort_natural";
+            items.sort_by(|a, b| {
+                let (a, b) = match &key {
+                    Some(key) => (
+                        property(a, key).map_or(Value::Null, Cow::into_owned),
+                        property(b, key).map_or(Value::Null, Cow::into_owned),
+                    ),
+                    None => (a.clone(), b.clone()),
+                };
+                sort_order(&a, &b, natural)
+            });
+            Value::Array(items)
+        }
+        "uniq" => {
+            let mut unique: Vec<Value> = Vec::new();
+            for item in iterate(input.clone()) {
+                if !unique.iter().any(|seen| equals(seen, &item)) {
+                    unique.push(item);
+                }
+            }
+            Value::Array(unique)
+        }
+        "compact" => iterate(input.clone())
+            .into_iter()
+            .filter(|item| !item.is_null())
+            .collect(),
+        "concat" => {
+            let mut items = iterate(input.clone());
+            items.extend(iterate(arg(0).clone()));
+            Value::Array(items)
+        }
+        "map" => {
+            let key = text_arg(0);
+            iterate(input.clone())
+                .iter()
+                .map(|item| property(item, &key).map_or(Value::Null, Cow::into_owned))
+                .collect()
+        }
+        "where" => {
+            let key = text_arg(0);
+            iterate(input.clone())
+                .into_iter()
+                .filter(|item| {
+                    let found = property(item, &key);
+                    match (args.get(1), found) {
+                        (Some(expected), Some(found)) => equals(&found, expected),
+                        (None, Some(found)) => is_truthy(&found),
+                        _ => false,
+                    }
+                })
+                .collect()
+        }
+        "sum" => {
+            let key = args.first().map(to_text);
+            let mut all_integers = true;
+            let mut total = 0.0;
+            for item in iterate(input.clone()) {
+                let item = match &key {
+                    Some(key) => property(&item, key).map_or(Value::Null, Cow::into_owned),
+                    None => item,
+                };
+                all_integers &= is_integer(&item) || item.is_null();
+                total += to_number(&item);
+            }
+            if all_integers {
+                Value::from(total as i64)
+            } else {
+                number(total)
+            }
+        }
+
+        // Math
+        "plus" => number(to_number(input) + to_number(arg(0))),
+        "minus" => number(to_number(input) - to_number(arg(0))),
+        "times" => number(to_number(input) * to_number(arg(0))),
+        // Division by zero gives `Infinity` or `NaN`, as in LiquidJS
+        "divided_by" => {
+            let quotient = to_number(input) / to_number(arg(0));
+            // `divided_by: n, true` requests integer division, as in LiquidJS
+            if is_truthy(arg(1)) && args.len() > 1 {
+                number(quotient.floor())
+            } else {
+                number(quotient)
+            }
+        }
+        "modulo" => number(to_number(input) % to_number(arg(0))),
+        "abs" => number(to_number(input).abs()),
+        "ceil" => number(to_number(input).ceil()),
+        "floor" => number(to_number(input).floor()),
+        "round" => {
+            let factor = 10f64.powi(to_number(arg(0)) as i32);
+            number((to_number(input) * factor + 0.5).floor() / factor)
+        }
+        "at_least" => number(to_number(input).max(to_number(arg(0)))),
+        "at_most" => number(to_number(input).min(to_number(arg(0)))),
+
+        _ => return None,
+    };
+
+    Some(Ok(value))
+}
+
+/// `split`: an empty separator splits into characters; trailing empty
+/// strings are dropped.
+fn split(text: &str, separator: &str) -> Value {
+    let mut parts: Vec<Value> = if separator.is_empty() {
+        text.chars().map(|c| Value::from(c.to_string())).collect()
+    } else {
+        text.split(separator).map(Value::from).collect()
+    };
+    while parts.last().is_some_and(|last| last.as_str() == Some("")) {
+        parts.pop();
+    }
+    Value::Array(parts)
+}
+
+/// `slice`: characters of a string or elements of an array; a negative
+/// start counts from the end.
+fn slice(input: &Value, start: i64, length: Option<i64>) -> Value {
+    let length = length.unwrap_or(1).max(0) as usize;
+    let resolve = |len: usize| {
+        let start = if start < 0 { len as i64 + start } else { start };
+        start.clamp(0, len as i64) as usize
+    };
+
+    match input {
+        Value::Array(items) => {
+            let from = resolve(items.len());
+            items.iter().skip(from).take(length).cloned().collect()
+        }
+        _ => {
+            let text = to_text(input);
+            let from = resolve(text.chars().count());
+            text.chars()
+                .skip(from)
+                .take(length)
+                .collect::<String>()
+                .into()
+        }
+    }
+}
+
+fn sort_order(a: &Value, b: &Value, natural: bool) -> std::cmp::Ordering {
+    use std::cmp::Ordering;
+    match (a, b) {
+        (Value::Null, Value::Null) => Ordering::Equal,
+        // nil sorts last
+        (Value::Null, _) => Ordering::Greater,
+        (_, Value::Null) => Ordering::Less,
+        (Value::Number(_), Value::Number(_)) => to_number(a)
+            .partial_cmp(&to_number(b))
+            .unwrap_or(Ordering::Equal),
+        _ if natural => to_text(a).to_lowercase().cmp(&to_text(b).to_lowercase()),
+        _ => to_text(a).cmp(&to_text(b)),
+    }
+}
+
+/// Percent-encodes everything except unreserved characters; spaces become
+/// `+`.
+fn url_encode(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                out.push(byte as char)
+            }
+            b' ' => out.push('+'),
+            _ => out.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn apply(name: &str, input: Value, args: &[Value]) -> Value {
+        apply_filter(name, &input, args, &[]).unwrap().unwrap()
+    }
+
+    #[test]
+    fn test_string_filters() {
+        assert_eq!(
+            apply("capitalize", json!("hOLA mundo"), &[]),
+            json!("Hola mundo")
+        );
+        assert_eq!(
+            apply("append", json!(3), &[json!(" días")]),
+            json!("3 días")
+        );
+        assert_eq!(
+            apply("split", json!("a,b,,"), &[json!(",")]),
+            json!(["a", "b"])
+        );
+        assert_eq!(
+            apply("truncatewords", json!("uno dos tres"), &[json!(2)]),
+            json!("uno dos...")
+        );
+        assert_eq!(
+            apply("slice", json!("Liquid"), &[json!(-3), json!(2)]),
+            json!("ui")
+        );
+        assert_eq!(apply("url_encode", json!("a b&c"), &[]), json!("a+b%26c"));
+        assert_eq!(
+            apply("escape_once", json!("&lt;b&gt; & <i>"), &[]),
+            json!("&lt;b&gt; &amp; &lt;i&gt;")
+        );
+        assert_eq!(apply("default", json!(""), &[json!("N/A")]), json!("N/A"));
+        assert_eq!(
+            apply("default", json!(false), &[json!("N/A")]),
+            json!(false)
+        );
+    }
+
+    #[test]
+    fn test_array_filters() {
+        let products = json!([
+            {"title": "b", "price": 20, "available": true},
+            {"title": "a", "price": 10, "available": false},
+            {"title": "c", "price": null, "available": true}
+        ]);
+        assert_eq!(
+            apply("map", products.clone(), &[json!("title")]),
+            json!(["b", "a", "c"])
+        );
+        assert_eq!(
+            apply("where", products.clone(), &[json!("available")])
+                .as_array()
+                .unwrap()
+                .len(),
+            2
+        );
+        assert_eq!(
+            apply("where", products.clone(), &[json!("price"), json!(10)]),
+            json!([{"title": "a", "price": 10, "available": false}])
+        );
+        assert_eq!(
+            apply(
+                "map",
+                apply("sort", products.clone(), &[json!("price")]),
+                &[json!("title")]
+            ),
+            json!(["a", "b", "c"])
+        );
+        assert_eq!(apply("sum", products, &[json!("price")]), json!(30));
+        assert_eq!(
+            apply("join", json!([1, "a"]), &[json!(", ")]),
+            json!("1, a")
+        );
+        assert_eq!(apply("uniq", json!([1, 1.0, "1"]), &[]), json!([1, "1"]));
+        assert_eq!(apply("compact", json!([1, null, 2]), &[]), json!([1, 2]));
+    }
+
+    #[test]
+    fn test_math_filters() {
+        assert_eq!(apply("plus", json!("2"), &[json!(3)]), json!(5));
+        assert_eq!(apply("times", json!(2), &[json!(1.5)]), json!(3));
+        assert_eq!(apply("divided_by", json!(10), &[json!(4)]), json!(2.5));
+        assert_eq!(
+            apply("divided_by", json!(10), &[json!(4), json!(true)]),
+            json!(2)
+        );
+        assert_eq!(apply("modulo", json!(-7), &[json!(3)]), json!(-1));
+        assert_eq!(apply("round", json!(2.345), &[json!(2)]), json!(2.35));
+        assert_eq!(apply("at_most", json!(5), &[json!(3)]), json!(3));
+        assert_eq!(
+            apply("divided_by", json!(1), &[json!(0)]),
+            json!("Infinity")
+        );
+        assert_eq!(apply("modulo", json!(1), &[json!(0)]), json!("NaN"));
+        assert!(apply_filter("money", &json!(1), &[], &[]).is_none());
+    }
+
+    #[test]
+    fn test_native_filters() {
+        assert_eq!(
+            apply("handleize", json!("Camisa Azul"), &[]),
+            json!("camisa-azul")
+        );
+        assert_eq!(
+            apply_filter(
+                "markdown",
+                &json!("# Hola"),
+                &[],
+                &[("heading_offset".to_string(), json!(1))]
+            )
+            .unwrap()
+            .unwrap(),
+            json!("<h2 id=\"hola\">Hola</h2>\n")
+        );
+        assert_eq!(apply("word_count", json!("<p>uno dos</p>"), &[]), json!(2));
+    }
+}
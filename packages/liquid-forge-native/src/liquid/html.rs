@@ -0,0 +1,282 @@
+/*
+ * Copyright 2025 Fasttify LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! HTML start tags of a template.
+//!
+//! Liquid is blanked out before scanning, so `{% if %}` around an attribute
+//! or `{{ }}` inside a value doesn't break the markup apart. An output in
+//! place of an attribute name (`<img {{ attrs }}>`) marks the element as
+//! dynamic: it may carry any attribute.
+
+use memchr::memmem;
+
+use super::lexer::{lex, TokenKind};
+
+/// Fill for outputs, so they can be told apart from whitespace.
+const OUTPUT: u8 = 0x01;
+
+/// Liquid blocks whose body isn't HTML.
+const NON_HTML_BLOCKS: &[&str] = &["schema", "javascript", "stylesheet", "style"];
+
+/// Elements whose content is not markup.
+const RAW_TEXT_ELEMENTS: &[&str] = &["script", "style", "textarea", "title"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Attribute {
+    /// Lowercased name.
+    pub name: String,
+    /// The value as written, Liquid included; `None` for bare attributes.
+    pub value: Option<String>,
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Element {
+    /// Lowercased tag name.
+    pub name: String,
+    pub attributes: Vec<Attribute>,
+    /// Whether Liquid outputs attributes the scan can't see.
+    pub dynamic: bool,
+    /// Byte range of the start tag, `<` through `>`.
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Element {
+    pub fn attribute(&self, name: &str) -> Option<&Attribute> {
+        self.attributes.iter().find(|attr| attr.name == name)
+    }
+
+    pub fn has(&self, name: &str) -> bool {
+        self.attribute(name).is_some()
+    }
+}
+
+/// The template with Liquid replaced by bytes of the same length: `output`
+/// for outputs, spaces for tags, comments and the whole of the blocks named
+/// in `skip_blocks`. Text is kept, so offsets still point at the source.
+pub(crate) fn blank_liquid(source: &str, output: u8, skip_blocks: &[&str]) -> Vec<u8> {
+    let mut bytes = source.as_bytes().to_vec();
+    let mut skip_until: Option<String> = None;
+    for token in lex(source) {
+        let range = token.start..token.end();
+        if let Some(end) = &skip_until {
+            if token.name() == end {
+                skip_until = None;
+            }
+            bytes[range].fill(b' ');
+            continue;
+        }
+        match token.kind {
+            TokenKind::Text => {}
+            TokenKind::Output => bytes[range].fill(output),
+            _ => {
+                if skip_blocks.contains(&token.name()) {
+                    skip_until = Some(format!("end{}", token.name()));
+                }
+                bytes[range].fill(b' ');
+            }
+        }
+    }
+    bytes
+}
+
+fn is_name_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b':' | b'.')
+}
+
+fn is_attribute_end(byte: u8) -> bool {
+    byte.is_ascii_whitespace() || matches!(byte, b'/' | b'>' | b'=')
+}
+
+/// Every start tag of the template, in source order.
+pub(crate) fn start_tags(source: &str) -> Vec<Element> {
+    let bytes = blank_liquid(source, OUTPUT, NON_HTML_BLOCKS);
+    let len = bytes.len();
+    let mut elements = Vec::new();
+    let mut pos = 0;
+    while let Some(offset) = memchr::memchr(b'<', &bytes[pos..]) {
+        let start = pos + offset;
+        pos = start + 1;
+        if bytes[pos..].starts_with(b"!--") {
+            pos = memmem::find(&bytes[pos..], b"-->").map_or(len, |end| pos + end + 3);
+            continue;
+        }
+        if !bytes.get(pos).is_some_and(u8::is_ascii_alphabetic) {
+            continue;
+        }
+        let name_end = pos
+            + bytes[pos..]
+                .iter()
+                .take_while(|b| is_name_byte(**b))
+                .count();
+        let name = source[pos..name_end].to_ascii_lowercase();
+        pos = name_end;
+
+        let mut element = Element {
+            name,
+            attributes: Vec::new(),
+            dynamic: false,
+            start,
+            end: len,
+        };
+        while pos < len {
+            match bytes[pos] {
+                b'>' => {
+                    pos += 1;
+                    element.end = pos;
+                    break;
+                }
+                byte if byte.is_ascii_whitespace() || byte == b'/' => pos += 1,
+                _ => pos = attribute(source, &bytes, pos, &mut element),
+            }
+        }
+
+        if RAW_TEXT_ELEMENTS.contains(&element.name.as_str()) {
+            let close = format!("</{}", element.name);
+            let rest = bytes[pos..].to_ascii_lowercase();
+            pos = memmem::find(&rest, close.as_bytes()).map_or(len, |end| pos + end);
+        }
+        elements.push(element);
+    }
+    elements
+}
+
+/// Reads the attribute at `start` into `element`, returning the offset
+/// after it.
+fn attribute(source: &str, bytes: &[u8], start: usize, element: &mut Element) -> usize {
+    let len = bytes.len();
+    let mut pos = start;
+    while pos < len && !is_attribute_end(bytes[pos]) {
+        pos += 1;
+    }
+    // A stray `=` or quote: skip it so the scan makes progress
+    if pos == start {
+        return pos + 1;
+    }
+    let name = &bytes[start..pos];
+    if name.contains(&OUTPUT) {
+        element.dynamic = true;
+    }
+
+    let mut value = None;
+    let mut after_space = pos;
+    while after_space < len && bytes[after_space].is_ascii_whitespace() {
+        after_space += 1;
+    }
+    if bytes.get(after_space) == Some(&b'=') {
+        pos = after_space + 1;
+        while pos < len && bytes[pos].is_ascii_whitespace() {
+            pos += 1;
+        }
+        let value_start = pos;
+        match bytes.get(pos) {
+            Some(&quote @ (b'"' | b'\'')) => {
+                let close =
+                    memchr::memchr(quote, &bytes[pos + 1..]).map_or(len, |end| pos + 1 + end);
+                value = Some(source[value_start + 1..close].to_string());
+                pos = (close + 1).min(len);
+            }
+            _ => {
+                while pos < len && !bytes[pos].is_ascii_whitespace() && bytes[pos] != b'>' {
+                    pos += 1;
+                }
+                value = Some(source[value_start..pos].to_string());
+            }
+        }
+    }
+
+    if !name.contains(&OUTPUT) {
+        element.attributes.push(Attribute {
+            name: source[start..start + name.len()].to_ascii_lowercase(),
+            value,
+            start,
+            end: pos,
+        });
+    }
+    pos
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Summary = (String, Vec<(String, Option<String>)>, bool);
+
+    fn summary(source: &str) -> Vec<Summary> {
+        start_tags(source)
+            .into_iter()
+            .map(|element| {
+                let attributes = element
+                    .attributes
+                    .into_iter()
+                    .map(|attr| (attr.name, attr.value))
+                    .collect();
+                (element.name, attributes, element.dynamic)
+            })
+            .collect()
+    }
+
+    fn attr(name: &str, value: Option<&str>) -> (String, Option<String>) {
+        (name.to_string(), value.map(str::to_string))
+    }
+
+    #[test]
+    fn test_start_tags() {
+        assert_eq!(
+            summary(r#"<IMG Src="{{ image | image_url }}" alt=x loading='lazy' hidden/>"#),
+            [(
+                "img".to_string(),
+                vec![
+                    attr("src", Some("{{ image | image_url }}")),
+                    attr("alt", Some("x")),
+                    attr("loading", Some("lazy")),
+                    attr("hidden", None),
+                ],
+                false
+            )]
+        );
+        assert_eq!(
+            summary("<img {% if a %}width=\"1\"{% endif %} {{ attrs }}>"),
+            [("img".to_string(), vec![attr("width", Some("1"))], true)]
+        );
+    }
+
+    #[test]
+    fn test_skipped_content() {
+        let source = concat!(
+            "<!-- <img> -->{% comment %}<img>{% endcomment %}",
+            "<script>if (a<b) document.write('<img>')</script>",
+            "{% schema %}{\"html\": \"<img>\"}{% endschema %}",
+            "{% raw %}{{ x }}{% endraw %}<p>a < b</p>"
+        );
+        let names: Vec<String> = start_tags(source).into_iter().map(|e| e.name).collect();
+        assert_eq!(names, ["script", "p"]);
+    }
+
+    #[test]
+    fn test_element_range() {
+        let source = "ab<a href=\"{{ url }}\">x</a><img";
+        let elements = start_tags(source);
+        assert_eq!((elements[0].start, elements[0].end), (2, 22));
+        assert_eq!(
+            &source[elements[0].start..elements[0].end],
+            "<a href=\"{{ url }}\">"
+        );
+        assert_eq!((elements[1].start, elements[1].end), (27, source.len()));
+    }
+}
@@ -0,0 +1,482 @@
+/*
+ * Copyright 2025 Fasttify LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Tolerant JSON parser for hand-written theme JSON.
+//!
+//! Accepts the JSON5 syntax theme authors actually write: `//` and `/* */`
+//! comments, trailing commas, single-quoted strings and unquoted keys,
+//! plus JSON5 numbers (hex, leading `+`, leading or trailing `.`). String
+//! contents are never touched, so a `//` inside a URL survives.
+//!
+//! Besides the value, parsing records the byte range of every value by
+//! its JSON pointer, so diagnostics can point at the source.
+
+use std::collections::HashMap;
+
+use serde_json::{Map, Number, Value};
+
+/// How deep objects and arrays may nest, so that no document can exhaust
+/// the stack.
+const MAX_DEPTH: usize = 128;
+
+/// A syntax error at a byte offset of the parsed text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Json5Error {
+    pub message: String,
+    pub offset: usize,
+}
+
+/// A parsed document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Json5Document {
+    pub value: Value,
+    /// Byte range of every value, by JSON pointer (`""` is the root).
+    pub spans: HashMap<String, (usize, usize)>,
+}
+
+/// Escapes a key for use in a JSON pointer.
+pub fn pointer_token(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+/// Parses `source` as one JSON5 value.
+pub fn parse_json5(source: &str) -> Result<Json5Document, Json5Error> {
+    let mut parser = Parser {
+        source,
+        pos: 0,
+        spans: HashMap::new(),
+        depth: 0,
+    };
+    parser.skip_trivia()?;
+    let value = parser.value(&mut String::new())?;
+    parser.skip_trivia()?;
+    if parser.pos < source.len() {
+        return Err(parser.error("Unexpected content after the value"));
+    }
+    Ok(Json5Document {
+        value,
+        spans: parser.spans,
+    })
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    pos: usize,
+    spans: HashMap<String, (usize, usize)>,
+    /// Objects and arrays being parsed.
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, message: impl Into<String>) -> Json5Error {
+        Json5Error {
+            message: message.into(),
+            offset: self.pos,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.source[self.pos..].chars().next()
+    }
+
+    fn unexpected(&self) -> Json5Error {
+        match self.peek() {
+            Some(c) => self.error(format!("Unexpected character '{}'", c)),
+            None => self.error("Unexpected end of input"),
+        }
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Skips whitespace and comments.
+    fn skip_trivia(&mut self) -> Result<(), Json5Error> {
+        loop {
+            let rest = &self.source[self.pos..];
+            if rest.starts_with("//") {
+                self.pos += rest.find('\n').unwrap_or(rest.len());
+            } else if let Some(comment) = rest.strip_prefix("/*") {
+                match comment.find("*/") {
+                    Some(end) => self.pos += end + 4,
+                    None => return Err(self.error("Unterminated comment")),
+                }
+            } else {
+                match self.peek() {
+                    Some(c) if c.is_whitespace() || c == '\u{feff}' => self.pos += c.len_utf8(),
+                    _ => return Ok(()),
+                }
+            }
+        }
+    }
+
+    /// Parses the value at `pointer` and records its range.
+    fn value(&mut self, pointer: &mut String) -> Result<Value, Json5Error> {
+        let start = self.pos;
+        let value = match self.peek() {
+            Some('{' | '[') if self.depth >= MAX_DEPTH => {
+                return Err(self.error("Too deeply nested"));
+            }
+            Some(open @ ('{' | '[')) => {
+                self.depth += 1;
+                let value = if open == '{' {
+                    self.object(pointer)
+                } else {
+                    self.array(pointer)
+                };
+                self.depth -= 1;
+                value?
+            }
+            Some(quote @ ('"' | '\'')) => Value::String(self.string(quote)?),
+            Some(c) if c.is_ascii_digit() || matches!(c, '-' | '+' | '.') => self.number()?,
+            Some(c) if is_identifier_start(c) => match self.identifier() {
+                "true" => Value::Bool(true),
+                "false" => Value::Bool(false),
+                "null" => Value::Null,
+                word => {
+                    self.pos = start;
+                    return Err(self.error(format!("Unexpected identifier '{}'", word)));
+                }
+            },
+            _ => return Err(self.unexpected()),
+        };
+        self.spans.insert(pointer.clone(), (start, self.pos));
+        Ok(value)
+    }
+
+    fn object(&mut self, pointer: &mut String) -> Result<Value, Json5Error> {
+        self.pos += 1;
+        let mut map = Map::new();
+        loop {
+            self.skip_trivia()?;
+            if self.eat('}') {
+                return Ok(Value::Object(map));
+            }
+            let key = match self.peek() {
+                Some(quote @ ('"' | '\'')) => self.string(quote)?,
+                Some(c) if is_identifier_start(c) => self.identifier().to_string(),
+                _ => return Err(self.unexpected()),
+            };
+            self.skip_trivia()?;
+            if !self.eat(':') {
+                return Err(self.error("Expected ':' after object key"));
+            }
+            self.skip_trivia()?;
+
+            let len = pointer.len();
+            pointer.push('/');
+            pointer.push_str(&pointer_token(&key));
+            let value = self.value(pointer)?;
+            pointer.truncate(len);
+            // A repeated key replaces the earlier value, as in `JSON.parse`
+            map.insert(key, value);
+
+            self.skip_trivia()?;
+            if !self.eat(',') {
+                self.skip_trivia()?;
+                if self.eat('}') {
+                    return Ok(Value::Object(map));
+                }
+                return Err(self.error("Expected ',' or '}' after object member"));
+            }
+        }
+    }
+
+    fn array(&mut self, pointer: &mut String) -> Result<Value, Json5Error> {
+        self.pos += 1;
+        let mut items = Vec::new();
+        loop {
+            self.skip_trivia()?;
+            if self.eat(']') {
+                return Ok(Value::Array(items));
+            }
+            let len = pointer.len();
+            pointer.push('/');
+            pointer.push_str(&items.len().to_string());
+            items.push(self.value(pointer)?);
+            pointer.truncate(len);
+
+            self.skip_trivia()?;
+            if !self.eat(',') {
+                if self.eat(']') {
+                    return Ok(Value::Array(items));
+                }
+                return Err(self.error("Expected ',' or ']' after array element"));
+            }
+        }
+    }
+
+    fn identifier(&mut self) -> &'a str {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if !(is_identifier_start(c) || c.is_ascii_digit()) {
+                break;
+            }
+            self.pos += c.len_utf8();
+        }
+        &self.source[start..self.pos]
+    }
+
+    fn string(&mut self, quote: char) -> Result<String, Json5Error> {
+        let start = self.pos;
+        self.pos += 1;
+        let mut out = String::new();
+        loop {
+            let Some(c) = self.peek() else {
+                self.pos = start;
+                return Err(self.error("Unterminated string"));
+            };
+            self.pos += c.len_utf8();
+            match c {
+                _ if c == quote => return Ok(out),
+                '\n' | '\r' => {
+                    self.pos -= 1;
+                    return Err(self.error("Unescaped line break in string"));
+                }
+                '\\' => self.escape(&mut out)?,
+                c => out.push(c),
+            }
+        }
+    }
+
+    fn escape(&mut self, out: &mut String) -> Result<(), Json5Error> {
+        let escape_start = self.pos - 1;
+        let Some(c) = self.peek() else {
+            return Err(self.error("Unterminated string"));
+        };
+        self.pos += c.len_utf8();
+        match c {
+            'b' => out.push('\u{8}'),
+            'f' => out.push('\u{c}'),
+            'n' => out.push('\n'),
+            'r' => out.push('\r'),
+            't' => out.push('\t'),
+            'v' => out.push('\u{b}'),
+            '0' if !self.peek().is_some_and(|c| c.is_ascii_digit()) => out.push('\0'),
+            'x' => {
+                let code = self.hex(2, escape_start)?;
+                out.push(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+            }
+            'u' => {
+                let code = self.hex(4, escape_start)?;
+                let code = if (0xd800..0xdc00).contains(&code)
+                    && self.source[self.pos..].starts_with("\\u")
+                {
+                    let saved = self.pos;
+                    self.pos += 2;
+                    let low = self.hex(4, escape_start)?;
+                    if (0xdc00..0xe000).contains(&low) {
+                        0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00)
+                    } else {
+                        self.pos = saved;
+                        code
+                    }
+                } else {
+                    code
+                };
+                out.push(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+            }
+            // Line continuation
+            '\n' => {}
+            '\r' => {
+                self.eat('\n');
+            }
+            c if c.is_ascii_digit() => {
+                self.pos = escape_start;
+                return Err(self.error("Invalid escape sequence"));
+            }
+            c => out.push(c),
+        }
+        Ok(())
+    }
+
+    fn hex(&mut self, digits: usize, escape_start: usize) -> Result<u32, Json5Error> {
+        let code = self
+            .source
+            .get(self.pos..self.pos + digits)
+            .filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit()))
+            .and_then(|hex| u32::from_str_radix(hex, 16).ok());
+        match code {
+            Some(code) => {
+                self.pos += digits;
+                Ok(code)
+            }
+            None => {
+                self.pos = escape_start;
+                Err(self.error("Invalid escape sequence"))
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<Value, Json5Error> {
+        let start = self.pos;
+        let negative = self.eat('-') || {
+            self.eat('+');
+            false
+        };
+
+        if self.source[self.pos..].starts_with("0x") || self.source[self.pos..].starts_with("0X") {
+            self.pos += 2;
+            let digits_start = self.pos;
+            while self.peek().is_some_and(|c| c.is_ascii_hexdigit()) {
+                self.pos += 1;
+            }
+            let value = i64::from_str_radix(&self.source[digits_start..self.pos], 16);
+            return match value {
+                Ok(value) => Ok(Value::from(if negative { -value } else { value })),
+                Err(_) => {
+                    self.pos = start;
+                    Err(self.error("Invalid number"))
+                }
+            };
+        }
+
+        let mut text = String::from(if negative { "-" } else { "" });
+        let mut digits = 0;
+        let mut integer = true;
+        while let Some(c) = self.peek() {
+            match c {
+                '0'..='9' => digits += 1,
+                '.' if integer => integer = false,
+                'e' | 'E' if digits > 0 => {
+                    integer = false;
+                    text.push(c);
+                    self.pos += 1;
+                    if let Some(sign @ ('+' | '-')) = self.peek() {
+                        text.push(sign);
+                        self.pos += 1;
+                    }
+                    continue;
+                }
+                _ => break,
+            }
+            // JSON5 allows `.5` and `5.`, which Rust parses as written
+            text.push(c);
+            self.pos += 1;
+        }
+        if digits == 0 {
+            self.pos = start;
+            return Err(self.error("Invalid number"));
+        }
+
+        let number = if integer {
+            text.parse::<i64>().ok().map(Number::from)
+        } else {
+            None
+        };
+        let number = number.or_else(|| text.parse::<f64>().ok().and_then(Number::from_f64));
+        match number {
+            Some(number) => Ok(Value::Number(number)),
+            None => {
+                self.pos = start;
+                Err(self.error("Invalid number"))
+            }
+        }
+    }
+}
+
+fn is_identifier_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_' || c == '$'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_json5() {
+        let cases = [
+            (
+                r#"{"a": [1, 2.5, true, null]}"#,
+                json!({"a": [1, 2.5, true, null]}),
+            ),
+            (
+                "{\n  // comment\n  name: 'Hero', /* block */ \"tags\": [\"x\",],\n}",
+                json!({"name": "Hero", "tags": ["x"]}),
+            ),
+            (
+                r#"{"url": "https://x.com//a  b", "q": 'it\'s "ok"'}"#,
+                json!({"url": "https://x.com//a  b", "q": "it's \"ok\""}),
+            ),
+            (
+                "[0x1F, +3, .5, 5., -2e2, 1E-1]",
+                json!([31, 3, 0.5, 5.0, -200.0, 0.1]),
+            ),
+            (r#""\u00e9\ud83d\ude00\x41\t\/""#, json!("é😀A\t/")),
+            ("'uno \\\ndos'", json!("uno dos")),
+            (r#"{"a": 1, "a": 2}"#, json!({"a": 2})),
+        ];
+        for (source, expected) in cases {
+            assert_eq!(parse_json5(source).unwrap().value, expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let cases = [
+            (
+                "{\"a\": 1 \"b\": 2}",
+                "Expected ',' or '}' after object member",
+                8,
+            ),
+            ("[1, 2", "Expected ',' or ']' after array element", 5),
+            ("{\"a\" 1}", "Expected ':' after object key", 5),
+            ("\"abc", "Unterminated string", 0),
+            ("\"a\nb\"", "Unescaped line break in string", 2),
+            ("[1,, 2]", "Unexpected character ','", 3),
+            ("{a: undefined}", "Unexpected identifier 'undefined'", 4),
+            ("/* open", "Unterminated comment", 0),
+            ("{} x", "Unexpected content after the value", 3),
+            ("\"\\u12\"", "Invalid escape sequence", 1),
+            ("", "Unexpected end of input", 0),
+        ];
+        let deep = "[".repeat(200_000);
+        let error = parse_json5(&deep).unwrap_err();
+        assert_eq!(
+            (error.message.as_str(), error.offset),
+            ("Too deeply nested", MAX_DEPTH)
+        );
+        let nested = format!("{}{}", "[".repeat(MAX_DEPTH), "]".repeat(MAX_DEPTH));
+        assert!(parse_json5(&nested).is_ok());
+        for (source, message, offset) in cases {
+            let error = parse_json5(source).unwrap_err();
+            assert_eq!(
+                (error.message.as_str(), error.offset),
+                (message, offset),
+                "{}",
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn test_value_spans() {
+        let source = "{ \"settings\": [ { \"id\": \"a/b\" } ] }";
+        let document = parse_json5(source).unwrap();
+        let (start, end) = document.spans["/settings/0/id"];
+        assert_eq!(&source[start..end], "\"a/b\"");
+        let (start, end) = document.spans["/settings"];
+        assert_eq!(&source[start..end], "[ { \"id\": \"a/b\" } ]");
+        assert_eq!(document.spans[""], (0, source.len()));
+        assert_eq!(pointer_token("a/b~c"), "a~1b~0c");
+    }
+}
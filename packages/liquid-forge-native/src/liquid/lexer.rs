@@ -0,0 +1,512 @@
+/*
+ * Copyright 2025 Fasttify LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Liquid tokenizer.
+//!
+//! Splits a template into raw text, `{{ output }}` and `{% tag %}` tokens.
+//! The bodies of `{% raw %}`, `{% comment %}` and `{% doc %}` are emitted as
+//! single tokens so that nothing inside them is ever interpreted as Liquid.
+
+use memchr::memchr;
+
+use super::span::{LineIndex, SourceSpan};
+
+/// The kind of a template token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    /// Plain text between Liquid delimiters.
+    Text,
+    /// `{{ ... }}`
+    Output,
+    /// `{% ... %}`
+    Tag,
+    /// Body of a `{% raw %}` block.
+    Raw,
+    /// Body of a `{% comment %}` or `{% doc %}` block.
+    Comment,
+    /// Malformed input: an unterminated delimiter or an unclosed
+    /// `raw`/`comment`/`doc` block.
+    Error,
+}
+
+impl TokenKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TokenKind::Text => "text",
+            TokenKind::Output => "output",
+            TokenKind::Tag => "tag",
+            TokenKind::Raw => "raw",
+            TokenKind::Comment => "comment",
+            TokenKind::Error => "error",
+        }
+    }
+}
+
+/// A token borrowed from the template source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    /// The token exactly as it appears in the source.
+    pub source: &'a str,
+    /// For outputs and tags, the content between the delimiters without
+    /// whitespace-control dashes and surrounding whitespace. Equal to
+    /// `source` for every other kind.
+    pub markup: &'a str,
+    /// Byte offset of `source` in the template.
+    pub start: usize,
+    /// Byte offset of `markup` in the template.
+    pub markup_start: usize,
+    /// `{{-` / `{%-`
+    pub trim_left: bool,
+    /// `-}}` / `-%}`
+    pub trim_right: bool,
+    /// Why the token is an error, for `TokenKind::Error`.
+    pub error: Option<&'static str>,
+}
+
+impl<'a> Token<'a> {
+    /// Byte offset just past the token.
+    pub fn end(&self) -> usize {
+        self.start + self.source.len()
+    }
+
+    /// The tag name (`if`, `render`, `#`...), or `""` for other tokens.
+    pub fn name(&self) -> &'a str {
+        if self.kind != TokenKind::Tag {
+            return "";
+        }
+        tag_name(self.markup)
+    }
+
+    /// The tag markup after its name, trimmed.
+    pub fn args(&self) -> &'a str {
+        let name = self.name();
+        self.markup[name.len()..].trim()
+    }
+
+    /// Byte offset of `args()` in the template.
+    pub fn args_start(&self) -> usize {
+        let after_name = &self.markup[self.name().len()..];
+        let leading = after_name.len() - after_name.trim_start().len();
+        self.markup_start + self.name().len() + leading
+    }
+}
+
+/// Returns the name at the start of a tag's markup.
+///
+/// Inline comments (`{% # note %}`) are named `#` even without a space.
+pub fn tag_name(markup: &str) -> &str {
+    if markup.starts_with('#') {
+        return "#";
+    }
+    let end = markup
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(markup.len());
+    &markup[..end]
+}
+
+/// Splits a template into tokens. Never fails: malformed input becomes
+/// `TokenKind::Error` tokens and lexing resumes right after them.
+pub fn lex(source: &str) -> Vec<Token<'_>> {
+    let bytes = source.as_bytes();
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+
+    while pos < bytes.len() {
+        let Some(open) = find_opener(bytes, pos) else {
+            tokens.push(plain(TokenKind::Text, source, pos, bytes.len()));
+            break;
+        };
+        if open > pos {
+            tokens.push(plain(TokenKind::Text, source, pos, open));
+        }
+
+        let is_tag = bytes[open + 1] == b'%';
+        let closer = if is_tag { b'%' } else { b'}' };
+
+        let Some(close) = find_closer(bytes, open + 2, closer) else {
+            let end = find_opener(bytes, open + 2).unwrap_or(bytes.len());
+            let message = if is_tag {
+                "Tag is not terminated with '%}'"
+            } else {
+                "Output is not terminated with '}}'"
+            };
+            tokens.push(error(source, open, end, message));
+            pos = end;
+            continue;
+        };
+
+        let kind = if is_tag {
+            TokenKind::Tag
+        } else {
+            TokenKind::Output
+        };
+        let token = delimited(kind, source, open, close + 2);
+        pos = token.end();
+
+        let block = match token.name() {
+            "raw" => Some(("raw", TokenKind::Raw)),
+            "comment" => Some(("comment", TokenKind::Comment)),
+            "doc" => Some(("doc", TokenKind::Comment)),
+            _ => None,
+        };
+        tokens.push(token);
+
+        if let Some((name, kind)) = block {
+            match find_block_end(source, pos, name) {
+                Some(end) => {
+                    if end > pos {
+                        tokens.push(plain(kind, source, pos, end));
+                    }
+                    pos = end;
+                }
+                None => {
+                    let message = match name {
+                        "raw" => "'raw' block is never closed with 'endraw'",
+                        "doc" => "'doc' block is never closed with 'enddoc'",
+                        _ => "'comment' block is never closed with 'endcomment'",
+                    };
+                    tokens.push(error(source, pos, bytes.len(), message));
+                    pos = bytes.len();
+                }
+            }
+        }
+    }
+
+    tokens
+}
+
+fn plain(kind: TokenKind, source: &str, start: usize, end: usize) -> Token<'_> {
+    let text = &source[start..end];
+    Token {
+        kind,
+        source: text,
+        markup: text,
+        start,
+        markup_start: start,
+        trim_left: false,
+        trim_right: false,
+        error: None,
+    }
+}
+
+fn error<'a>(source: &'a str, start: usize, end: usize, message: &'static str) -> Token<'a> {
+    Token {
+        error: Some(message),
+        ..plain(TokenKind::Error, source, start, end)
+    }
+}
+
+/// Builds an output or tag token spanning `start..end` (delimiters included).
+fn delimited(kind: TokenKind, source: &str, start: usize, end: usize) -> Token<'_> {
+    let mut inner_start = start + 2;
+    let mut inner_end = end - 2;
+
+    let trim_left = source.as_bytes()[inner_start] == b'-';
+    if trim_left {
+        inner_start += 1;
+    }
+    let trim_right = inner_end > inner_start && source.as_bytes()[inner_end - 1] == b'-';
+    if trim_right {
+        inner_end -= 1;
+    }
+
+    let inner = &source[inner_start..inner_end];
+    let markup = inner.trim();
+    let leading = inner.len() - inner.trim_start().len();
+
+    Token {
+        kind,
+        source: &source[start..end],
+        markup,
+        start,
+        markup_start: inner_start + leading,
+        trim_left,
+        trim_right,
+        error: None,
+    }
+}
+
+/// Finds the next `{{` or `{%` at or after `from`.
+fn find_opener(bytes: &[u8], from: usize) -> Option<usize> {
+    let mut pos = from;
+    while let Some(offset) = memchr(b'{', &bytes[pos..]) {
+        let at = pos + offset;
+        match bytes.get(at + 1) {
+            Some(b'{') | Some(b'%') => return Some(at),
+            _ => pos = at + 1,
+        }
+    }
+    None
+}
+
+/// Finds the `}}` or `%}` closing a delimiter opened before `from`.
+///
+/// Quoted strings are skipped so `{{ "}}" }}` works. Running into another
+/// opener before the closer means the delimiter is unterminated; a string
+/// that never closes falls back to a quote-unaware search.
+fn find_closer(bytes: &[u8], from: usize, closer: u8) -> Option<usize> {
+    find_closer_with(bytes, from, closer, true)
+        .or_else(|| find_closer_with(bytes, from, closer, false))
+}
+
+fn find_closer_with(bytes: &[u8], from: usize, closer: u8, quotes: bool) -> Option<usize> {
+    let mut quote: Option<u8> = None;
+    let mut i = from;
+
+    while i + 1 < bytes.len() {
+        let b = bytes[i];
+        match quote {
+            Some(q) if b == q => quote = None,
+            Some(_) => {}
+            None if quotes && (b == b'"' || b == b'\'') => quote = Some(b),
+            None if b == closer && bytes[i + 1] == b'}' => return Some(i),
+            None if b == b'{' && matches!(bytes[i + 1], b'{' | b'%') => return None,
+            None => {}
+        }
+        i += 1;
+    }
+
+    None
+}
+
+/// Finds the start of the `{% end<name> %}` tag that closes a block whose
+/// body starts at `from`. Nested blocks of the same name are balanced
+/// (only meaningful for `comment`; `raw` and `doc` cannot nest).
+fn find_block_end(source: &str, from: usize, name: &str) -> Option<usize> {
+    let bytes = source.as_bytes();
+    let end_name = format!("end{}", name);
+    let mut depth = 0usize;
+    let mut pos = from;
+
+    while let Some(offset) = memchr(b'{', &bytes[pos..]) {
+        let at = pos + offset;
+        pos = at + 1;
+        if bytes.get(at + 1) != Some(&b'%') {
+            continue;
+        }
+
+        let Some(close) = find_closer_with(bytes, at + 2, b'%', false) else {
+            continue;
+        };
+        let tag = delimited(TokenKind::Tag, source, at, close + 2);
+        match tag.name() {
+            n if n == end_name => {
+                if depth == 0 {
+                    return Some(at);
+                }
+                depth -= 1;
+            }
+            n if n == name && name == "comment" => depth += 1,
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// A token as exposed to JavaScript.
+#[napi(object)]
+pub struct LiquidToken {
+    /// "text", "output", "tag", "raw", "comment" or "error".
+    pub kind: String,
+    /// The token exactly as it appears in the source.
+    pub source: String,
+    /// Content between the delimiters, for outputs and tags.
+    pub markup: Option<String>,
+    /// Tag name, for tags.
+    pub name: Option<String>,
+    pub trim_left: bool,
+    pub trim_right: bool,
+    pub span: SourceSpan,
+    /// Error description, for error tokens.
+    pub message: Option<String>,
+}
+
+/// Tokenizes a Liquid template.
+///
+/// Returns a flat list of text, output, tag, raw, comment and error tokens
+/// with byte offsets and 1-based line/column positions. Malformed input
+/// produces error tokens and tokenizing continues after them.
+///
+/// # Arguments
+///
+/// * `template` - The Liquid source
+///
+/// # Returns
+///
+/// The token list
+///
+/// # Examples
+///
+/// ```javascript
+/// tokenize("Hi {{- name }}")
+/// // [
+/// //   { kind: "text", source: "Hi ", ... },
+/// //   { kind: "output", source: "{{- name }}", markup: "name", trimLeft: true, ... }
+/// // ]
+/// ```
+#[napi]
+pub fn tokenize(template: String) -> Vec<LiquidToken> {
+    let index = LineIndex::new(&template);
+
+    lex(&template)
+        .into_iter()
+        .map(|token| {
+            let delimited = matches!(token.kind, TokenKind::Output | TokenKind::Tag);
+            LiquidToken {
+                kind: token.kind.as_str().to_string(),
+                source: token.source.to_string(),
+                markup: delimited.then(|| token.markup.to_string()),
+                name: (token.kind == TokenKind::Tag).then(|| token.name().to_string()),
+                trim_left: token.trim_left,
+                trim_right: token.trim_right,
+                span: index.span(token.start, token.end()),
+                message: token.error.map(str::to_string),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(source: &str) -> Vec<(TokenKind, &str)> {
+        lex(source)
+            .into_iter()
+            .map(|t| (t.kind, t.source))
+            .collect()
+    }
+
+    #[test]
+    fn test_lex_text_output_and_tags() {
+        assert_eq!(
+            kinds("Hi {{ name }}!{% if x %}y{% endif %}"),
+            vec![
+                (TokenKind::Text, "Hi "),
+                (TokenKind::Output, "{{ name }}"),
+                (TokenKind::Text, "!"),
+                (TokenKind::Tag, "{% if x %}"),
+                (TokenKind::Text, "y"),
+                (TokenKind::Tag, "{% endif %}"),
+            ]
+        );
+        assert!(lex("").is_empty());
+        assert_eq!(kinds("a { b } {c"), vec![(TokenKind::Text, "a { b } {c")]);
+    }
+
+    #[test]
+    fn test_lex_whitespace_control_and_markup() {
+        let tokens = lex("{{- product.title | upcase -}}{%-if a-%}");
+        assert_eq!(tokens[0].markup, "product.title | upcase");
+        assert!(tokens[0].trim_left && tokens[0].trim_right);
+        assert_eq!(tokens[0].markup_start, 4);
+        assert_eq!(tokens[1].name(), "if");
+        assert_eq!(tokens[1].args(), "a");
+        assert_eq!(tokens[1].args_start(), 36);
+        assert!(tokens[1].trim_left && tokens[1].trim_right);
+    }
+
+    #[test]
+    fn test_lex_quoted_delimiters() {
+        assert_eq!(
+            kinds("{{ \"}}\" | append: '%}' }}{% assign a = \"{%\" %}"),
+            vec![
+                (TokenKind::Output, "{{ \"}}\" | append: '%}' }}"),
+                (TokenKind::Tag, "{% assign a = \"{%\" %}"),
+            ]
+        );
+        // An unbalanced quote does not swallow the rest of the template
+        assert_eq!(
+            kinds("{{ \"oops }} b"),
+            vec![(TokenKind::Output, "{{ \"oops }}"), (TokenKind::Text, " b")]
+        );
+    }
+
+    #[test]
+    fn test_lex_raw_and_comment_bodies() {
+        assert_eq!(
+            kinds("{% raw %}{{ x }}{% if %}{%- endraw -%}"),
+            vec![
+                (TokenKind::Tag, "{% raw %}"),
+                (TokenKind::Raw, "{{ x }}{% if %}"),
+                (TokenKind::Tag, "{%- endraw -%}"),
+            ]
+        );
+        assert_eq!(
+            kinds("{% comment %}a{% comment %}b{% endcomment %}{{ c }}{% endcomment %}d"),
+            vec![
+                (TokenKind::Tag, "{% comment %}"),
+                (TokenKind::Comment, "a{% comment %}b{% endcomment %}{{ c }}"),
+                (TokenKind::Tag, "{% endcomment %}"),
+                (TokenKind::Text, "d"),
+            ]
+        );
+        assert_eq!(
+            kinds("{%- doc -%}@example {% render 'x' }{%- enddoc -%}"),
+            vec![
+                (TokenKind::Tag, "{%- doc -%}"),
+                (TokenKind::Comment, "@example {% render 'x' }"),
+                (TokenKind::Tag, "{%- enddoc -%}"),
+            ]
+        );
+        assert_eq!(lex("{% # note %}")[0].name(), "#");
+        assert_eq!(lex("{%#note%}")[0].name(), "#");
+    }
+
+    #[test]
+    fn test_lex_errors_are_recoverable() {
+        let tokens = lex("a {{ b {% if c %}d{% endif");
+        assert_eq!(
+            tokens.iter().map(|t| t.kind).collect::<Vec<_>>(),
+            vec![
+                TokenKind::Text,
+                TokenKind::Error,
+                TokenKind::Tag,
+                TokenKind::Text,
+                TokenKind::Error,
+            ]
+        );
+        assert_eq!(tokens[1].source, "{{ b ");
+        assert_eq!(tokens[1].error, Some("Output is not terminated with '}}'"));
+        assert_eq!(tokens[4].source, "{% endif");
+
+        let tokens = lex("{% raw %}{{ x }}");
+        assert_eq!(tokens[1].kind, TokenKind::Error);
+        assert_eq!(tokens[1].source, "{{ x }}");
+    }
+
+    #[test]
+    fn test_tokenize_spans() {
+        let tokens = tokenize("ñ\n  {%- if x %}".to_string());
+        assert_eq!(tokens[1].kind, "tag");
+        assert_eq!(tokens[1].name.as_deref(), Some("if"));
+        assert_eq!(tokens[1].markup.as_deref(), Some("if x"));
+        assert_eq!(
+            tokens[1].span,
+            SourceSpan {
+                start: 5,
+                end: 16,
+                line: 2,
+                column: 3,
+                end_line: 2,
+                end_column: 14,
+            }
+        );
+        assert_eq!(tokens[0].markup, None);
+    }
+}
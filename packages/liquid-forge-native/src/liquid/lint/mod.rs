@@ -0,0 +1,632 @@
+/*
+ * Copyright 2025 Fasttify LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Theme linting.
+//!
+//! Each rule can be turned off or given another severity in `LintConfig`.
+//! Templates silence rules with inline comments:
+//!
+//! - `{% # lint-disable img-alt, unused-assign %}` until the matching
+//!   `{% # lint-enable img-alt %}` or the end of the file;
+//! - `{% # lint-disable-next-line img-alt %}` for the following line.
+//!
+//! Without rule names they apply to every rule.
+
+mod rules;
+mod sarif;
+
+use std::collections::HashMap;
+
+use napi::{Error, Result, Status};
+use serde::Serialize;
+
+use super::dependencies::ThemeFile;
+use super::html::start_tags;
+use super::lexer::lex;
+use super::parser::parse_template;
+use super::span::{LineIndex, SourceSpan};
+use rules::{rule, Context, Rule, Severity, RULES};
+
+/// Lint settings.
+#[napi(object)]
+#[derive(Debug, Clone, Default)]
+pub struct LintConfig {
+    /// Severity by rule id: "error", "warning", "info" or "off". Rules not
+    /// listed keep their default severity.
+    pub rules: Option<HashMap<String, String>>,
+    /// Domains the shop is served from, reported by `hardcoded-shop-url`
+    /// besides the platform's shop subdomains.
+    pub shop_domains: Option<Vec<String>>,
+}
+
+/// A lint rule and its default severity.
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct LintRule {
+    pub id: String,
+    pub severity: String,
+    pub description: String,
+}
+
+/// A problem found by a lint rule.
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LintDiagnostic {
+    pub rule: String,
+    /// "error", "warning" or "info".
+    pub severity: String,
+    pub message: String,
+    pub path: String,
+    pub span: SourceSpan,
+}
+
+/// Diagnostics of one or more templates, in file and source order.
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LintResult {
+    pub diagnostics: Vec<LintDiagnostic>,
+    pub error_count: u32,
+    pub warning_count: u32,
+    pub info_count: u32,
+}
+
+impl LintResult {
+    fn new(diagnostics: Vec<LintDiagnostic>) -> Self {
+        let count = |severity: Severity| {
+            diagnostics
+                .iter()
+                .filter(|d| d.severity == severity.as_str())
+                .count() as u32
+        };
+        LintResult {
+            error_count: count(Severity::Error),
+            warning_count: count(Severity::Warning),
+            info_count: count(Severity::Info),
+            diagnostics,
+        }
+    }
+}
+
+/// A `LintConfig` checked against the rule set.
+struct Settings {
+    rules: Vec<(&'static Rule, Severity)>,
+    shop_domains: Vec<String>,
+}
+
+impl Settings {
+    fn new(config: Option<LintConfig>) -> Result<Self> {
+        let config = config.unwrap_or_default();
+        let overrides = config.rules.unwrap_or_default();
+        for (id, severity) in &overrides {
+            if rule(id).is_none() {
+                return Err(Error::new(
+                    Status::InvalidArg,
+                    format!("Unknown lint rule '{}'", id),
+                ));
+            }
+            if severity != "off" && Severity::parse(severity).is_none() {
+                return Err(Error::new(
+                    Status::InvalidArg,
+                    format!(
+                        "Invalid severity '{}' for rule '{}': expected error, warning, info or off",
+                        severity, id
+                    ),
+                ));
+            }
+        }
+        let rules = RULES
+            .iter()
+            .filter_map(|rule| match overrides.get(rule.id) {
+                Some(severity) => Severity::parse(severity).map(|severity| (rule, severity)),
+                None => Some((rule, rule.severity)),
+            })
+            .collect();
+        let shop_domains = config
+            .shop_domains
+            .unwrap_or_default()
+            .iter()
+            .map(|domain| domain.trim().to_ascii_lowercase())
+            .collect();
+        Ok(Settings {
+            rules,
+            shop_domains,
+        })
+    }
+}
+
+/// A range where a rule, or every rule when `rule` is `None`, is silenced.
+#[derive(Debug, PartialEq)]
+struct Suppression {
+    rule: Option<String>,
+    start: usize,
+    end: usize,
+}
+
+impl Suppression {
+    fn covers(&self, rule: &str, offset: usize) -> bool {
+        self.rule.as_deref().is_none_or(|silenced| silenced == rule)
+            && (self.start..self.end).contains(&offset)
+    }
+}
+
+/// The ranges silenced by `lint-disable` comments.
+fn suppressions(source: &str) -> Vec<Suppression> {
+    let mut suppressions = Vec::new();
+    // Start of each open `lint-disable`, by rule; `None` for all rules
+    let mut open: Vec<(Option<String>, usize)> = Vec::new();
+    for token in lex(source).into_iter().filter(|token| token.name() == "#") {
+        let mut words = token.markup[1..]
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|word| !word.is_empty());
+        let directive = words.next().unwrap_or_default();
+        let mut names: Vec<Option<String>> = words.map(|word| Some(word.to_string())).collect();
+        if names.is_empty() {
+            names.push(None);
+        }
+        match directive {
+            "lint-disable" => {
+                for name in names {
+                    if !open.iter().any(|(open_name, _)| *open_name == name) {
+                        open.push((name, token.end()));
+                    }
+                }
+            }
+            "lint-enable" => {
+                let closes_all = names == [None];
+                open.retain(|(name, start)| {
+                    let closed = closes_all || names.contains(name);
+                    if closed {
+                        suppressions.push(Suppression {
+                            rule: name.clone(),
+                            start: *start,
+                            end: token.start,
+                        });
+                    }
+                    !closed
+                });
+            }
+            "lint-disable-next-line" => {
+                let Some(newline) = source[token.end()..].find('\n') else {
+                    continue;
+                };
+                let start = token.end() + newline + 1;
+                let end = source[start..]
+                    .find('\n')
+                    .map_or(source.len(), |i| start + i);
+                suppressions.extend(
+                    names
+                        .into_iter()
+                        .map(|rule| Suppression { rule, start, end }),
+                );
+            }
+            _ => {}
+        }
+    }
+    suppressions.extend(open.into_iter().map(|(rule, start)| Suppression {
+        rule,
+        start,
+        end: source.len(),
+    }));
+    suppressions
+}
+
+fn lint_source(source: &str, path: &str, settings: &Settings) -> Vec<LintDiagnostic> {
+    let parsed = parse_template(source);
+    let elements = start_tags(source);
+    let context = Context {
+        path,
+        source,
+        nodes: &parsed.nodes,
+        elements: &elements,
+        shop_domains: &settings.shop_domains,
+    };
+    let suppressions = suppressions(source);
+    let index = LineIndex::new(source);
+
+    let mut diagnostics = Vec::new();
+    for (rule, severity) in &settings.rules {
+        for finding in (rule.check)(&context) {
+            if suppressions
+                .iter()
+                .any(|s| s.covers(rule.id, finding.start))
+            {
+                continue;
+            }
+            diagnostics.push(LintDiagnostic {
+                rule: rule.id.to_string(),
+                severity: severity.as_str().to_string(),
+                message: finding.message,
+                path: path.to_string(),
+                span: index.span(finding.start, finding.end),
+            });
+        }
+    }
+    diagnostics.sort_by_key(|diagnostic| diagnostic.span.start);
+    diagnostics
+}
+
+/// Lists the lint rules with their default severities.
+///
+/// # Returns
+///
+/// Every rule id, severity and description
+///
+/// # Examples
+///
+/// ```javascript
+/// lintRules()[0]
+/// // { id: "img-dimensions", severity: "warning", description: "..." }
+/// ```
+#[napi]
+pub fn lint_rules() -> Vec<LintRule> {
+    RULES
+        .iter()
+        .map(|rule| LintRule {
+            id: rule.id.to_string(),
+            severity: rule.severity.as_str().to_string(),
+            description: rule.description.to_string(),
+        })
+        .collect()
+}
+
+/// Lints a template.
+///
+/// # Arguments
+///
+/// * `template` - The Liquid source
+/// * `path` - Path of the template in the theme, reported with each diagnostic
+/// * `config` - Rule severities and shop domains
+///
+/// # Returns
+///
+/// The diagnostics in source order with their counts, or an error for an
+/// unknown rule or severity in `config`
+///
+/// # Examples
+///
+/// ```javascript
+/// lintTemplate('{% include "card" %}<img src="{{ src }}">', "sections/hero.liquid", {
+///   rules: { "img-alt": "off" },
+/// })
+/// // {
+/// //   diagnostics: [
+/// //     { rule: "deprecated-include", severity: "warning", message: "'include' is deprecated; use 'render'", ... },
+/// //     { rule: "img-dimensions", severity: "warning", message: "<img> is missing width and height", ... }
+/// //   ],
+/// //   errorCount: 0, warningCount: 2, infoCount: 0
+/// // }
+/// ```
+#[napi]
+pub fn lint_template(
+    template: String,
+    path: String,
+    config: Option<LintConfig>,
+) -> Result<LintResult> {
+    let settings = Settings::new(config)?;
+    Ok(LintResult::new(lint_source(&template, &path, &settings)))
+}
+
+/// Lints every Liquid file of a theme.
+///
+/// # Arguments
+///
+/// * `files` - Theme files; only `.liquid` files are linted
+/// * `config` - Rule severities and shop domains
+///
+/// # Returns
+///
+/// The diagnostics of every file, in the order of `files`
+///
+/// # Examples
+///
+/// ```javascript
+/// const result = lintTheme(files, { shopDomains: ["shop.example.com"] });
+/// result.errorCount // 1
+/// ```
+#[napi]
+pub fn lint_theme(files: Vec<ThemeFile>, config: Option<LintConfig>) -> Result<LintResult> {
+    let settings = Settings::new(config)?;
+    let diagnostics = files
+        .iter()
+        .filter(|file| file.path.ends_with(".liquid"))
+        .flat_map(|file| lint_source(&file.content, &file.path, &settings))
+        .collect();
+    Ok(LintResult::new(diagnostics))
+}
+
+/// Serializes a lint result.
+///
+/// # Arguments
+///
+/// * `result` - The result of `lintTemplate` or `lintTheme`
+/// * `format` - "json", or "sarif" for SARIF 2.1.0 as read by code scanning tools
+///
+/// # Returns
+///
+/// The serialized result, or an error for an unknown format
+///
+/// # Examples
+///
+/// ```javascript
+/// fs.writeFileSync("lint.sarif", formatLintResult(lintTheme(files), "sarif"));
+/// ```
+#[napi]
+pub fn format_lint_result(result: LintResult, format: String) -> Result<String> {
+    let value = match format.as_str() {
+        "json" => serde_json::to_value(&result),
+        "sarif" => Ok(sarif::to_sarif(&result)),
+        _ => {
+            return Err(Error::new(
+                Status::InvalidArg,
+                format!("Unknown lint format '{}': expected json or sarif", format),
+            ))
+        }
+    };
+    value
+        .and_then(|value| serde_json::to_string_pretty(&value))
+        .map_err(|e| Error::new(Status::GenericFailure, e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test_support::example_theme_files;
+
+    fn config(rules: &[(&str, &str)]) -> Option<LintConfig> {
+        Some(LintConfig {
+            rules: Some(
+                rules
+                    .iter()
+                    .map(|(id, severity)| (id.to_string(), severity.to_string()))
+                    .collect(),
+            ),
+            shop_domains: None,
+        })
+    }
+
+    fn found(template: &str, config: Option<LintConfig>) -> Vec<(String, String, u32)> {
+        lint_template(
+            template.to_string(),
+            "layout/theme.liquid".to_string(),
+            config,
+        )
+        .unwrap()
+        .diagnostics
+        .into_iter()
+        .map(|d| (d.rule, d.severity, d.span.line))
+        .collect()
+    }
+
+    fn found_rules(template: &str) -> Vec<String> {
+        found(template, None)
+            .into_iter()
+            .map(|(rule, ..)| rule)
+            .collect()
+    }
+
+    #[test]
+    fn test_rules() {
+        let cases: [(&str, &[&str]); 14] = [
+            (r#"<img src="a.png" width="1" height="1" alt="">"#, &[]),
+            (r#"<img src="a.png" alt="">"#, &["img-dimensions"]),
+            (r#"<img src="a.png" width="1" height="1">"#, &["img-alt"]),
+            (r#"<img {{ image | image_tag_attributes }}>"#, &[]),
+            (r#"<script src="a.js"></script>"#, &["parser-blocking-script"]),
+            (r#"<script src="a.js" defer></script><script>x()</script>"#, &[]),
+            (r#"<script type="module" src="a.js"></script>"#, &[]),
+            ("{% include 'card' %}", &["deprecated-include"]),
+            ("{{ product.featured_image | img_url: '300x' }}", &["deprecated-img-url"]),
+            ("{% assign a = 1 %}{% capture b %}x{% endcapture %}{{ b }}", &["unused-assign"]),
+            ("{% assign a = 1 %}{% for i in (1..a) %}{% endfor %}{% assign _b = 2 %}", &[]),
+            ("{{ product }}{{ product.title }}{{ shop | json }}", &["object-output"]),
+            (
+                "<a href=\"https://demo.fasttify.com/products/x\">x</a><img src=\"https://cdn.fasttify.com/a.png\" width=1 height=1 alt>",
+                &["hardcoded-shop-url"],
+            ),
+            ("{% assign url = 'http://demo.myshopify.com' %}{{ url }}", &["hardcoded-shop-url"]),
+        ];
+        for (template, expected) in cases {
+            assert_eq!(found_rules(template), expected, "{}", template);
+        }
+    }
+
+    #[test]
+    fn test_unused_assign_with_include() {
+        // The included snippet may read `a`
+        assert_eq!(
+            found_rules("{% assign a = 1 %}{% include 'card' %}"),
+            ["deprecated-include"]
+        );
+        assert_eq!(
+            found_rules("{% liquid\n  assign a = 1\n  echo a\n%}{% style %}{{ a }}{% endstyle %}"),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn test_shop_domains() {
+        let template = "<a href=\"https://www.shop.example.com/cart\">x</a> https://example.com";
+        let config = Some(LintConfig {
+            rules: None,
+            shop_domains: Some(vec!["Shop.Example.com".to_string()]),
+        });
+        let result = lint_template(
+            template.to_string(),
+            "layout/theme.liquid".to_string(),
+            config,
+        )
+        .unwrap();
+        assert_eq!(result.diagnostics.len(), 1);
+        assert_eq!(
+            result.diagnostics[0].message,
+            "'https://www.shop.example.com/cart' hard-codes the shop URL; use a relative URL"
+        );
+        assert_eq!(result.diagnostics[0].span.column, 10);
+    }
+
+    #[test]
+    fn test_config() {
+        let template = "{% include 'a' %}\n<img src=\"x\">";
+        assert_eq!(
+            found(
+                template,
+                config(&[("img-alt", "off"), ("deprecated-include", "error")])
+            ),
+            [
+                ("deprecated-include".to_string(), "error".to_string(), 1),
+                ("img-dimensions".to_string(), "warning".to_string(), 2),
+            ]
+        );
+        let result = lint_template(
+            template.to_string(),
+            "a.liquid".to_string(),
+            config(&[("img-alt", "info")]),
+        )
+        .unwrap();
+        assert_eq!(
+            (result.error_count, result.warning_count, result.info_count),
+            (0, 2, 1)
+        );
+
+        let error = |rules: &[(&str, &str)]| {
+            lint_template(String::new(), String::new(), config(rules))
+                .unwrap_err()
+                .reason
+        };
+        assert_eq!(
+            error(&[("img-size", "off")]),
+            "Unknown lint rule 'img-size'"
+        );
+        assert_eq!(
+            error(&[("img-alt", "fatal")]),
+            "Invalid severity 'fatal' for rule 'img-alt': expected error, warning, info or off"
+        );
+    }
+
+    #[test]
+    fn test_inline_disable() {
+        let template = concat!(
+            "<img>\n",
+            "{% # lint-disable img-alt %}\n",
+            "<img>\n",
+            "{% # lint-enable img-alt %}\n",
+            "<img>\n",
+            "{%- # lint-disable-next-line -%}\n",
+            "<img> {% include 'x' %}\n",
+            "{% #lint-disable %}\n",
+            "<img>\n",
+        );
+        let lines: Vec<(String, u32)> = found(template, config(&[("img-dimensions", "off")]))
+            .into_iter()
+            .map(|(rule, _, line)| (rule, line))
+            .collect();
+        assert_eq!(
+            lines,
+            [("img-alt".to_string(), 1), ("img-alt".to_string(), 5)]
+        );
+    }
+
+    #[test]
+    fn test_suppressions() {
+        let source = "{% # lint-disable a, b %}x{% # lint-enable b %}y{% # lint-enable %}";
+        assert_eq!(
+            suppressions(source),
+            [
+                Suppression {
+                    rule: Some("b".to_string()),
+                    start: 25,
+                    end: 26
+                },
+                Suppression {
+                    rule: Some("a".to_string()),
+                    start: 25,
+                    end: 48
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lint_theme() {
+        let file = |path: &str, content: &str| ThemeFile {
+            path: path.to_string(),
+            content: content.to_string(),
+        };
+        let files = vec![
+            file("layout/theme.liquid", "<script src=\"a.js\"></script>"),
+            file("sections/header.liquid", "<script src=\"a.js\"></script>"),
+            file("assets/app.js", "{% include 'a' %}"),
+            file("snippets/card.liquid", "{{ collection }}"),
+        ];
+        let result = lint_theme(files, None).unwrap();
+        let found: Vec<(&str, &str)> = result
+            .diagnostics
+            .iter()
+            .map(|d| (d.path.as_str(), d.rule.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("layout/theme.liquid", "parser-blocking-script"),
+                ("snippets/card.liquid", "object-output"),
+            ]
+        );
+        assert_eq!(result.error_count, 2);
+    }
+
+    #[test]
+    fn test_format_lint_result() {
+        let result = lint_template(
+            "<img>".to_string(),
+            "snippets/a.liquid".to_string(),
+            config(&[("img-dimensions", "off")]),
+        )
+        .unwrap();
+        let json: serde_json::Value =
+            serde_json::from_str(&format_lint_result(result.clone(), "json".to_string()).unwrap())
+                .unwrap();
+        assert_eq!(json["warningCount"], 1);
+        assert_eq!(json["diagnostics"][0]["rule"], "img-alt");
+        assert_eq!(json["diagnostics"][0]["span"]["endColumn"], 6);
+
+        let error = format_lint_result(result, "xml".to_string()).unwrap_err();
+        assert_eq!(
+            error.reason,
+            "Unknown lint format 'xml': expected json or sarif"
+        );
+    }
+
+    #[test]
+    fn test_example_themes() {
+        let files = example_theme_files("liquid")
+            .into_iter()
+            .map(|path| ThemeFile {
+                path: path.to_string_lossy().to_string(),
+                content: std::fs::read_to_string(&path).unwrap(),
+            })
+            .collect();
+        let result = lint_theme(files, None).unwrap();
+        let objects: Vec<&LintDiagnostic> = result
+            .diagnostics
+            .iter()
+            .filter(|d| d.rule == "object-output")
+            .collect();
+        assert!(objects.is_empty(), "{:?}", objects);
+    }
+}
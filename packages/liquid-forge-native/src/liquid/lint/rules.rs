@@ -0,0 +1,536 @@
+/*
+ * Copyright 2025 Fasttify LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! The lint rules.
+
+use std::collections::HashSet;
+
+use memchr::memmem;
+
+use super::super::ast::{walk, Condition, Expr, Filtered, Node, PathSegment};
+use super::super::html::Element;
+use super::super::lexer::{lex, TokenKind};
+use super::super::syntax::applied_filters;
+
+/// How serious a finding is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+impl Severity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Info => "info",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "error" => Some(Severity::Error),
+            "warning" => Some(Severity::Warning),
+            "info" => Some(Severity::Info),
+            _ => None,
+        }
+    }
+}
+
+/// What a rule sees of a template.
+pub(crate) struct Context<'a> {
+    /// Path of the template in the theme, like `layout/theme.liquid`.
+    pub path: &'a str,
+    pub source: &'a str,
+    pub nodes: &'a [Node],
+    pub elements: &'a [Element],
+    /// Lowercased domains the shop is served from.
+    pub shop_domains: &'a [String],
+}
+
+/// A problem found by a rule, as a byte range of the template.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Finding {
+    pub message: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Finding {
+    fn new(message: String, start: usize, end: usize) -> Self {
+        Finding {
+            message,
+            start,
+            end,
+        }
+    }
+}
+
+pub(crate) struct Rule {
+    pub id: &'static str,
+    pub severity: Severity,
+    pub description: &'static str,
+    pub check: fn(&Context) -> Vec<Finding>,
+}
+
+/// Every rule, with its default severity.
+pub(crate) const RULES: &[Rule] = &[
+    Rule {
+        id: "img-dimensions",
+        severity: Severity::Warning,
+        description:
+            "<img> elements need width and height so the page doesn't shift as images load",
+        check: img_dimensions,
+    },
+    Rule {
+        id: "img-alt",
+        severity: Severity::Warning,
+        description: "<img> elements need alt text",
+        check: img_alt,
+    },
+    Rule {
+        id: "parser-blocking-script",
+        severity: Severity::Error,
+        description:
+            "External scripts in layouts need defer or async so they don't block page parsing",
+        check: parser_blocking_script,
+    },
+    Rule {
+        id: "deprecated-include",
+        severity: Severity::Warning,
+        description: "'include' is deprecated in favor of 'render'",
+        check: deprecated_include,
+    },
+    Rule {
+        id: "deprecated-img-url",
+        severity: Severity::Warning,
+        description: "'img_url' is deprecated in favor of 'image_url'",
+        check: deprecated_img_url,
+    },
+    Rule {
+        id: "unused-assign",
+        severity: Severity::Warning,
+        description: "Variables that are assigned or captured but never used",
+        check: unused_assign,
+    },
+    Rule {
+        id: "object-output",
+        severity: Severity::Error,
+        description: "Outputs of whole objects, which don't render anything useful",
+        check: object_output,
+    },
+    Rule {
+        id: "hardcoded-shop-url",
+        severity: Severity::Warning,
+        description: "Absolute URLs to the shop, which break on other domains",
+        check: hardcoded_shop_url,
+    },
+];
+
+pub(crate) fn rule(id: &str) -> Option<&'static Rule> {
+    RULES.iter().find(|rule| rule.id == id)
+}
+
+fn images<'a>(context: &'a Context) -> impl Iterator<Item = &'a Element> {
+    context
+        .elements
+        .iter()
+        .filter(|element| element.name == "img" && !element.dynamic)
+}
+
+fn img_dimensions(context: &Context) -> Vec<Finding> {
+    images(context)
+        .filter_map(|img| {
+            let missing: Vec<&str> = ["width", "height"]
+                .into_iter()
+                .filter(|name| !img.has(name))
+                .collect();
+            (!missing.is_empty()).then(|| {
+                let message = format!("<img> is missing {}", missing.join(" and "));
+                Finding::new(message, img.start, img.end)
+            })
+        })
+        .collect()
+}
+
+fn img_alt(context: &Context) -> Vec<Finding> {
+    images(context)
+        .filter(|img| !img.has("alt"))
+        .map(|img| Finding::new("<img> is missing alt".to_string(), img.start, img.end))
+        .collect()
+}
+
+/// Whether the template is a layout, which renders the `<head>`.
+fn is_layout(path: &str) -> bool {
+    let path = path.replace('\\', "/");
+    path.starts_with("layout/") || path.contains("/layout/")
+}
+
+/// Only layouts are checked: sections and snippets are rendered inside the
+/// body, after the content a blocking script would hold up.
+fn parser_blocking_script(context: &Context) -> Vec<Finding> {
+    if !is_layout(context.path) {
+        return Vec::new();
+    }
+    context
+        .elements
+        .iter()
+        .filter(|script| {
+            let module = script
+                .attribute("type")
+                .and_then(|attr| attr.value.as_deref())
+                .is_some_and(|value| value.trim().eq_ignore_ascii_case("module"));
+            script.name == "script"
+                && !script.dynamic
+                && script.has("src")
+                && !script.has("defer")
+                && !script.has("async")
+                && !module
+        })
+        .map(|script| {
+            let message = "<script src> without defer or async blocks page parsing".to_string();
+            Finding::new(message, script.start, script.end)
+        })
+        .collect()
+}
+
+fn deprecated_include(context: &Context) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    walk(context.nodes, &mut |node| {
+        if let Node::Include(partial) = node {
+            let message = "'include' is deprecated; use 'render'".to_string();
+            let span = partial.span;
+            findings.push(Finding::new(
+                message,
+                span.start as usize,
+                span.end as usize,
+            ));
+        }
+    });
+    findings
+}
+
+fn deprecated_img_url(context: &Context) -> Vec<Finding> {
+    applied_filters(context.nodes)
+        .into_iter()
+        .filter(|filter| filter.name == "img_url")
+        .map(|filter| {
+            let message = "'img_url' is deprecated; use 'image_url'".to_string();
+            Finding::new(
+                message,
+                filter.span.start as usize,
+                filter.span.end as usize,
+            )
+        })
+        .collect()
+}
+
+/// Collects the root names of every variable read in a template.
+#[derive(Default)]
+struct References {
+    names: HashSet<String>,
+}
+
+impl References {
+    fn expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Variable { name, path } => {
+                self.names.insert(name.clone());
+                for step in path {
+                    if let PathSegment::Index { expr } = step {
+                        self.expr(expr);
+                    }
+                }
+            }
+            Expr::Range { start, end } => {
+                self.expr(start);
+                self.expr(end);
+            }
+            _ => {}
+        }
+    }
+
+    fn filtered(&mut self, filtered: &Filtered) {
+        self.expr(&filtered.expr);
+        for filter in &filtered.filters {
+            for arg in &filter.args {
+                self.expr(arg);
+            }
+            for arg in &filter.kwargs {
+                self.expr(&arg.value);
+            }
+        }
+    }
+
+    fn condition(&mut self, condition: &Condition) {
+        match condition {
+            Condition::Test { expr } => self.expr(expr),
+            Condition::Compare { left, right, .. } => {
+                self.expr(left);
+                self.expr(right);
+            }
+            Condition::And { left, right } | Condition::Or { left, right } => {
+                self.condition(left);
+                self.condition(right);
+            }
+            Condition::Filtered { condition, filters } => {
+                self.condition(condition);
+                for filter in filters {
+                    for arg in filter
+                        .args
+                        .iter()
+                        .chain(filter.kwargs.iter().map(|a| &a.value))
+                    {
+                        self.expr(arg);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Every identifier of markup the grammar keeps unparsed.
+    fn markup(&mut self, markup: &str) {
+        for word in markup.split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-')) {
+            if !word.is_empty() {
+                self.names.insert(word.to_string());
+            }
+        }
+    }
+
+    fn node(&mut self, node: &Node) {
+        match node {
+            Node::Output { value, .. } | Node::Echo { value, .. } | Node::Assign { value, .. } => {
+                self.filtered(value)
+            }
+            Node::If { branches, .. } | Node::Unless { branches, .. } => {
+                for branch in branches {
+                    self.condition(&branch.condition);
+                }
+            }
+            Node::Case { subject, whens, .. } => {
+                self.expr(subject);
+                for value in whens.iter().flat_map(|when| &when.values) {
+                    self.expr(value);
+                }
+            }
+            Node::For {
+                collection,
+                limit,
+                offset,
+                ..
+            } => {
+                self.expr(collection);
+                for value in limit.iter().chain(offset) {
+                    self.expr(value);
+                }
+            }
+            Node::Tablerow {
+                collection,
+                cols,
+                limit,
+                offset,
+                ..
+            } => {
+                self.expr(collection);
+                for value in cols.iter().chain(limit).chain(offset) {
+                    self.expr(value);
+                }
+            }
+            Node::Cycle { group, values, .. } => {
+                for value in group.iter().chain(values) {
+                    self.expr(value);
+                }
+            }
+            Node::Render(partial) | Node::Include(partial) => {
+                self.expr(&partial.template);
+                if let Some(variable) = &partial.variable {
+                    self.expr(variable);
+                }
+                for arg in &partial.args {
+                    self.filtered(&arg.value);
+                }
+            }
+            Node::Section { name, .. } | Node::Sections { name, .. } => self.expr(name),
+            Node::Layout {
+                name: Some(name), ..
+            } => self.expr(name),
+            Node::Paginate {
+                collection,
+                per_page,
+                ..
+            } => {
+                self.expr(collection);
+                if let Some(per_page) = per_page {
+                    self.expr(per_page);
+                }
+            }
+            Node::Form {
+                form_type,
+                args,
+                kwargs,
+                ..
+            } => {
+                self.expr(form_type);
+                for arg in args.iter().chain(kwargs.iter().map(|arg| &arg.value)) {
+                    self.expr(arg);
+                }
+            }
+            Node::Block { markup, .. } | Node::Tag { markup, .. } => self.markup(markup),
+            _ => {}
+        }
+    }
+}
+
+fn unused_assign(context: &Context) -> Vec<Finding> {
+    let mut references = References::default();
+    let mut assigned: Vec<(&str, usize, usize)> = Vec::new();
+    let mut includes = false;
+    walk(context.nodes, &mut |node| {
+        references.node(node);
+        match node {
+            Node::Assign { name, span, .. } | Node::Capture { name, span, .. } => {
+                assigned.push((name, span.start as usize, span.end as usize))
+            }
+            Node::Include(_) => includes = true,
+            _ => {}
+        }
+    });
+    // Included snippets share the template's variables, so any of them
+    // may be read there
+    if includes {
+        return Vec::new();
+    }
+
+    let mut reported = HashSet::new();
+    assigned
+        .into_iter()
+        .filter(|(name, ..)| {
+            !name.starts_with('_') && !references.names.contains(*name) && reported.insert(*name)
+        })
+        .map(|(name, start, end)| {
+            Finding::new(format!("'{}' is assigned but never used", name), start, end)
+        })
+        .collect()
+}
+
+/// Globals that are objects, so outputting them directly is a mistake.
+const OBJECTS: &[&str] = &[
+    "article",
+    "blog",
+    "cart",
+    "collection",
+    "collections",
+    "customer",
+    "forloop",
+    "linklists",
+    "localization",
+    "page",
+    "pages",
+    "paginate",
+    "policies",
+    "product",
+    "products",
+    "request",
+    "routes",
+    "section",
+    "settings",
+    "shop",
+];
+
+fn object_output(context: &Context) -> Vec<Finding> {
+    let mut assigned = HashSet::new();
+    let mut outputs = Vec::new();
+    walk(context.nodes, &mut |node| match node {
+        Node::Assign { name, .. } | Node::Capture { name, .. } => {
+            assigned.insert(name.as_str());
+        }
+        Node::Output { value, span } | Node::Echo { value, span } => {
+            if let (Expr::Variable { name, path }, true) = (&value.expr, value.filters.is_empty()) {
+                if path.is_empty() && OBJECTS.contains(&name.as_str()) {
+                    outputs.push((name.as_str(), *span));
+                }
+            }
+        }
+        _ => {}
+    });
+    outputs
+        .into_iter()
+        .filter(|(name, _)| !assigned.contains(name))
+        .map(|(name, span)| {
+            let message = format!("'{}' is an object; output one of its properties", name);
+            Finding::new(message, span.start as usize, span.end as usize)
+        })
+        .collect()
+}
+
+/// Subdomains of the platform that don't belong to a shop.
+const PLATFORM_SUBDOMAINS: &[&str] = &["www", "cdn", "orders"];
+
+fn is_shop_host(host: &str, shop_domains: &[String]) -> bool {
+    let host = host.to_ascii_lowercase();
+    if host.ends_with(".myshopify.com") {
+        return true;
+    }
+    if let Some(subdomain) = host.strip_suffix(".fasttify.com") {
+        return !subdomain.contains('.') && !PLATFORM_SUBDOMAINS.contains(&subdomain);
+    }
+    let bare = host.strip_prefix("www.").unwrap_or(&host);
+    shop_domains
+        .iter()
+        .any(|domain| domain.strip_prefix("www.").unwrap_or(domain) == bare)
+}
+
+fn is_url_end(byte: u8) -> bool {
+    byte.is_ascii_whitespace() || matches!(byte, b'"' | b'\'' | b'<' | b'>' | b')' | b'{' | b'}')
+}
+
+fn hardcoded_shop_url(context: &Context) -> Vec<Finding> {
+    let source = context.source;
+    let bytes = source.as_bytes();
+    let mut findings = Vec::new();
+    for token in lex(source) {
+        if matches!(token.kind, TokenKind::Comment) {
+            continue;
+        }
+        let mut pos = token.start;
+        let end = token.end();
+        while let Some(found) = memmem::find(&bytes[pos..end], b"://") {
+            let scheme_end = pos + found;
+            pos = scheme_end + 3;
+            let scheme_start = source[token.start..scheme_end]
+                .trim_end_matches(|c: char| c.is_ascii_alphabetic())
+                .len()
+                + token.start;
+            let scheme = &source[scheme_start..scheme_end];
+            if !(scheme.eq_ignore_ascii_case("http") || scheme.eq_ignore_ascii_case("https")) {
+                continue;
+            }
+            let url_end = (pos..end).find(|i| is_url_end(bytes[*i])).unwrap_or(end);
+            let host_end = (pos..url_end)
+                .find(|i| matches!(bytes[*i], b'/' | b'?' | b'#' | b':'))
+                .unwrap_or(url_end);
+            if is_shop_host(&source[pos..host_end], context.shop_domains) {
+                let url = &source[scheme_start..url_end];
+                let message = format!("'{}' hard-codes the shop URL; use a relative URL", url);
+                findings.push(Finding::new(message, scheme_start, url_end));
+            }
+            pos = url_end;
+        }
+    }
+    findings
+}
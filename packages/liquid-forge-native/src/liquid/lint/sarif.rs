@@ -0,0 +1,129 @@
+/*
+ * Copyright 2025 Fasttify LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! SARIF 2.1.0 output.
+
+use serde_json::{json, Value};
+
+use super::rules::{Severity, RULES};
+use super::LintResult;
+
+/// The SARIF `level` of a severity.
+fn level(severity: &str) -> &'static str {
+    match Severity::parse(severity) {
+        Some(Severity::Error) => "error",
+        Some(Severity::Warning) => "warning",
+        _ => "note",
+    }
+}
+
+/// Builds a SARIF log with one run. Columns count Unicode characters, as
+/// `SourceSpan` columns do.
+pub(super) fn to_sarif(result: &LintResult) -> Value {
+    let rules: Vec<Value> = RULES
+        .iter()
+        .map(|rule| {
+            json!({
+                "id": rule.id,
+                "shortDescription": { "text": rule.description },
+                "defaultConfiguration": { "level": level(rule.severity.as_str()) },
+            })
+        })
+        .collect();
+    let results: Vec<Value> = result
+        .diagnostics
+        .iter()
+        .map(|diagnostic| {
+            let span = &diagnostic.span;
+            json!({
+                "ruleId": diagnostic.rule,
+                "ruleIndex": RULES.iter().position(|rule| rule.id == diagnostic.rule),
+                "level": level(&diagnostic.severity),
+                "message": { "text": diagnostic.message },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": diagnostic.path },
+                        "region": {
+                            "startLine": span.line,
+                            "startColumn": span.column,
+                            "endLine": span.end_line,
+                            "endColumn": span.end_column,
+                        },
+                    },
+                }],
+            })
+        })
+        .collect();
+
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                },
+            },
+            "columnKind": "unicodeCodePoints",
+            "results": results,
+        }],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{lint_template, LintConfig};
+    use super::*;
+
+    #[test]
+    fn test_to_sarif() {
+        let config = LintConfig {
+            rules: Some([("img-alt".to_string(), "info".to_string())].into()),
+            shop_domains: None,
+        };
+        let result = lint_template(
+            "\n  <img>".to_string(),
+            "sections/hero.liquid".to_string(),
+            Some(config),
+        )
+        .unwrap();
+        let sarif = to_sarif(&result);
+        let run = &sarif["runs"][0];
+        assert_eq!(sarif["version"], "2.1.0");
+        assert_eq!(
+            run["tool"]["driver"]["rules"].as_array().unwrap().len(),
+            RULES.len()
+        );
+        assert_eq!(run["tool"]["driver"]["rules"][0]["id"], "img-dimensions");
+
+        let results = run["results"].as_array().unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0]["ruleId"], "img-dimensions");
+        assert_eq!(results[0]["ruleIndex"], 0);
+        assert_eq!(results[0]["level"], "warning");
+        assert_eq!(results[1]["ruleId"], "img-alt");
+        assert_eq!(results[1]["level"], "note");
+        assert_eq!(
+            results[1]["locations"][0]["physicalLocation"],
+            json!({
+                "artifactLocation": { "uri": "sections/hero.liquid" },
+                "region": { "startLine": 2, "startColumn": 3, "endLine": 2, "endColumn": 8 },
+            })
+        );
+    }
+}
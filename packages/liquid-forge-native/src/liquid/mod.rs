@@ -0,0 +1,58 @@
+/*
+ * Copyright 2025 Fasttify LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Liquid template tooling.
+//!
+//! This module contains:
+//!
+//! - the tokenizer and parser shared by every native analysis of Liquid
+//!   templates, so they all agree on the same grammar and spans;
+//! - the renderer built on them;
+//! - a binary format for precompiled templates.
+
+mod analysis;
+pub mod ast;
+mod bytecode;
+mod condition;
+mod dependencies;
+mod expr;
+mod fasttify_attributes;
+mod filters;
+pub(crate) mod html;
+mod json5;
+mod lexer;
+mod lint;
+mod parser;
+mod pipeline;
+mod render;
+mod schema;
+mod span;
+mod syntax;
+mod value;
+
+pub use analysis::*;
+pub use bytecode::*;
+pub use condition::*;
+pub use dependencies::*;
+pub use fasttify_attributes::*;
+pub use lexer::*;
+pub use lint::*;
+pub use parser::*;
+pub use pipeline::*;
+pub use render::*;
+pub use schema::*;
+pub use span::*;
+pub use syntax::*;
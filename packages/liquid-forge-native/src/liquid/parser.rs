@@ -0,0 +1,1191 @@
+/*
+ * Copyright 2025 Fasttify LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Liquid template parser.
+//!
+//! Builds an [`ast::Node`](super::ast::Node) tree from the token stream.
+//! Parsing never stops at the first problem: errors are collected and the
+//! parser recovers so callers always get a best-effort tree.
+
+use napi::{Error, Result};
+use serde::Serialize;
+
+use super::ast::{Arg, Branch, Condition, Expr, Filtered, Node, Partial, Span, When};
+use super::expr::{
+    parse_condition, parse_expression, parse_filtered, ExprError, ExprParser, Tok,
+    MAX_NESTING_DEPTH,
+};
+use super::lexer::{lex, Token, TokenKind};
+use super::span::{LineIndex, SourceSpan};
+
+/// Tags with a body closed by `end<name>`.
+const BLOCK_TAGS: &[&str] = &[
+    "if",
+    "unless",
+    "case",
+    "for",
+    "tablerow",
+    "capture",
+    "paginate",
+    "form",
+    "style",
+    "javascript",
+    "stylesheet",
+    "script",
+    "raw",
+    "comment",
+    "doc",
+    "schema",
+    "block",
+];
+
+/// A syntax problem found while parsing.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ParseError {
+    /// Stable identifier such as `unclosed-block` or `invalid-syntax`.
+    pub code: &'static str,
+    pub message: String,
+    pub span: Span,
+}
+
+/// The parsed tree plus every error found on the way.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ParseResult {
+    pub nodes: Vec<Node>,
+    pub errors: Vec<ParseError>,
+}
+
+/// Parses a template into nodes, collecting errors.
+pub fn parse_template(source: &str) -> ParseResult {
+    let mut parser = Parser::new(source, lex(source));
+    let nodes = parser.document();
+    ParseResult {
+        nodes,
+        errors: parser.errors,
+    }
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    tokens: Vec<Token<'a>>,
+    pos: usize,
+    errors: Vec<ParseError>,
+    /// End tags of the blocks being parsed, innermost last.
+    open_blocks: Vec<Vec<String>>,
+    /// Blocks enclosing the `{% liquid %}` tag this parser reads, if any.
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(source: &'a str, tokens: Vec<Token<'a>>) -> Self {
+        Self {
+            source,
+            tokens,
+            pos: 0,
+            errors: Vec::new(),
+            open_blocks: Vec::new(),
+            depth: 0,
+        }
+    }
+
+    fn document(&mut self) -> Vec<Node> {
+        let (nodes, _) = self.body(&[]);
+        nodes
+    }
+
+    fn error(&mut self, code: &'static str, message: impl Into<String>, start: usize, end: usize) {
+        self.errors.push(ParseError {
+            code,
+            message: message.into(),
+            span: Span::new(start, end),
+        });
+    }
+
+    fn expr_error(&mut self, error: ExprError) {
+        self.error(error.code, error.message, error.start, error.end);
+    }
+
+    /// Unwraps a markup parse result, recording the error and falling back
+    /// to `fallback` so the enclosing block is still parsed.
+    fn recover<T>(&mut self, result: std::result::Result<T, ExprError>, fallback: T) -> T {
+        result.unwrap_or_else(|error| {
+            self.expr_error(error);
+            fallback
+        })
+    }
+
+    /// Byte offset just past the last consumed token.
+    fn last_end(&self) -> usize {
+        self.pos
+            .checked_sub(1)
+            .and_then(|i| self.tokens.get(i))
+            .map_or(0, Token::end)
+    }
+
+    fn peek(&self) -> Option<&Token<'a>> {
+        self.tokens.get(self.pos)
+    }
+
+    /// Parses nodes until a tag named in `ends` (returned) or the end of
+    /// the input (`None`).
+    ///
+    /// An end tag of an enclosing block also stops the body, unconsumed,
+    /// so that block still closes: in `{% if %}{% for %}{% endif %}` only
+    /// the `for` is left unclosed.
+    fn body(&mut self, ends: &[&str]) -> (Vec<Node>, Option<Token<'a>>) {
+        self.open_blocks
+            .push(ends.iter().map(|end| end.to_string()).collect());
+        // The body of the document itself isn't nested
+        let nesting = self.depth + self.open_blocks.len() - 1;
+        let result = if nesting > MAX_NESTING_DEPTH {
+            self.skip_body(ends)
+        } else {
+            self.block_body(ends)
+        };
+        self.open_blocks.pop();
+        result
+    }
+
+    /// Skips a body nested too deeply to parse, up to the tag that ends it.
+    /// Blocks inside it are only counted, so their end tags aren't taken
+    /// for its own.
+    fn skip_body(&mut self, ends: &[&str]) -> (Vec<Node>, Option<Token<'a>>) {
+        let (start, end) = self.last_token_span();
+        self.error(
+            "nesting-too-deep",
+            format!(
+                "Blocks are nested more than {} levels deep",
+                MAX_NESTING_DEPTH
+            ),
+            start,
+            end,
+        );
+        let mut nested = 0usize;
+        while let Some(token) = self.tokens.get(self.pos).cloned() {
+            self.pos += 1;
+            if token.kind != TokenKind::Tag {
+                continue;
+            }
+            let name = token.name();
+            if BLOCK_TAGS.contains(&name) {
+                nested += 1;
+            } else if nested > 0 {
+                if name
+                    .strip_prefix("end")
+                    .is_some_and(|open| BLOCK_TAGS.contains(&open))
+                {
+                    nested -= 1;
+                }
+            } else if ends.contains(&name) {
+                return (Vec::new(), Some(token));
+            } else if self.closes_outer_block(name) {
+                self.pos -= 1;
+                return (Vec::new(), None);
+            }
+        }
+        (Vec::new(), None)
+    }
+
+    /// Span of the last consumed token, the tag that opened a body.
+    fn last_token_span(&self) -> (usize, usize) {
+        self.pos
+            .checked_sub(1)
+            .and_then(|i| self.tokens.get(i))
+            .map_or((0, 0), |token| (token.start, token.end()))
+    }
+
+    /// Whether `name` closes a block enclosing the innermost one.
+    fn closes_outer_block(&self, name: &str) -> bool {
+        let outer = &self.open_blocks[..self.open_blocks.len().saturating_sub(1)];
+        name.starts_with("end") && outer.iter().flatten().any(|end| end == name)
+    }
+
+    fn block_body(&mut self, ends: &[&str]) -> (Vec<Node>, Option<Token<'a>>) {
+        let mut nodes = Vec::new();
+
+        while let Some(token) = self.tokens.get(self.pos).cloned() {
+            self.pos += 1;
+            match token.kind {
+                TokenKind::Text | TokenKind::Raw | TokenKind::Comment => {
+                    if let Some(node) = self.text(&token) {
+                        nodes.push(node);
+                    }
+                }
+                TokenKind::Output => {
+                    let value = parse_filtered(token.markup, token.markup_start);
+                    if let Some(value) = self.ok(value) {
+                        nodes.push(Node::Output {
+                            value,
+                            span: Span::new(token.start, token.end()),
+                        });
+                    }
+                }
+                TokenKind::Error => self.lexer_error(&token),
+                TokenKind::Tag => {
+                    if ends.contains(&token.name()) {
+                        return (nodes, Some(token));
+                    }
+                    if self.closes_outer_block(token.name()) {
+                        let open = ends
+                            .iter()
+                            .find_map(|end| end.strip_prefix("end"))
+                            .unwrap_or_default();
+                        self.error(
+                            "mismatched-end-tag",
+                            format!("'{}' found before '{}' was closed", token.name(), open),
+                            token.start,
+                            token.end(),
+                        );
+                        self.pos -= 1;
+                        return (nodes, None);
+                    }
+                    if let Some(node) = self.tag(token) {
+                        nodes.push(node);
+                    }
+                }
+            }
+        }
+
+        (nodes, None)
+    }
+
+    fn ok<T>(&mut self, result: std::result::Result<T, ExprError>) -> Option<T> {
+        result.map_err(|error| self.expr_error(error)).ok()
+    }
+
+    fn lexer_error(&mut self, token: &Token<'a>) {
+        let code = if token.source.starts_with("{{") {
+            "unterminated-output"
+        } else if token.source.starts_with("{%") {
+            "unterminated-tag"
+        } else {
+            "unclosed-block"
+        };
+        let message = token.error.unwrap_or("Malformed Liquid");
+        self.error(code, message, token.start, token.end());
+    }
+
+    /// Builds a text node, applying the whitespace control of the
+    /// surrounding delimiters.
+    fn text(&self, token: &Token<'a>) -> Option<Node> {
+        let index = self.pos - 1;
+        let mut value = token.source;
+        if index > 0 && self.tokens[index - 1].trim_right {
+            value = value.trim_start();
+        }
+        if self.tokens.get(index + 1).is_some_and(|t| t.trim_left) {
+            value = value.trim_end();
+        }
+        (!value.is_empty()).then(|| Node::Text {
+            value: value.to_string(),
+            span: Span::new(token.start, token.end()),
+        })
+    }
+
+    /// Parses the body of a block opened by `open` up to one of `ends`,
+    /// reporting an error if the input ends first.
+    fn block(&mut self, open: &Token<'a>, ends: &[&str]) -> (Vec<Node>, Option<Token<'a>>) {
+        let (nodes, end) = self.body(ends);
+        if end.is_none() {
+            self.unclosed(open);
+        }
+        (nodes, end)
+    }
+
+    fn unclosed(&mut self, open: &Token<'a>) {
+        self.error(
+            "unclosed-block",
+            format!("'{}' tag was never closed", open.name()),
+            open.start,
+            open.end(),
+        );
+    }
+
+    /// Span from the opening tag to the last consumed token.
+    fn span_from(&self, open: &Token<'a>) -> Span {
+        Span::new(open.start, self.last_end().max(open.end()))
+    }
+
+    fn tag(&mut self, token: Token<'a>) -> Option<Node> {
+        let span = Span::new(token.start, token.end());
+        let name = token.name();
+        let args = token.args();
+        let base = token.args_start();
+
+        let node = match name {
+            "if" | "unless" => self.if_tag(&token),
+            "case" => self.case_tag(&token),
+            "for" => self.for_tag(&token),
+            "tablerow" => self.tablerow_tag(&token),
+            "capture" => {
+                let mut parser = ExprParser::new(args, base);
+                let name = match parser.peek() {
+                    Some(Tok::Str(name)) => Ok(name.to_string()),
+                    _ => parser.identifier(),
+                };
+                let name = self.recover(name, String::new());
+                let (body, _) = self.block(&token, &["endcapture"]);
+                Node::Capture {
+                    name,
+                    body,
+                    span: self.span_from(&token),
+                }
+            }
+            "paginate" => {
+                let parsed = paginate_args(args, base);
+                let (collection, per_page) = self.recover(parsed, (Expr::Nil, None));
+                let (body, _) = self.block(&token, &["endpaginate"]);
+                Node::Paginate {
+                    collection,
+                    per_page,
+                    body,
+                    span: self.span_from(&token),
+                }
+            }
+            "form" => {
+                let parsed = form_args(args, base);
+                let (form_type, args, kwargs) =
+                    self.recover(parsed, (Expr::Nil, Vec::new(), Vec::new()));
+                let (body, _) = self.block(&token, &["endform"]);
+                Node::Form {
+                    form_type,
+                    args,
+                    kwargs,
+                    body,
+                    span: self.span_from(&token),
+                }
+            }
+            "style" => {
+                let (body, _) = self.block(&token, &["endstyle"]);
+                Node::Style {
+                    body,
+                    span: self.span_from(&token),
+                }
+            }
+            "javascript" => {
+                let (body, _) = self.block(&token, &["endjavascript"]);
+                Node::Javascript {
+                    body,
+                    span: self.span_from(&token),
+                }
+            }
+            "stylesheet" | "script" | "block" => {
+                let end = format!("end{}", name);
+                let (body, _) = self.block(&token, &[end.as_str()]);
+                Node::Block {
+                    name: name.to_string(),
+                    markup: args.to_string(),
+                    body,
+                    span: self.span_from(&token),
+                }
+            }
+            "raw" | "comment" | "doc" => self.verbatim_tag(&token),
+            "schema" => self.schema_tag(&token),
+            "liquid" => self.liquid_tag(&token),
+            "#" => Node::Comment {
+                body: token.markup[1..].trim().to_string(),
+                span,
+            },
+            "assign" => {
+                let parsed = assign_args(args, base);
+                let (name, value) = self.ok(parsed)?;
+                Node::Assign { name, value, span }
+            }
+            "echo" => Node::Echo {
+                value: self.ok(parse_filtered(args, base))?,
+                span,
+            },
+            "increment" | "decrement" => {
+                let mut parser = ExprParser::new(args, base);
+                let parsed = parser.identifier().and_then(|n| parser.finish().map(|_| n));
+                let variable = self.ok(parsed)?;
+                if name == "increment" {
+                    Node::Increment {
+                        name: variable,
+                        span,
+                    }
+                } else {
+                    Node::Decrement {
+                        name: variable,
+                        span,
+                    }
+                }
+            }
+            "cycle" => {
+                let (group, values) = self.ok(cycle_args(args, base))?;
+                Node::Cycle {
+                    group,
+                    values,
+                    span,
+                }
+            }
+            "break" => Node::Break { span },
+            "continue" => Node::Continue { span },
+            "render" | "include" => {
+                let partial = self.ok(partial_args(args, base, span))?;
+                if name == "render" {
+                    Node::Render(partial)
+                } else {
+                    Node::Include(partial)
+                }
+            }
+            "section" | "sections" => {
+                let name_expr = self.ok(parse_expression(args, base))?;
+                if name == "section" {
+                    Node::Section {
+                        name: name_expr,
+                        span,
+                    }
+                } else {
+                    Node::Sections {
+                        name: name_expr,
+                        span,
+                    }
+                }
+            }
+            "layout" => {
+                let mut parser = ExprParser::new(args, base);
+                let parsed = if parser.eat_keyword("none") {
+                    parser.finish().map(|_| None)
+                } else {
+                    parser
+                        .expression()
+                        .and_then(|e| parser.finish().map(|_| Some(e)))
+                };
+                Node::Layout {
+                    name: self.ok(parsed)?,
+                    span,
+                }
+            }
+            "else" | "elsif" | "when" => {
+                self.error(
+                    "unexpected-tag",
+                    format!("'{}' is not allowed here", name),
+                    token.start,
+                    token.end(),
+                );
+                return None;
+            }
+            "" => {
+                self.error(
+                    "invalid-syntax",
+                    "Expected a tag name",
+                    token.start,
+                    token.end(),
+                );
+                return None;
+            }
+            _ if name.starts_with("end") => {
+                self.error(
+                    "unexpected-end-tag",
+                    format!("'{}' does not close any open block", name),
+                    token.start,
+                    token.end(),
+                );
+                return None;
+            }
+            _ => Node::Tag {
+                name: name.to_string(),
+                markup: args.to_string(),
+                span,
+            },
+        };
+
+        Some(node)
+    }
+
+    fn condition(&mut self, token: &Token<'a>) -> Condition {
+        let parsed = parse_condition(token.args(), token.args_start());
+        self.recover(parsed, Condition::Test { expr: Expr::Nil })
+    }
+
+    fn if_tag(&mut self, open: &Token<'a>) -> Node {
+        let end_name = format!("end{}", open.name());
+        let mut branches = Vec::new();
+        let mut else_body = None;
+        let mut condition = self.condition(open);
+
+        loop {
+            let (body, end) = self.block(open, &["elsif", "else", end_name.as_str()]);
+            branches.push(Branch { condition, body });
+
+            match end.as_ref().map(Token::name) {
+                Some("elsif") => condition = self.condition(end.as_ref().unwrap()),
+                Some("else") => {
+                    let (body, _) = self.block(open, &[end_name.as_str()]);
+                    else_body = Some(body);
+                    break;
+                }
+                _ => break,
+            }
+        }
+
+        let span = self.span_from(open);
+        if open.name() == "if" {
+            Node::If {
+                branches,
+                else_body,
+                span,
+            }
+        } else {
+            Node::Unless {
+                branches,
+                else_body,
+                span,
+            }
+        }
+    }
+
+    fn case_tag(&mut self, open: &Token<'a>) -> Node {
+        let parsed = parse_expression(open.args(), open.args_start());
+        let subject = self.recover(parsed, Expr::Nil);
+        let mut whens = Vec::new();
+        let mut else_body = None;
+
+        // Only whitespace is allowed before the first `when`
+        let (_, mut end) = self.block(open, &["when", "else", "endcase"]);
+        loop {
+            match end.as_ref().map(Token::name) {
+                Some("when") => {
+                    let token = end.take().unwrap();
+                    let values =
+                        self.recover(when_values(token.args(), token.args_start()), Vec::new());
+                    let (body, next) = self.block(open, &["when", "else", "endcase"]);
+                    whens.push(When { values, body });
+                    end = next;
+                }
+                Some("else") => {
+                    let (body, _) = self.block(open, &["endcase"]);
+                    else_body = Some(body);
+                    break;
+                }
+                _ => break,
+            }
+        }
+
+        Node::Case {
+            subject,
+            whens,
+            else_body,
+            span: self.span_from(open),
+        }
+    }
+
+    fn for_tag(&mut self, open: &Token<'a>) -> Node {
+        let parsed = loop_args(open.args(), open.args_start(), &["limit", "offset"]);
+        let args = self.recover(parsed, LoopArgs::default());
+
+        let (body, end) = self.block(open, &["else", "endfor"]);
+        let else_body = match end.as_ref().map(Token::name) {
+            Some("else") => Some(self.block(open, &["endfor"]).0),
+            _ => None,
+        };
+
+        Node::For {
+            variable: args.variable,
+            collection: args.collection,
+            reversed: args.reversed,
+            limit: take_option(&args.options, "limit"),
+            offset: take_option(&args.options, "offset"),
+            body,
+            else_body,
+            span: self.span_from(open),
+        }
+    }
+
+    fn tablerow_tag(&mut self, open: &Token<'a>) -> Node {
+        let parsed = loop_args(open.args(), open.args_start(), &["cols", "limit", "offset"]);
+        let args = self.recover(parsed, LoopArgs::default());
+        let (body, _) = self.block(open, &["endtablerow"]);
+
+        Node::Tablerow {
+            variable: args.variable,
+            collection: args.collection,
+            cols: take_option(&args.options, "cols"),
+            limit: take_option(&args.options, "limit"),
+            offset: take_option(&args.options, "offset"),
+            body,
+            span: self.span_from(open),
+        }
+    }
+
+    /// `raw`, `comment` and `doc`: the lexer already captured the body
+    /// verbatim. `doc` blocks become comments.
+    fn verbatim_tag(&mut self, open: &Token<'a>) -> Node {
+        let end_name = format!("end{}", open.name());
+        let mut body = String::new();
+
+        if let Some(token) = self.peek() {
+            if matches!(token.kind, TokenKind::Raw | TokenKind::Comment) {
+                body = token.source.to_string();
+                self.pos += 1;
+            }
+        }
+
+        match self.peek().cloned() {
+            Some(token) if token.kind == TokenKind::Tag && token.name() == end_name => {
+                self.pos += 1;
+            }
+            Some(token) if token.kind == TokenKind::Error => {
+                self.pos += 1;
+                body = token.source.to_string();
+                self.unclosed(open);
+            }
+            // Inside `{% liquid %}` the body is made of plain lines
+            _ => {
+                let (_, end) = self.body(&[end_name.as_str()]);
+                if end.is_none() {
+                    self.unclosed(open);
+                }
+            }
+        }
+
+        let span = self.span_from(open);
+        if open.name() == "raw" {
+            Node::Raw { body, span }
+        } else {
+            Node::Comment { body, span }
+        }
+    }
+
+    /// `schema` keeps its JSON body exactly as written; nothing inside it
+    /// is parsed as Liquid.
+    fn schema_tag(&mut self, open: &Token<'a>) -> Node {
+        let body_start = open.end();
+        let mut body_end = None;
+
+        while let Some(token) = self.tokens.get(self.pos) {
+            self.pos += 1;
+            if token.kind == TokenKind::Tag && token.name() == "endschema" {
+                body_end = Some(token.start);
+                break;
+            }
+        }
+
+        let body_end = body_end.unwrap_or_else(|| {
+            self.unclosed(open);
+            self.source.len()
+        });
+
+        Node::Schema {
+            body: self.source[body_start..body_end].to_string(),
+            span: self.span_from(open),
+        }
+    }
+
+    /// `{% liquid %}`: every non-empty line is a tag without delimiters.
+    fn liquid_tag(&mut self, open: &Token<'a>) -> Node {
+        let args = open.args();
+        let base = open.args_start();
+        let mut lines = Vec::new();
+        let mut offset = 0;
+
+        for line in args.split('\n') {
+            let markup = line.trim();
+            if !markup.is_empty() {
+                let start = base + offset + (line.len() - line.trim_start().len());
+                lines.push(Token {
+                    kind: TokenKind::Tag,
+                    source: markup,
+                    markup,
+                    start,
+                    markup_start: start,
+                    trim_left: false,
+                    trim_right: false,
+                    error: None,
+                });
+            }
+            offset += line.len() + 1;
+        }
+
+        let mut parser = Parser::new(self.source, lines);
+        parser.depth = self.depth + self.open_blocks.len();
+        let body = parser.document();
+        self.errors.append(&mut parser.errors);
+
+        Node::Liquid {
+            body,
+            span: Span::new(open.start, open.end()),
+        }
+    }
+}
+
+#[derive(Default)]
+struct LoopArgs {
+    variable: String,
+    collection: Expr,
+    reversed: bool,
+    options: Vec<Arg>,
+}
+
+fn take_option(options: &[Arg], name: &str) -> Option<Expr> {
+    options
+        .iter()
+        .find(|arg| arg.name == name)
+        .map(|arg| arg.value.clone())
+}
+
+/// `item in collection reversed limit: 4 offset: 2` (and `cols:` for
+/// `tablerow`). Options may be separated by commas.
+fn loop_args(
+    markup: &str,
+    base: usize,
+    allowed: &[&str],
+) -> std::result::Result<LoopArgs, ExprError> {
+    let mut parser = ExprParser::new(markup, base);
+    let variable = parser.identifier()?;
+    if !parser.eat_keyword("in") {
+        return Err(parser.error("Expected 'in'"));
+    }
+    let collection = parser.expression()?;
+    let mut args = LoopArgs {
+        variable,
+        collection,
+        ..LoopArgs::default()
+    };
+
+    loop {
+        parser.eat(Tok::Comma);
+        match parser.peek() {
+            None => return Ok(args),
+            Some(Tok::Ident("reversed")) => {
+                parser.eat_keyword("reversed");
+                args.reversed = true;
+            }
+            Some(Tok::Ident(name)) if allowed.contains(&name) => {
+                parser.eat(Tok::Ident(name));
+                if !parser.eat(Tok::Colon) {
+                    return Err(parser.error(format!("Expected ':' after '{}'", name)));
+                }
+                args.options.push(Arg {
+                    name: name.to_string(),
+                    value: parser.expression()?,
+                });
+            }
+            _ => return Err(parser.error("Unexpected loop option")),
+        }
+    }
+}
+
+/// `name = value | filters`
+fn assign_args(markup: &str, base: usize) -> std::result::Result<(String, Filtered), ExprError> {
+    let mut parser = ExprParser::new(markup, base);
+    let name = parser.identifier()?;
+    if !parser.eat(Tok::Equals) {
+        return Err(parser.error("Expected '=' in assign"));
+    }
+    let value = parser.filtered()?;
+    parser.finish()?;
+    Ok((name, value))
+}
+
+/// `value` alternatives of a `when`, separated by `or` or commas.
+fn when_values(markup: &str, base: usize) -> std::result::Result<Vec<Expr>, ExprError> {
+    let mut parser = ExprParser::new(markup, base);
+    let mut values = vec![parser.expression()?];
+    while parser.eat(Tok::Comma) || parser.eat_keyword("or") {
+        values.push(parser.expression()?);
+    }
+    parser.finish()?;
+    Ok(values)
+}
+
+/// `[group:] value, value, ...`
+fn cycle_args(
+    markup: &str,
+    base: usize,
+) -> std::result::Result<(Option<Expr>, Vec<Expr>), ExprError> {
+    let mut parser = ExprParser::new(markup, base);
+    let first = parser.expression()?;
+    let (group, mut values) = if parser.eat(Tok::Colon) {
+        (Some(first), vec![parser.expression()?])
+    } else {
+        (None, vec![first])
+    };
+    while parser.eat(Tok::Comma) {
+        values.push(parser.expression()?);
+    }
+    parser.finish()?;
+    Ok((group, values))
+}
+
+/// `collection [by per_page]`
+fn paginate_args(
+    markup: &str,
+    base: usize,
+) -> std::result::Result<(Expr, Option<Expr>), ExprError> {
+    let mut parser = ExprParser::new(markup, base);
+    let collection = parser.expression()?;
+    let per_page = if parser.eat_keyword("by") {
+        Some(parser.expression()?)
+    } else {
+        None
+    };
+    parser.finish()?;
+    Ok((collection, per_page))
+}
+
+/// `'type'[, object][, key: value...]`
+fn form_args(
+    markup: &str,
+    base: usize,
+) -> std::result::Result<(Expr, Vec<Expr>, Vec<Arg>), ExprError> {
+    let mut parser = ExprParser::new(markup, base);
+    let form_type = parser.expression()?;
+    let mut args = Vec::new();
+    let mut kwargs = Vec::new();
+    while parser.eat(Tok::Comma) {
+        parser.argument(&mut args, &mut kwargs)?;
+    }
+    parser.finish()?;
+    Ok((form_type, args, kwargs))
+}
+
+/// `'snippet' [with|for value] [as alias] [, key: value...]`
+fn partial_args(markup: &str, base: usize, span: Span) -> std::result::Result<Partial, ExprError> {
+    let mut parser = ExprParser::new(markup, base);
+    let template = parser.expression()?;
+    let mut variable = None;
+    let mut alias = None;
+    let mut for_loop = false;
+
+    if parser.eat_keyword("with") {
+        variable = Some(parser.expression()?);
+    } else if parser.eat_keyword("for") {
+        variable = Some(parser.expression()?);
+        for_loop = true;
+    }
+    if parser.eat_keyword("as") {
+        alias = Some(parser.identifier()?);
+    }
+
+    Ok(Partial {
+        template,
+        variable,
+        alias,
+        for_loop,
+        args: parser.keyword_arguments()?,
+        span,
+    })
+}
+
+/// A parse error as exposed to JavaScript.
+#[derive(Serialize)]
+struct JsParseError<'a> {
+    code: &'a str,
+    message: &'a str,
+    span: SourceSpan,
+}
+
+#[derive(Serialize)]
+struct JsParseResult<'a> {
+    nodes: &'a [Node],
+    errors: Vec<JsParseError<'a>>,
+}
+
+/// Parses a Liquid template into an AST.
+///
+/// Returns `{ nodes, errors }`. Nodes are plain objects tagged with a
+/// `type` field (`text`, `output`, `if`, `for`, `render`...) carrying byte
+/// `span`s; errors carry a stable `code`, a message and a full line/column
+/// span. Parsing continues after errors, so `nodes` is always usable.
+///
+/// # Arguments
+///
+/// * `template` - The Liquid source
+///
+/// # Returns
+///
+/// The AST and the list of syntax errors
+///
+/// # Examples
+///
+/// ```javascript
+/// parse("{% if a %}{{ b | upcase }}{% endif %}").nodes[0]
+/// // { type: "if", branches: [{ condition: { type: "test", ... }, body: [...] }], ... }
+/// ```
+#[napi]
+pub fn parse(template: String) -> Result<serde_json::Value> {
+    let result = parse_template(&template);
+    let index = LineIndex::new(&template);
+
+    let js = JsParseResult {
+        nodes: &result.nodes,
+        errors: result
+            .errors
+            .iter()
+            .map(|error| JsParseError {
+                code: error.code,
+                message: &error.message,
+                span: index.span(error.span.start as usize, error.span.end as usize),
+            })
+            .collect(),
+    };
+
+    serde_json::to_value(js).map_err(|e| Error::from_reason(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::liquid::ast::{CompareOp, PathSegment};
+
+    fn nodes(source: &str) -> Vec<Node> {
+        let result = parse_template(source);
+        assert_eq!(result.errors, vec![], "unexpected errors for {:?}", source);
+        result.nodes
+    }
+
+    fn codes(source: &str) -> Vec<&'static str> {
+        parse_template(source)
+            .errors
+            .into_iter()
+            .map(|e| e.code)
+            .collect()
+    }
+
+    fn var(name: &str) -> Expr {
+        Expr::Variable {
+            name: name.to_string(),
+            path: vec![],
+        }
+    }
+
+    fn text(value: &str, start: usize, end: usize) -> Node {
+        Node::Text {
+            value: value.to_string(),
+            span: Span::new(start, end),
+        }
+    }
+
+    #[test]
+    fn test_parse_text_and_output() {
+        assert_eq!(
+            nodes("Hi {{ name }}"),
+            vec![
+                text("Hi ", 0, 3),
+                Node::Output {
+                    value: Filtered {
+                        expr: var("name"),
+                        filters: vec![],
+                    },
+                    span: Span::new(3, 13),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_whitespace_control() {
+        let parsed = nodes("a \n {{- x -}} \n b");
+        assert_eq!(parsed[0], text("a", 0, 4));
+        assert_eq!(parsed[2], text("b", 13, 17));
+    }
+
+    #[test]
+    fn test_parse_if_elsif_else() {
+        let parsed = nodes("{% if a %}1{% elsif b > 2 %}2{% else %}3{% endif %}");
+        let Node::If {
+            branches,
+            else_body,
+            span,
+        } = &parsed[0]
+        else {
+            panic!("expected if, got {:?}", parsed[0]);
+        };
+        assert_eq!(branches.len(), 2);
+        assert_eq!(
+            branches[1].condition,
+            Condition::Compare {
+                left: var("b"),
+                op: CompareOp::Gt,
+                right: Expr::Int { value: 2 },
+            }
+        );
+        assert_eq!(else_body.as_ref().unwrap()[0], text("3", 39, 40));
+        assert_eq!(*span, Span::new(0, 51));
+    }
+
+    #[test]
+    fn test_parse_for_and_case() {
+        let parsed = nodes(
+            "{% for p in collection.products reversed limit: 4 %}{{ p }}{% else %}-{% endfor %}",
+        );
+        let Node::For {
+            variable,
+            reversed,
+            limit,
+            else_body,
+            ..
+        } = &parsed[0]
+        else {
+            panic!("expected for");
+        };
+        assert_eq!(variable, "p");
+        assert!(*reversed);
+        assert_eq!(*limit, Some(Expr::Int { value: 4 }));
+        assert!(else_body.is_some());
+
+        let parsed =
+            nodes("{% case x %} {% when 1, 2 or 3 %}a{% when 'b' %}b{% else %}c{% endcase %}");
+        let Node::Case { whens, .. } = &parsed[0] else {
+            panic!("expected case");
+        };
+        assert_eq!(whens[0].values.len(), 3);
+        assert_eq!(whens[1].body, vec![text("b", 48, 49)]);
+    }
+
+    #[test]
+    fn test_parse_theme_tags() {
+        let parsed = nodes(
+            "{% render 'card' for products as product, show: true %}\
+             {% section 'header' %}{% layout none %}\
+             {% paginate collection.products by 12 %}{% endpaginate %}\
+             {% form 'contact', class: 'f' %}{% endform %}\
+             {% render 'badge', label: tag | upcase %}{% paginate products %}{% endpaginate %}",
+        );
+        let Node::Render(partial) = &parsed[0] else {
+            panic!("expected render");
+        };
+        assert_eq!(partial.template.as_str(), Some("card"));
+        assert!(partial.for_loop);
+        assert_eq!(partial.alias.as_deref(), Some("product"));
+        assert_eq!(partial.args[0].name, "show");
+        assert!(matches!(parsed[1], Node::Section { .. }));
+        assert_eq!(
+            parsed[2],
+            Node::Layout {
+                name: None,
+                span: Span::new(77, 94),
+            }
+        );
+        assert!(matches!(
+            &parsed[3],
+            Node::Paginate {
+                per_page: Some(Expr::Int { value: 12 }),
+                ..
+            }
+        ));
+        assert!(matches!(&parsed[4], Node::Form { kwargs, .. } if kwargs.len() == 1));
+        assert!(
+            matches!(&parsed[5], Node::Render(partial) if partial.args[0].value.filters[0].name == "upcase")
+        );
+        assert!(matches!(&parsed[6], Node::Paginate { per_page: None, .. }));
+    }
+
+    #[test]
+    fn test_parse_verbatim_blocks() {
+        let parsed = nodes(
+            "{% raw %}{{ x }}{% endraw %}{% comment %}{% if %}{% endcomment %}\
+             {% schema %}{\"name\": \"{{ x }}\"}{% endschema %}{% # note %}\
+             {% doc %}@param {string} x{% enddoc %}",
+        );
+        assert!(matches!(&parsed[0], Node::Raw { body, .. } if body == "{{ x }}"));
+        assert!(matches!(&parsed[1], Node::Comment { body, .. } if body == "{% if %}"));
+        assert!(
+            matches!(&parsed[2], Node::Schema { body, .. } if body == "{\"name\": \"{{ x }}\"}")
+        );
+        assert!(matches!(&parsed[3], Node::Comment { body, .. } if body == "note"));
+        assert!(matches!(&parsed[4], Node::Comment { body, .. } if body == "@param {string} x"));
+    }
+
+    #[test]
+    fn test_parse_liquid_tag() {
+        let parsed = nodes(
+            "{% liquid\n  assign t = product.title | upcase\n  # note\n  if t\n    echo t\n  endif\n%}",
+        );
+        let Node::Liquid { body, .. } = &parsed[0] else {
+            panic!("expected liquid");
+        };
+        assert!(
+            matches!(&body[0], Node::Assign { name, span, .. } if name == "t" && *span == Span::new(12, 45))
+        );
+        assert!(matches!(&body[1], Node::Comment { .. }));
+        assert!(
+            matches!(&body[2], Node::If { branches, .. } if matches!(branches[0].body[0], Node::Echo { .. }))
+        );
+    }
+
+    #[test]
+    fn test_parse_nesting_limit() {
+        let nested = |depth: usize| {
+            format!(
+                "{}x{}{{{{ after }}}}",
+                "{% if a %}".repeat(depth),
+                "{% endif %}".repeat(depth)
+            )
+        };
+        assert!(parse_template(&nested(MAX_NESTING_DEPTH)).errors.is_empty());
+
+        assert_eq!(codes(&nested(1000)), vec!["nesting-too-deep"]);
+        let parsed = parse_template(&nested(1000));
+        // The skipped blocks' end tags close them, so what follows is parsed
+        assert!(matches!(parsed.nodes.last(), Some(Node::Output { .. })));
+
+        let liquid = format!("{{% {} echo a %}}", "liquid ".repeat(10_000));
+        assert_eq!(codes(&liquid), vec!["nesting-too-deep"]);
+        let lookup = format!("{{{{ {}a{} }}}}", "a[".repeat(100_000), "]".repeat(100_000));
+        assert_eq!(codes(&lookup), vec!["nesting-too-deep"]);
+    }
+
+    #[test]
+    fn test_parse_collects_errors_and_recovers() {
+        assert_eq!(codes("{% if a %}x"), vec!["unclosed-block"]);
+        assert_eq!(codes("{% endfor %}"), vec!["unexpected-end-tag"]);
+        assert_eq!(codes("{% else %}"), vec!["unexpected-tag"]);
+        assert_eq!(
+            codes("{{ a | }}{% assign = 1 %}{{ 'x }}"),
+            vec!["invalid-syntax", "invalid-syntax", "unterminated-string"]
+        );
+        assert_eq!(codes("{{ a "), vec!["unterminated-output"]);
+        assert_eq!(
+            codes("{% if a %}{% for x in y %}{% endif %}{{ x }}"),
+            vec!["mismatched-end-tag", "unclosed-block"]
+        );
+
+        let result = parse_template("{% if a ==  %}{{ b }}{% endif %}{{ c }}");
+        assert_eq!(result.errors.len(), 1);
+        assert_eq!(result.nodes.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_bracket_lookup() {
+        let parsed = nodes("{{ collections['ofertas'].products }}");
+        let Node::Output { value, .. } = &parsed[0] else {
+            panic!("expected output");
+        };
+        assert!(matches!(
+            &value.expr,
+            Expr::Variable { path, .. } if matches!(&path[0], PathSegment::Index { expr } if expr.as_str() == Some("ofertas"))
+        ));
+    }
+
+    #[test]
+    fn test_parse_export_serializes_plain_objects() {
+        let value = parse("{% if a %}{{ b }}{% endif %}{% endfor %}".to_string()).unwrap();
+        assert_eq!(value["nodes"][0]["type"], "if");
+        assert_eq!(
+            value["nodes"][0]["branches"][0]["condition"]["type"],
+            "test"
+        );
+        assert_eq!(
+            value["nodes"][0]["branches"][0]["body"][0]["value"]["expr"]["name"],
+            "b"
+        );
+        assert_eq!(value["errors"][0]["code"], "unexpected-end-tag");
+        assert_eq!(value["errors"][0]["span"]["column"], 29);
+    }
+}
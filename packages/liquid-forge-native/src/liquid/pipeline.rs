@@ -0,0 +1,320 @@
+/*
+ * Copyright 2025 Fasttify LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Filter chains run in one native call.
+//!
+//! Calling native filters one by one from JavaScript copies every
+//! intermediate string across the NAPI boundary. A chain such as
+//! `title | strip_html | truncate: 40 | escape` is instead passed whole and
+//! its intermediate values stay in Rust.
+//!
+//! Each step is `[name, ...args]`. Named arguments (`excerpt: 30, unit:
+//! "words"`) go in a trailing object: `["excerpt", 30, { unit: "words" }]`.
+
+use napi::bindgen_prelude::{Buffer, Either};
+use napi::Error;
+use serde_json::Value;
+
+use super::filters::apply_filter;
+use super::syntax::is_native_filter;
+use crate::filters::split_buffer;
+
+/// Error codes of filter chains, exposed as the `code` of the JavaScript
+/// error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterChainError {
+    /// A step is not `[name, ...args]`.
+    InvalidStep,
+    /// No native filter has the step's name.
+    UnknownFilter,
+    /// A filter rejected its arguments.
+    FilterFailed,
+    /// A batch input is not valid UTF-8.
+    InvalidInput,
+}
+
+impl AsRef<str> for FilterChainError {
+    fn as_ref(&self) -> &str {
+        match self {
+            FilterChainError::InvalidStep => "InvalidStep",
+            FilterChainError::UnknownFilter => "UnknownFilter",
+            FilterChainError::FilterFailed => "FilterFailed",
+            FilterChainError::InvalidInput => "InvalidInput",
+        }
+    }
+}
+
+type Result<T> = napi::Result<T, FilterChainError>;
+
+#[derive(Debug, Clone)]
+struct Step {
+    name: String,
+    args: Vec<Value>,
+    kwargs: Vec<(String, Value)>,
+}
+
+fn compile(steps: Vec<Vec<Value>>) -> Result<Vec<Step>> {
+    steps
+        .into_iter()
+        .enumerate()
+        .map(|(i, step)| {
+            let mut values = step.into_iter();
+            let name = match values.next() {
+                Some(Value::String(name)) => name,
+                _ => {
+                    return Err(Error::new(
+                        FilterChainError::InvalidStep,
+                        format!("Filter step {} must start with the filter name", i),
+                    ))
+                }
+            };
+            if !is_native_filter(&name) {
+                return Err(Error::new(
+                    FilterChainError::UnknownFilter,
+                    format!("Unknown filter '{}' at step {}", name, i),
+                ));
+            }
+
+            let mut args: Vec<Value> = values.collect();
+            let kwargs = match args.last() {
+                Some(Value::Object(_)) => match args.pop() {
+                    Some(Value::Object(map)) => map.into_iter().collect(),
+                    _ => unreachable!(),
+                },
+                _ => Vec::new(),
+            };
+            Ok(Step { name, args, kwargs })
+        })
+        .collect()
+}
+
+fn run(steps: &[Step], input: Value) -> Result<Value> {
+    steps
+        .iter()
+        .enumerate()
+        .try_fold(input, |value, (i, step)| {
+            match apply_filter(&step.name, &value, &step.args, &step.kwargs) {
+                Some(Ok(value)) => Ok(value),
+                Some(Err(message)) => Err(Error::new(
+                    FilterChainError::FilterFailed,
+                    format!("Filter '{}' at step {}: {}", step.name, i, message),
+                )),
+                None => unreachable!("filters are checked when the chain is compiled"),
+            }
+        })
+}
+
+/// A filter chain compiled once and applied to many inputs.
+///
+/// # Examples
+///
+/// ```javascript
+/// const cardTitle = new FilterChain([["strip_html"], ["truncate", 40], ["escape"]])
+/// products.map((product) => cardTitle.apply(product.title))
+/// ```
+#[napi]
+pub struct FilterChain {
+    steps: Vec<Step>,
+}
+
+#[napi]
+impl FilterChain {
+    /// Checks every step, failing with code `UnknownFilter` or
+    /// `InvalidStep`.
+    #[napi(constructor)]
+    pub fn new(filters: Vec<Vec<Value>>) -> Result<Self> {
+        Ok(FilterChain {
+            steps: compile(filters)?,
+        })
+    }
+
+    /// Runs the chain on `input`. A filter rejecting its arguments fails
+    /// with code `FilterFailed`.
+    #[napi]
+    pub fn apply(&self, input: Option<Value>) -> Result<Value> {
+        run(&self.steps, input.unwrap_or(Value::Null))
+    }
+
+    /// Number of steps in the chain.
+    #[napi(getter)]
+    pub fn length(&self) -> u32 {
+        self.steps.len() as u32
+    }
+}
+
+/// Applies a chain of filters in a single native call.
+///
+/// # Arguments
+///
+/// * `input` - The value to filter
+/// * `filters` - The steps, each `[name, ...args]`
+///
+/// # Returns
+///
+/// The filtered value. Errors have `code` `UnknownFilter`, `InvalidStep`
+/// or `FilterFailed`, and nothing runs if any step names an unknown filter
+///
+/// # Examples
+///
+/// ```javascript
+/// applyFilters("<b>Camisa</b> & más", [["strip_html"], ["truncate", 11], ["escape"]])
+/// // "Camisa &amp;..."
+/// ```
+#[napi]
+pub fn apply_filters(input: Option<Value>, filters: Vec<Vec<Value>>) -> Result<Value> {
+    run(&compile(filters)?, input.unwrap_or(Value::Null))
+}
+
+/// Applies one filter to many inputs in a single native call.
+///
+/// # Arguments
+///
+/// * `name` - The filter name
+/// * `inputs` - The values, as an array or a Buffer of UTF-8 strings
+///   separated by NUL bytes
+/// * `args` - Arguments passed to every call, as in `applyFilters`
+///
+/// # Returns
+///
+/// One result per input, in order. Errors have the same codes as
+/// `applyFilters`, plus `InvalidInput` for a Buffer that is not UTF-8
+///
+/// # Examples
+///
+/// ```javascript
+/// mapFilter("truncate", products.map((p) => p.title), [20])
+/// mapFilter("times", prices, [1.19])
+/// ```
+#[napi]
+pub fn map_filter(
+    name: String,
+    inputs: Either<Vec<Option<Value>>, Buffer>,
+    args: Option<Vec<Value>>,
+) -> Result<Vec<Value>> {
+    let mut step = vec![Value::String(name)];
+    step.extend(args.unwrap_or_default());
+    let steps = compile(vec![step])?;
+
+    match inputs {
+        Either::A(items) => items
+            .into_iter()
+            .map(|item| run(&steps, item.unwrap_or(Value::Null)))
+            .collect(),
+        Either::B(buffer) => split_buffer(&buffer)
+            .map(|item| match item {
+                Ok(text) => run(&steps, Value::from(text)),
+                Err(message) => Err(Error::new(FilterChainError::InvalidInput, message)),
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn steps(value: Value) -> Vec<Vec<Value>> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_apply_filters() {
+        let cases = [
+            (
+                json!("<b>Camisa</b> & más"),
+                json!([["strip_html"], ["truncate", 11], ["escape"]]),
+                json!("Camisa &amp;..."),
+            ),
+            (
+                json!(" Camisa Azul "),
+                json!([["strip"], ["handleize"], ["append", ".html"]]),
+                json!("camisa-azul.html"),
+            ),
+            (
+                json!([3, 1, 2]),
+                json!([["sort"], ["join", "-"]]),
+                json!("1-2-3"),
+            ),
+            (
+                json!(10),
+                json!([["divided_by", 4], ["plus", 0.5]]),
+                json!(3),
+            ),
+            (
+                json!("<p>uno dos tres cuatro</p>"),
+                json!([["excerpt", 2, {"unit": "words", "ellipsis": "…"}]]),
+                json!("uno dos…"),
+            ),
+            (Value::Null, json!([["default", "vacío"]]), json!("vacío")),
+            (json!("same"), json!([]), json!("same")),
+        ];
+        for (input, filters, expected) in cases {
+            assert_eq!(
+                apply_filters(Some(input), steps(filters.clone())).unwrap(),
+                expected,
+                "{}",
+                filters
+            );
+        }
+    }
+
+    #[test]
+    fn test_filter_chain_errors() {
+        let error = apply_filters(Some(json!("x")), steps(json!([["upcase"], ["t"]]))).unwrap_err();
+        assert_eq!(error.status, FilterChainError::UnknownFilter);
+        assert_eq!(error.reason, "Unknown filter 't' at step 1");
+
+        let error = FilterChain::new(steps(json!([[1]]))).err().unwrap();
+        assert_eq!(error.status, FilterChainError::InvalidStep);
+        let error = FilterChain::new(vec![vec![]]).err().unwrap();
+        assert_eq!(error.status, FilterChainError::InvalidStep);
+
+        let error =
+            apply_filters(Some(json!("x")), steps(json!([["sanitize_html", "nope"]]))).unwrap_err();
+        assert_eq!(error.status, FilterChainError::FilterFailed);
+    }
+
+    #[test]
+    fn test_map_filter() {
+        let inputs = Either::A(vec![Some(json!("Camisa")), None, Some(json!(12))]);
+        assert_eq!(
+            map_filter("append".into(), inputs, Some(vec![json!("!")])).unwrap(),
+            [json!("Camisa!"), json!("!"), json!("12!")]
+        );
+
+        let inputs = Either::B(Buffer::from(b"uno dos\0tres".to_vec()));
+        assert_eq!(
+            map_filter("split".into(), inputs, Some(vec![json!(" ")])).unwrap(),
+            [json!(["uno", "dos"]), json!(["tres"])]
+        );
+
+        let error = map_filter("money".into(), Either::A(vec![]), None).unwrap_err();
+        assert_eq!(error.status, FilterChainError::UnknownFilter);
+        let inputs = Either::B(Buffer::from(b"\xff".to_vec()));
+        let error = map_filter("upcase".into(), inputs, None).unwrap_err();
+        assert_eq!(error.status, FilterChainError::InvalidInput);
+    }
+
+    #[test]
+    fn test_compiled_chain_is_reusable() {
+        let chain = FilterChain::new(steps(json!([["upcase"], ["prepend", "# "]]))).unwrap();
+        assert_eq!(chain.length(), 2);
+        assert_eq!(chain.apply(Some(json!("uno"))).unwrap(), json!("# UNO"));
+        assert_eq!(chain.apply(Some(json!("dos"))).unwrap(), json!("# DOS"));
+        assert_eq!(chain.apply(None).unwrap(), json!("# "));
+    }
+}
@@ -0,0 +1,1354 @@
+/*
+ * Copyright 2025 Fasttify LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Native Liquid renderer.
+//!
+//! Renders a parsed template against a JSON context with the semantics of
+//! the LiquidJS engine it replaces. Everything that still lives in
+//! TypeScript (platform filters, theme tags such as `section` or `form`,
+//! and snippet loading) is delegated to a host callback.
+
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use napi::bindgen_prelude::Function;
+use napi::{Error, Result, Status};
+use serde_json::{json, Map, Value};
+
+use super::ast::{Condition, Expr, Filter, Filtered, Node, Partial, PathSegment, Span};
+use super::condition::{compare_operands, operands_equal, Operand};
+use super::filters::apply_filter;
+use super::parser::parse_template;
+use super::span::LineIndex;
+use super::value::{index, is_truthy, iterate, to_number, to_text, write_output};
+
+/// Maximum nesting of `render` and `include`, to stop recursive snippets.
+const MAX_PARTIAL_DEPTH: usize = 64;
+
+/// Largest range a template may build with `(a..b)`.
+const MAX_RANGE_LEN: f64 = 1_000_000.0;
+
+/// Receives the requests the renderer cannot answer itself and returns
+/// `None` when it has no answer. Requests are objects with a `kind`:
+///
+/// * `{ kind: "filter", name, input, args }` - a filter that is not
+///   native; keyword arguments are appended to `args` as `[name, value]`
+///   pairs, as LiquidJS passes them.
+/// * `{ kind: "tag", name, args, body, markup }` - `section`, `sections`,
+///   `layout`, `form`, `style`, `javascript`, `stylesheet`, `script` and
+///   unknown tags. `body` is the rendered block content; the answer is
+///   the tag's output.
+/// * `{ kind: "partial", name }` - the source of a snippet, either as a
+///   string or as `{ source, variables }` with extra snippet variables.
+pub type Host<'h> = dyn FnMut(&Value) -> Result<Option<Value>> + 'h;
+
+/// Options accepted by `render`.
+#[napi(object)]
+#[derive(Default)]
+pub struct RenderOptions {
+    /// Snippet sources by name for `render` and `include`, used before
+    /// asking the host.
+    pub partials: Option<HashMap<String, String>>,
+    /// Filters implemented by the host. They are always sent to the host,
+    /// even when a native filter has the same name.
+    pub host_filters: Option<Vec<String>>,
+    /// Fail on unknown filters instead of returning the input unchanged
+    /// (default: false).
+    pub strict_filters: Option<bool>,
+    /// Fail on undefined variables instead of rendering nil
+    /// (default: false).
+    pub strict_variables: Option<bool>,
+}
+
+/// A parsed template ready to render.
+pub struct Template {
+    /// Snippet name; `None` for the page template.
+    pub name: Option<String>,
+    pub source: String,
+    pub nodes: Vec<Node>,
+    /// Variables supplied by the host along with a snippet source.
+    variables: Map<String, Value>,
+}
+
+impl Template {
+    /// Parses `source`, failing on the first syntax error.
+    pub fn parse(name: Option<String>, source: String) -> Result<Self> {
+        let parsed = parse_template(&source);
+        let mut template = Template {
+            name,
+            source,
+            nodes: parsed.nodes,
+            variables: Map::new(),
+        };
+        match parsed.errors.first() {
+            Some(error) => Err(template_error(&template, error.span, &error.message)),
+            None => {
+                template.nodes.shrink_to_fit();
+                Ok(template)
+            }
+        }
+    }
+
+    /// Rebuilds a page template from already parsed nodes.
+    pub(crate) fn from_parts(source: String, nodes: Vec<Node>) -> Self {
+        Template {
+            name: None,
+            source,
+            nodes,
+            variables: Map::new(),
+        }
+    }
+}
+
+/// How control leaves a list of nodes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Flow {
+    Normal,
+    Break,
+    Continue,
+}
+
+/// Renders templates against one context.
+pub struct Renderer<'h> {
+    /// The render context. `increment`/`decrement` counters live here too.
+    env: Map<String, Value>,
+    /// Local scopes, innermost last.
+    scopes: Vec<Map<String, Value>>,
+    /// Scope that `assign` and `capture` write to.
+    root: usize,
+    host: Option<&'h mut Host<'h>>,
+    partials: HashMap<String, String>,
+    loaded: HashMap<String, Rc<Template>>,
+    host_filters: HashSet<String>,
+    strict_filters: bool,
+    strict_variables: bool,
+    /// Comparing a string with a number is an error rather than false.
+    strict_comparisons: bool,
+    cycles: HashMap<String, usize>,
+    depth: usize,
+    /// Template being rendered and the span of the current node, for error
+    /// positions.
+    template: Option<Rc<Template>>,
+    span: Span,
+}
+
+impl<'h> Renderer<'h> {
+    pub fn new(
+        context: Map<String, Value>,
+        options: RenderOptions,
+        host: Option<&'h mut Host<'h>>,
+    ) -> Self {
+        Self {
+            env: context,
+            scopes: vec![Map::new()],
+            root: 0,
+            host,
+            partials: options.partials.unwrap_or_default(),
+            loaded: HashMap::new(),
+            host_filters: options
+                .host_filters
+                .unwrap_or_default()
+                .into_iter()
+                .collect(),
+            strict_filters: options.strict_filters.unwrap_or(false),
+            strict_variables: options.strict_variables.unwrap_or(false),
+            strict_comparisons: false,
+            cycles: HashMap::new(),
+            depth: 0,
+            template: None,
+            span: Span::default(),
+        }
+    }
+
+    /// Renders a page template.
+    pub fn render(&mut self, template: Rc<Template>) -> Result<String> {
+        let mut out = String::with_capacity(template.source.len());
+        let previous = self.template.replace(template.clone());
+        let result = self.render_nodes(&template.nodes, &mut out);
+        self.template = previous;
+        result.map(|_| out)
+    }
+
+    /// Evaluates a condition written in `template`'s source, outside of any
+    /// node. Unlike in templates, comparing a string with a number is an
+    /// error, as in Shopify.
+    pub(crate) fn evaluate(
+        &mut self,
+        template: Rc<Template>,
+        condition: &Condition,
+    ) -> Result<bool> {
+        let previous = self.template.replace(template);
+        let strict = std::mem::replace(&mut self.strict_comparisons, true);
+        let result = self.condition(condition);
+        self.template = previous;
+        self.strict_comparisons = strict;
+        result
+    }
+
+    fn error(&self, span: Span, message: impl AsRef<str>) -> Error {
+        match &self.template {
+            Some(template) => template_error(template, span, message.as_ref()),
+            None => Error::new(Status::GenericFailure, message.as_ref().to_string()),
+        }
+    }
+
+    fn render_nodes(&mut self, nodes: &[Node], out: &mut String) -> Result<Flow> {
+        for node in nodes {
+            match self.render_node(node, out)? {
+                Flow::Normal => {}
+                flow => return Ok(flow),
+            }
+        }
+        Ok(Flow::Normal)
+    }
+
+    fn render_to_string(&mut self, nodes: &[Node]) -> Result<String> {
+        let mut out = String::new();
+        self.render_nodes(nodes, &mut out)?;
+        Ok(out)
+    }
+
+    fn render_node(&mut self, node: &Node, out: &mut String) -> Result<Flow> {
+        self.span = node.span();
+
+        match node {
+            Node::Text { value, .. } => out.push_str(value),
+            Node::Raw { body, .. } => out.push_str(body),
+            Node::Comment { .. } | Node::Schema { .. } => {}
+            Node::Output { value, .. } | Node::Echo { value, .. } => {
+                let value = self.filtered(value)?;
+                write_output(&value, out);
+            }
+            Node::Assign { name, value, .. } => {
+                let value = self.filtered(value)?;
+                self.scopes[self.root].insert(name.clone(), value);
+            }
+            Node::Capture { name, body, .. } => {
+                let value = self.render_to_string(body)?;
+                self.scopes[self.root].insert(name.clone(), Value::String(value));
+            }
+            Node::Increment { name, .. } | Node::Decrement { name, .. } => {
+                let current = self.env.get(name).and_then(Value::as_i64).unwrap_or(0);
+                let (shown, next) = if matches!(node, Node::Increment { .. }) {
+                    (current, current + 1)
+                } else {
+                    (current - 1, current - 1)
+                };
+                self.env.insert(name.clone(), Value::from(next));
+                out.push_str(&shown.to_string());
+            }
+            Node::If {
+                branches,
+                else_body,
+                ..
+            }
+            | Node::Unless {
+                branches,
+                else_body,
+                ..
+            } => {
+                let negate_first = matches!(node, Node::Unless { .. });
+                for (i, branch) in branches.iter().enumerate() {
+                    let matched = self.condition(&branch.condition)?;
+                    if matched != (negate_first && i == 0) {
+                        return self.render_nodes(&branch.body, out);
+                    }
+                }
+                if let Some(body) = else_body {
+                    return self.render_nodes(body, out);
+                }
+            }
+            Node::Case {
+                subject,
+                whens,
+                else_body,
+                ..
+            } => {
+                let value = self.operand(subject)?;
+                for when in whens {
+                    for candidate in &when.values {
+                        if operands_equal(&value, &self.operand(candidate)?) {
+                            return self.render_nodes(&when.body, out);
+                        }
+                    }
+                }
+                if let Some(body) = else_body {
+                    return self.render_nodes(body, out);
+                }
+            }
+            Node::For {
+                variable,
+                collection,
+                reversed,
+                limit,
+                offset,
+                body,
+                else_body,
+                ..
+            } => {
+                let items = self.loop_items(collection, limit.as_ref(), offset.as_ref())?;
+                let items = if *reversed {
+                    items.into_iter().rev().collect()
+                } else {
+                    items
+                };
+                if items.is_empty() {
+                    if let Some(body) = else_body {
+                        return self.render_nodes(body, out);
+                    }
+                } else {
+                    self.for_loop(variable, items, body, out)?;
+                }
+            }
+            Node::Tablerow {
+                variable,
+                collection,
+                cols,
+                limit,
+                offset,
+                body,
+                ..
+            } => {
+                let items = self.loop_items(collection, limit.as_ref(), offset.as_ref())?;
+                let cols = match cols {
+                    Some(cols) => to_number(&self.eval(cols)?) as usize,
+                    None => 0,
+                };
+                self.tablerow(variable, items, cols, body, out)?;
+            }
+            Node::Cycle { group, values, .. } => {
+                let values = values
+                    .iter()
+                    .map(|v| self.eval(v))
+                    .collect::<Result<Vec<_>>>()?;
+                let key = match group {
+                    Some(group) => to_text(&self.eval(group)?).into_owned(),
+                    None => Value::Array(values.clone()).to_string(),
+                };
+                let position = self.cycles.entry(key).or_insert(0);
+                if let Some(value) = values.get(*position % values.len().max(1)) {
+                    write_output(value, out);
+                }
+                *position += 1;
+            }
+            Node::Break { .. } => return Ok(Flow::Break),
+            Node::Continue { .. } => return Ok(Flow::Continue),
+            Node::Liquid { body, .. } => return self.render_nodes(body, out),
+            Node::Render(partial) => self.partial(partial, true, out)?,
+            Node::Include(partial) => self.partial(partial, false, out)?,
+            Node::Section { name, .. } | Node::Sections { name, .. } => {
+                let tag = if matches!(node, Node::Section { .. }) {
+                    "section"
+                } else {
+                    "sections"
+                };
+                let args = vec![self.eval(name)?];
+                if let Some(html) = self.host_tag(tag, args, None, None)? {
+                    out.push_str(&html);
+                }
+            }
+            Node::Layout { name, .. } => {
+                let name = match name {
+                    Some(name) => self.eval(name)?,
+                    None => Value::Null,
+                };
+                if let Some(html) = self.host_tag("layout", vec![name], None, None)? {
+                    out.push_str(&html);
+                }
+            }
+            Node::Paginate { body, .. } => {
+                // The `paginate` object is built by the caller, as the
+                // TypeScript tag expects; without it nothing is rendered.
+                if self.variable("paginate").is_some() {
+                    self.scopes.push(Map::new());
+                    let flow = self.render_nodes(body, out);
+                    self.scopes.pop();
+                    return flow;
+                }
+            }
+            Node::Form {
+                form_type,
+                args,
+                kwargs,
+                body,
+                ..
+            } => {
+                let mut values = vec![self.eval(form_type)?];
+                for arg in args {
+                    values.push(self.eval(arg)?);
+                }
+                for arg in kwargs {
+                    values.push(json!([arg.name, self.eval(&arg.value)?]));
+                }
+                let body = self.render_to_string(body)?;
+                match self.host_tag("form", values, Some(&body), None)? {
+                    Some(html) => out.push_str(&html),
+                    None => {
+                        out.push_str("<form method=\"post\">");
+                        out.push_str(&body);
+                        out.push_str("</form>");
+                    }
+                }
+            }
+            Node::Style { body, .. } | Node::Javascript { body, .. } => {
+                let (tag, element) = if matches!(node, Node::Style { .. }) {
+                    ("style", "style")
+                } else {
+                    ("javascript", "script")
+                };
+                let body = self.render_to_string(body)?;
+                self.asset_tag(tag, element, body, None, out)?;
+            }
+            // The default content; templates extending the layout replace
+            // it when LiquidJS renders them
+            Node::Block { name, body, .. } if name == "block" => {
+                return self.render_nodes(body, out);
+            }
+            Node::Block {
+                name, markup, body, ..
+            } => {
+                let element = if name == "stylesheet" {
+                    "style"
+                } else {
+                    "script"
+                };
+                let body = self.render_to_string(body)?;
+                self.asset_tag(name, element, body, Some(markup), out)?;
+            }
+            Node::Tag { name, markup, .. } => {
+                if let Some(html) = self.host_tag(name, Vec::new(), None, Some(markup))? {
+                    out.push_str(&html);
+                }
+            }
+        }
+
+        Ok(Flow::Normal)
+    }
+
+    /// Asset blocks go to the host, which collects them; without an answer
+    /// their content is inlined in a `<style>` or `<script>` element.
+    fn asset_tag(
+        &mut self,
+        name: &str,
+        element: &str,
+        body: String,
+        markup: Option<&str>,
+        out: &mut String,
+    ) -> Result<()> {
+        match self.host_tag(name, Vec::new(), Some(&body), markup)? {
+            Some(html) => out.push_str(&html),
+            None if body.trim().is_empty() => {}
+            None => {
+                out.push_str(&format!("<{}>", element));
+                out.push_str(&body);
+                out.push_str(&format!("</{}>", element));
+            }
+        }
+        Ok(())
+    }
+
+    fn host_tag(
+        &mut self,
+        name: &str,
+        args: Vec<Value>,
+        body: Option<&str>,
+        markup: Option<&str>,
+    ) -> Result<Option<String>> {
+        let Some(host) = self.host.as_mut() else {
+            return Ok(None);
+        };
+        let request = json!({
+            "kind": "tag",
+            "name": name,
+            "args": args,
+            "body": body,
+            "markup": markup,
+        });
+        Ok(host(&request)?.map(|value| to_text(&value).into_owned()))
+    }
+
+    /// Evaluates a `for`/`tablerow` collection and applies `offset` and
+    /// `limit`.
+    fn loop_items(
+        &mut self,
+        collection: &Expr,
+        limit: Option<&Expr>,
+        offset: Option<&Expr>,
+    ) -> Result<Vec<Value>> {
+        let mut items = iterate(self.eval(collection)?);
+        if let Some(offset) = offset {
+            let offset = (to_number(&self.eval(offset)?).max(0.0) as usize).min(items.len());
+            items.drain(..offset);
+        }
+        if let Some(limit) = limit {
+            items.truncate(to_number(&self.eval(limit)?).max(0.0) as usize);
+        }
+        Ok(items)
+    }
+
+    fn for_loop(
+        &mut self,
+        variable: &str,
+        items: Vec<Value>,
+        body: &[Node],
+        out: &mut String,
+    ) -> Result<()> {
+        let parent = self.variable("forloop").cloned().unwrap_or(Value::Null);
+        let length = items.len();
+        self.scopes.push(Map::new());
+
+        for (i, item) in items.into_iter().enumerate() {
+            let scope = self.scopes.last_mut().unwrap();
+            scope.insert(variable.to_string(), item);
+            scope.insert("forloop".to_string(), forloop(i, length, &parent));
+            match self.render_nodes(body, out) {
+                Ok(Flow::Break) => break,
+                Ok(_) => {}
+                Err(error) => {
+                    self.scopes.pop();
+                    return Err(error);
+                }
+            }
+        }
+
+        self.scopes.pop();
+        Ok(())
+    }
+
+    /// Renders `<tr>`/`<td>` rows the way LiquidJS does.
+    fn tablerow(
+        &mut self,
+        variable: &str,
+        items: Vec<Value>,
+        cols: usize,
+        body: &[Node],
+        out: &mut String,
+    ) -> Result<()> {
+        let length = items.len();
+        let cols = if cols == 0 { length.max(1) } else { cols };
+        self.scopes.push(Map::new());
+
+        let mut result = Ok(());
+        for (i, item) in items.into_iter().enumerate() {
+            let (row, col0) = (i / cols + 1, i % cols);
+            if col0 == 0 {
+                if row > 1 {
+                    out.push_str("</tr>");
+                }
+                out.push_str(&format!("<tr class=\"row{}\">", row));
+            }
+
+            let mut tablerowloop = forloop(i, length, &Value::Null);
+            if let Value::Object(fields) = &mut tablerowloop {
+                fields.insert("col".to_string(), (col0 + 1).into());
+                fields.insert("col0".to_string(), col0.into());
+                fields.insert("col_first".to_string(), (col0 == 0).into());
+                fields.insert(
+                    "col_last".to_string(),
+                    (col0 + 1 == cols || i + 1 == length).into(),
+                );
+                fields.insert("row".to_string(), row.into());
+            }
+            let scope = self.scopes.last_mut().unwrap();
+            scope.insert(variable.to_string(), item);
+            scope.insert("tablerowloop".to_string(), tablerowloop);
+
+            out.push_str(&format!("<td class=\"col{}\">", col0 + 1));
+            match self.render_nodes(body, out) {
+                Ok(flow) => {
+                    out.push_str("</td>");
+                    if flow == Flow::Break {
+                        break;
+                    }
+                }
+                Err(error) => {
+                    result = Err(error);
+                    break;
+                }
+            }
+        }
+        if length > 0 && result.is_ok() {
+            out.push_str("</tr>");
+        }
+
+        self.scopes.pop();
+        result
+    }
+
+    /// `render` (isolated) and `include` (shares the caller's scope).
+    fn partial(&mut self, partial: &Partial, isolated: bool, out: &mut String) -> Result<()> {
+        let name = to_text(&self.eval(&partial.template)?).into_owned();
+        if self.depth >= MAX_PARTIAL_DEPTH {
+            return Err(self.error(
+                partial.span,
+                format!("Snippet '{}' is nested too deeply", name),
+            ));
+        }
+
+        let Some(template) = self.load(&name)? else {
+            out.push_str(&format!("<!-- Snippet '{}' not found -->", name));
+            return Ok(());
+        };
+
+        let mut base = template.variables.clone();
+        for arg in &partial.args {
+            let value = self.filtered(&arg.value)?;
+            base.insert(arg.name.clone(), value);
+        }
+
+        let alias = partial.alias.as_deref().unwrap_or(&name).to_string();
+        match &partial.variable {
+            Some(variable) if partial.for_loop => {
+                let items = iterate(self.eval(variable)?);
+                let length = items.len();
+                for (i, item) in items.into_iter().enumerate() {
+                    let mut scope = base.clone();
+                    scope.insert(alias.clone(), item);
+                    scope.insert("forloop".to_string(), forloop(i, length, &Value::Null));
+                    self.partial_body(&template, scope, isolated, out)?;
+                }
+            }
+            Some(variable) => {
+                let value = self.eval(variable)?;
+                base.insert(alias, value);
+                self.partial_body(&template, base, isolated, out)?;
+            }
+            None => self.partial_body(&template, base, isolated, out)?,
+        }
+
+        Ok(())
+    }
+
+    fn partial_body(
+        &mut self,
+        template: &Rc<Template>,
+        scope: Map<String, Value>,
+        isolated: bool,
+        out: &mut String,
+    ) -> Result<()> {
+        let (saved_root, saved_len) = (self.root, self.scopes.len());
+        self.scopes.push(scope);
+        if isolated {
+            self.root = saved_len;
+        }
+        let saved_template = self.template.replace(template.clone());
+        self.depth += 1;
+
+        let result = self.render_nodes(&template.nodes, out);
+
+        self.depth -= 1;
+        self.template = saved_template;
+        self.scopes.truncate(saved_len);
+        self.root = saved_root;
+        // `break` and `continue` do not leave the snippet
+        result.map(|_| ())
+    }
+
+    /// Loads and parses a snippet from the options or the host, once per
+    /// render.
+    fn load(&mut self, name: &str) -> Result<Option<Rc<Template>>> {
+        if let Some(template) = self.loaded.get(name) {
+            return Ok(Some(template.clone()));
+        }
+
+        let (source, variables) = if let Some(source) = self.partials.get(name) {
+            (source.clone(), Map::new())
+        } else if let Some(host) = self.host.as_mut() {
+            match host(&json!({ "kind": "partial", "name": name }))? {
+                Some(Value::String(source)) => (source, Map::new()),
+                Some(Value::Object(mut fields)) => {
+                    let Some(Value::String(source)) = fields.remove("source") else {
+                        return Ok(None);
+                    };
+                    match fields.remove("variables") {
+                        Some(Value::Object(variables)) => (source, variables),
+                        _ => (source, Map::new()),
+                    }
+                }
+                _ => return Ok(None),
+            }
+        } else {
+            return Ok(None);
+        };
+
+        let mut template = Template::parse(Some(name.to_string()), source)?;
+        template.variables = variables;
+        let template = Rc::new(template);
+        self.loaded.insert(name.to_string(), template.clone());
+        Ok(Some(template))
+    }
+
+    fn filtered(&mut self, value: &Filtered) -> Result<Value> {
+        let mut result = self.eval(&value.expr)?;
+        for filter in &value.filters {
+            result = self.filter(filter, result)?;
+        }
+        Ok(result)
+    }
+
+    fn filter(&mut self, filter: &Filter, input: Value) -> Result<Value> {
+        let args = filter
+            .args
+            .iter()
+            .map(|arg| self.eval(arg))
+            .collect::<Result<Vec<_>>>()?;
+        let kwargs = filter
+            .kwargs
+            .iter()
+            .map(|arg| Ok((arg.name.clone(), self.eval(&arg.value)?)))
+            .collect::<Result<Vec<_>>>()?;
+
+        let host_filter = self.host_filters.contains(&filter.name);
+        if !host_filter {
+            if let Some(result) = apply_filter(&filter.name, &input, &args, &kwargs) {
+                return result.map_err(|message| {
+                    self.error(filter.span, format!("{}: {}", filter.name, message))
+                });
+            }
+        }
+
+        if let Some(host) = self.host.as_mut() {
+            let mut host_args = args;
+            host_args.extend(kwargs.into_iter().map(|(name, value)| json!([name, value])));
+            let request = json!({
+                "kind": "filter",
+                "name": filter.name,
+                "input": input,
+                "args": host_args,
+            });
+            match host(&request)? {
+                Some(value) => return Ok(value),
+                None if host_filter => return Ok(Value::Null),
+                None => {}
+            }
+        }
+
+        if self.strict_filters {
+            return Err(self.error(filter.span, format!("Unknown filter '{}'", filter.name)));
+        }
+        Ok(input)
+    }
+
+    fn condition(&mut self, condition: &Condition) -> Result<bool> {
+        Ok(match condition {
+            Condition::Test { expr } => is_truthy(&self.eval(expr)?),
+            Condition::Compare { left, op, right } => {
+                let (lhs, rhs) = (self.operand(left)?, self.operand(right)?);
+                match compare_operands(&lhs, *op, &rhs) {
+                    Ok(result) => result,
+                    Err(message) if self.strict_comparisons => {
+                        return Err(self.error(self.span, message))
+                    }
+                    // A failed comparison must not take down the page
+                    Err(_) => false,
+                }
+            }
+            Condition::And { left, right } => self.condition(left)? && self.condition(right)?,
+            Condition::Or { left, right } => self.condition(left)? || self.condition(right)?,
+            Condition::Filtered { condition, filters } => {
+                let mut value = Value::Bool(self.condition(condition)?);
+                for filter in filters {
+                    value = self.filter(filter, value)?;
+                }
+                is_truthy(&value)
+            }
+        })
+    }
+
+    /// Evaluates one side of a comparison. Ranges are kept as bounds, so
+    /// `(1..n) contains x` works for any `n`.
+    fn operand(&self, expr: &Expr) -> Result<Operand> {
+        Ok(match expr {
+            Expr::Range { start, end } => Operand::Range(
+                to_number(&self.eval(start)?) as i64,
+                to_number(&self.eval(end)?) as i64,
+            ),
+            _ => Operand::literal(expr, self.eval(expr)?),
+        })
+    }
+
+    fn eval(&self, expr: &Expr) -> Result<Value> {
+        Ok(match expr {
+            Expr::Nil => Value::Null,
+            Expr::Empty | Expr::Blank => Value::String(String::new()),
+            Expr::Bool { value } => Value::Bool(*value),
+            Expr::Int { value } => Value::from(*value),
+            Expr::Float { value } => super::value::number(*value),
+            Expr::String { value } => Value::String(value.clone()),
+            Expr::Variable { name, path } => self
+                .lookup(name, path)?
+                .map_or(Value::Null, Cow::into_owned),
+            Expr::Range { start, end } => {
+                let start = to_number(&self.eval(start)?).trunc();
+                let end = to_number(&self.eval(end)?).trunc();
+                if end - start >= MAX_RANGE_LEN {
+                    return Err(self.error(self.span, "Range is too large"));
+                }
+                if end < start {
+                    Value::Array(Vec::new())
+                } else {
+                    (start as i64..=end as i64).map(Value::from).collect()
+                }
+            }
+        })
+    }
+
+    /// A top-level variable from the innermost scope defining it. Inside
+    /// `{% render %}` the scopes of the caller are out of reach.
+    fn variable(&self, name: &str) -> Option<&Value> {
+        self.scopes[self.root..]
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name))
+            .or_else(|| self.env.get(name))
+    }
+
+    /// Resolves a variable and its property path. `None` if any step is
+    /// undefined.
+    fn lookup(&self, name: &str, path: &[PathSegment]) -> Result<Option<Cow<'_, Value>>> {
+        let Some(root) = self.variable(name) else {
+            if self.strict_variables {
+                return Err(self.error(self.span, format!("Undefined variable '{}'", name)));
+            }
+            return Ok(None);
+        };
+
+        let mut current = Cow::Borrowed(root);
+        for segment in path {
+            let key = match segment {
+                PathSegment::Key { name } => Value::String(name.clone()),
+                PathSegment::Index { expr } => self.eval(expr)?,
+            };
+            let next = match current {
+                Cow::Borrowed(value) => index(value, &key),
+                Cow::Owned(value) => index(&value, &key).map(|v| Cow::Owned(v.into_owned())),
+            };
+            match next {
+                Some(next) => current = next,
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(current))
+    }
+}
+
+/// The `forloop` object for iteration `i` of `length`.
+fn forloop(i: usize, length: usize, parent: &Value) -> Value {
+    json!({
+        "first": i == 0,
+        "index": i + 1,
+        "index0": i,
+        "last": i + 1 == length,
+        "length": length,
+        "rindex": length - i,
+        "rindex0": length - i - 1,
+        "parentloop": parent,
+    })
+}
+
+fn template_error(template: &Template, span: Span, message: &str) -> Error {
+    let (line, column) = LineIndex::new(&template.source).position(span.start as usize);
+    let location = match &template.name {
+        Some(name) => format!(" in '{}'", name),
+        None => String::new(),
+    };
+    Error::new(
+        Status::GenericFailure,
+        format!("{} (line {}, column {}{})", message, line, column, location),
+    )
+}
+
+/// Renders a template with the given context, options and host.
+pub fn render_template<'h>(
+    source: String,
+    context: Map<String, Value>,
+    options: RenderOptions,
+    host: Option<&'h mut Host<'h>>,
+) -> Result<String> {
+    let template = Rc::new(Template::parse(None, source)?);
+    Renderer::new(context, options, host).render(template)
+}
+
+/// Parses the JSON render context, which must be an object.
+pub(crate) fn parse_context(context_json: Option<&str>) -> Result<Map<String, Value>> {
+    match context_json.map(str::trim) {
+        None | Some("") => Ok(Map::new()),
+        Some(json) => match serde_json::from_str(json) {
+            Ok(Value::Object(map)) => Ok(map),
+            Ok(_) => Err(Error::new(
+                Status::InvalidArg,
+                "Render context must be a JSON object".to_string(),
+            )),
+            Err(e) => Err(Error::new(
+                Status::InvalidArg,
+                format!("Invalid render context JSON: {}", e),
+            )),
+        },
+    }
+}
+
+/// Renders a Liquid template natively.
+///
+/// Supports the standard tags (`if`, `unless`, `case`, `for`, `tablerow`,
+/// `cycle`, `assign`, `capture`, `increment`, `render`, `include`, `raw`,
+/// `liquid`...), whitespace control, `forloop`/`tablerowloop`, the
+/// standard filters and every native filter of this crate. Platform
+/// filters, theme tags and snippet sources are requested from `host`.
+///
+/// # Arguments
+///
+/// * `template` - The Liquid source
+/// * `context_json` - The render context as a JSON object
+/// * `options` - Snippets, host filters and strictness
+/// * `host` - Callback answering `filter`, `tag` and `partial` requests;
+///   it returns `undefined` when it has no answer
+///
+/// # Returns
+///
+/// The rendered output, or an error with its line and column
+///
+/// # Examples
+///
+/// ```javascript
+/// render(
+///   "{% for p in products %}{{ p.title | upcase }}{% unless forloop.last %}, {% endunless %}{% endfor %}",
+///   JSON.stringify({ products: [{ title: "a" }, { title: "b" }] })
+/// )
+/// // "A, B"
+///
+/// render("{{ 'products.title' | t }}", "{}", { hostFilters: ["t"] }, (req) =>
+///   req.kind === "filter" ? translate(req.input) : undefined
+/// )
+/// ```
+#[napi]
+pub fn render(
+    template: String,
+    context_json: Option<String>,
+    options: Option<RenderOptions>,
+    host: Option<Function<'_, Value, Option<Value>>>,
+) -> Result<String> {
+    render_js(
+        Template::parse(None, template)?,
+        context_json,
+        options,
+        host,
+    )
+}
+
+/// Shared body of the JavaScript render entry points.
+pub(crate) fn render_js(
+    template: Template,
+    context_json: Option<String>,
+    options: Option<RenderOptions>,
+    host: Option<Function<'_, Value, Option<Value>>>,
+) -> Result<String> {
+    let context = parse_context(context_json.as_deref())?;
+    let options = options.unwrap_or_default();
+    let template = Rc::new(template);
+    match host {
+        Some(function) => {
+            let mut call = |request: &Value| function.call(request.clone());
+            Renderer::new(context, options, Some(&mut call)).render(template)
+        }
+        None => Renderer::new(context, options, None).render(template),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::{Path, PathBuf};
+
+    use crate::test_support::{example_theme_files, example_themes};
+
+    fn render_with(source: &str, context: Value) -> Result<String> {
+        let Value::Object(context) = context else {
+            panic!("context must be an object");
+        };
+        render_template(source.to_string(), context, RenderOptions::default(), None)
+    }
+
+    fn render_ok(source: &str, context: Value) -> String {
+        render_with(source, context).unwrap()
+    }
+
+    fn parity_dir() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/parity")
+    }
+
+    /// Store data shared with `scripts/parity-goldens.js`.
+    fn parity_context() -> Map<String, Value> {
+        let context = std::fs::read_to_string(parity_dir().join("context.json")).unwrap();
+        serde_json::from_str(&context).unwrap()
+    }
+
+    /// Renders like the LiquidJS engine of `scripts/parity-goldens.js`:
+    /// snippets come from the theme, `t` returns its key and other host
+    /// filters and tags are missing.
+    fn render_theme_file(
+        theme: &Path,
+        source: String,
+        context: Map<String, Value>,
+    ) -> Result<String> {
+        let mut host = |request: &Value| -> Result<Option<Value>> {
+            Ok(match request["kind"].as_str() {
+                Some("partial") => {
+                    let name = request["name"].as_str().unwrap_or_default();
+                    std::fs::read_to_string(theme.join("snippets").join(format!("{}.liquid", name)))
+                        .ok()
+                        .map(Value::String)
+                }
+                Some("filter") if request["name"] == "t" => Some(request["input"].clone()),
+                _ => None,
+            })
+        };
+        render_template(source, context, RenderOptions::default(), Some(&mut host))
+    }
+
+    #[test]
+    fn test_render_output_and_filters() {
+        let context = json!({"product": {"title": "camisa azul", "tags": ["a", "b"]}});
+        assert_eq!(
+            render_ok(
+                "{{ product.title | capitalize }} ({{ product.tags | join: ', ' }})",
+                context
+            ),
+            "Camisa azul (a, b)"
+        );
+        assert_eq!(
+            render_ok("{{ 'Tienda & Co' | escape }}{{ nada }}", json!({})),
+            "Tienda &amp; Co"
+        );
+        assert_eq!(
+            render_ok("{{ 10 | divided_by: 4 | plus: 0.5 }}", json!({})),
+            "3"
+        );
+        assert_eq!(render_ok("{{ x | no_such_filter }}", json!({"x": 1})), "1");
+    }
+
+    #[test]
+    fn test_render_assign_capture_and_counters() {
+        assert_eq!(
+            render_ok(
+                "{% assign n = 'x' | upcase %}{% capture c %}[{{ n }}]{% endcapture %}{{ c }}\
+                 {% increment k %}{% increment k %}{% decrement d %}{{ k }}",
+                json!({})
+            ),
+            "[X]01-12"
+        );
+    }
+
+    #[test]
+    fn test_render_conditions() {
+        let context = json!({"tags": ["sale"], "title": "", "price": 10, "list": [], "text": "5"});
+        let cases = [
+            (
+                "{% if tags contains 'sale' and price > 5 %}y{% endif %}",
+                "y",
+            ),
+            ("{% if title == empty and list == empty %}y{% endif %}", "y"),
+            ("{% if title == blank and nada == blank %}y{% endif %}", "y"),
+            ("{% if title %}truthy{% endif %}", "truthy"),
+            ("{% unless price < 5 %}u{% else %}e{% endunless %}", "u"),
+            (
+                "{% if price < 5 %}a{% elsif price == 10.0 %}b{% else %}c{% endif %}",
+                "b",
+            ),
+            (
+                "{% case price %}{% when 1, 10 %}ten{% else %}other{% endcase %}",
+                "ten",
+            ),
+            (
+                "{% if false or price | minus: 100 %}filtered{% endif %}",
+                "filtered",
+            ),
+            (
+                "before {% if text > 3 %}big{% endif %} after",
+                "before  after",
+            ),
+            ("{% unless 3 <= text %}u{% endunless %}", "u"),
+        ];
+        for (source, expected) in cases {
+            assert_eq!(render_ok(source, context.clone()), expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn test_render_for_loops() {
+        let context = json!({"items": [1, 2, 3, 4, 5], "hash": {"a": 1, "b": 2}});
+        assert_eq!(
+            render_ok(
+                "{% for i in items limit: 3 offset: 1 reversed %}{{ forloop.index }}:{{ i }}{% unless forloop.last %},{% endunless %}{% endfor %}",
+                context.clone()
+            ),
+            "1:4,2:3,3:2"
+        );
+        assert_eq!(
+            render_ok(
+                "{% for i in items %}{% if i == 2 %}{% continue %}{% endif %}{% if i > 3 %}{% break %}{% endif %}{{ i }}{% endfor %}",
+                context.clone()
+            ),
+            "13"
+        );
+        assert_eq!(
+            render_ok(
+                "{% for i in (1..2) %}{% for j in hash %}{{ forloop.parentloop.index }}{{ j[0] }}{{ j[1] }} {% endfor %}{% endfor %}",
+                context.clone()
+            ),
+            "1a1 1b2 2a1 2b2 "
+        );
+        assert_eq!(
+            render_ok(
+                "{% for i in nada %}x{% else %}empty{% endfor %}",
+                context.clone()
+            ),
+            "empty"
+        );
+        assert_eq!(
+            render_ok(
+                "{% for i in items limit: 4 %}{% cycle 'odd', 'even' %}{% endfor %}",
+                context
+            ),
+            "oddevenoddeven"
+        );
+    }
+
+    #[test]
+    fn test_render_tablerow() {
+        assert_eq!(
+            render_ok(
+                "{% tablerow i in items cols: 2 %}{{ i }}{{ tablerowloop.col_last }}{% endtablerow %}",
+                json!({"items": [1, 2, 3]})
+            ),
+            "<tr class=\"row1\"><td class=\"col1\">1false</td><td class=\"col2\">2true</td>\
+             </tr><tr class=\"row2\"><td class=\"col1\">3true</td></tr>"
+        );
+    }
+
+    #[test]
+    fn test_render_whitespace_control_and_verbatim() {
+        assert_eq!(
+            render_ok(
+                "<ul>\n  {%- for i in items -%}\n    <li>{{ i }}</li>\n  {%- endfor -%}\n</ul>\
+                 {% raw %}{{ x }}{% endraw %}{% comment %}{{ y }}{% endcomment %}",
+                json!({"items": [1, 2]})
+            ),
+            "<ul><li>1</li><li>2</li></ul>{{ x }}"
+        );
+        assert_eq!(
+            render_ok(
+                "<main>{% block content %}{{ title }}{% endblock %}</main>",
+                json!({"title": "Inicio"})
+            ),
+            "<main>Inicio</main>"
+        );
+    }
+
+    #[test]
+    fn test_render_partials() {
+        let options = RenderOptions {
+            partials: Some(HashMap::from([
+                (
+                    "card".to_string(),
+                    "{% assign inner = 1 %}[{{ card.title }}{{ label }}{{ outer }}]".to_string(),
+                ),
+                ("set".to_string(), "{% assign leaked = 'yes' %}".to_string()),
+            ])),
+            ..Default::default()
+        };
+        let Value::Object(context) =
+            json!({"products": [{"title": "a"}, {"title": "b"}], "outer": "!"})
+        else {
+            unreachable!()
+        };
+        let output = render_template(
+            "{% render 'card' for products, label: 'x' | upcase %}{% render 'card' with products[0] as card %}\
+             {{ inner }}{% include 'set' %}{{ leaked }}{% render 'missing' %}"
+                .to_string(),
+            context,
+            options,
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            output,
+            "[aX!][bX!][a!]yes<!-- Snippet 'missing' not found -->"
+        );
+    }
+
+    #[test]
+    fn test_render_partials_are_isolated() {
+        let options = RenderOptions {
+            partials: Some(HashMap::from([
+                (
+                    "s".to_string(),
+                    "[{{ secret }}|{{ item }}|{{ shop }}]".to_string(),
+                ),
+                (
+                    "i".to_string(),
+                    "({{ secret }}{% render 's' %})".to_string(),
+                ),
+            ])),
+            ..Default::default()
+        };
+        let Value::Object(context) = json!({"shop": "S"}) else {
+            unreachable!()
+        };
+        let output = render_template(
+            "{% assign secret = 'LEAK' %}{% for item in (1..2) %}{% render 's' %}{% endfor %}\
+             {% capture secret %}C{% endcapture %}{% include 'i' %}"
+                .to_string(),
+            context,
+            options,
+            None,
+        )
+        .unwrap();
+        assert_eq!(output, "[||S][||S](C[||S])");
+    }
+
+    #[test]
+    fn test_render_host_requests() {
+        let mut requests = Vec::new();
+        let mut host = |request: &Value| -> Result<Option<Value>> {
+            requests.push(request["name"].as_str().unwrap_or_default().to_string());
+            Ok(match (request["kind"].as_str(), request["name"].as_str()) {
+                (Some("filter"), Some("t")) => {
+                    Some(json!(format!("T({})", request["input"].as_str().unwrap())))
+                }
+                (Some("filter"), Some("lookup")) => None,
+                (Some("tag"), Some("section")) => Some(json!(format!(
+                    "<section>{}</section>",
+                    request["args"][0].as_str().unwrap()
+                ))),
+                (Some("tag"), Some("style")) => Some(json!("")),
+                (Some("partial"), Some("hello")) => {
+                    Some(json!({"source": "hi {{ who }}", "variables": {"who": "host"}}))
+                }
+                _ => None,
+            })
+        };
+        let options = RenderOptions {
+            host_filters: Some(vec!["t".to_string(), "lookup".to_string()]),
+            ..Default::default()
+        };
+        let output = render_template(
+            "{{ 'k' | t }}{{ 'a' | lookup | default: 'nil' }}{% section 'header' %}\
+             {% style %}p{}{% endstyle %}{% javascript %}go(){% endjavascript %}{% render 'hello' %}"
+                .to_string(),
+            Map::new(),
+            options,
+            Some(&mut host),
+        )
+        .unwrap();
+        assert_eq!(
+            output,
+            "T(k)nil<section>header</section><script>go()</script>hi host"
+        );
+        assert_eq!(
+            requests,
+            ["t", "lookup", "section", "style", "javascript", "hello"]
+        );
+    }
+
+    #[test]
+    fn test_render_errors() {
+        let strict = RenderOptions {
+            strict_filters: Some(true),
+            strict_variables: Some(true),
+            ..Default::default()
+        };
+        let error =
+            render_template("a\n  {{ x }}".to_string(), Map::new(), strict, None).unwrap_err();
+        assert_eq!(error.reason, "Undefined variable 'x' (line 2, column 3)");
+        let error = render_with("{{ 1 | nope }}", json!({})).map(|_| ());
+        assert!(error.is_ok());
+        let error = render_with("{% if a %}", json!({})).unwrap_err();
+        assert!(error
+            .reason
+            .starts_with("'if' tag was never closed (line 1, column 1)"));
+        assert!(parse_context(Some("[1]")).is_err());
+        let options = RenderOptions {
+            partials: Some(HashMap::from([(
+                "loop".to_string(),
+                "{% render 'loop' %}".to_string(),
+            )])),
+            ..Default::default()
+        };
+        let error = render_template("{% render 'loop' %}".to_string(), Map::new(), options, None)
+            .unwrap_err();
+        assert!(error.reason.contains("nested too deeply"));
+    }
+
+    /// Every template of the example themes renders with snippets loaded
+    /// through the host and representative store data.
+    #[test]
+    fn test_example_themes_render() {
+        let context = parity_context();
+        let files = example_theme_files("liquid");
+        assert!(files.len() > 100, "example themes not found");
+
+        let mut failures = Vec::new();
+        for file in &files {
+            let theme = file
+                .ancestors()
+                .find(|dir| dir.join("snippets").is_dir())
+                .unwrap()
+                .to_path_buf();
+            let source = std::fs::read_to_string(file).unwrap();
+            if let Err(error) = render_theme_file(&theme, source, context.clone()) {
+                failures.push(format!("{}: {}", file.display(), error.reason));
+            }
+        }
+        assert!(failures.is_empty(), "{:#?}", failures);
+    }
+
+    /// Output matches what LiquidJS renders for the cases of
+    /// `fixtures/parity`. Regenerate `expected/` with
+    /// `node scripts/parity-goldens.js` when adding cases.
+    #[test]
+    fn test_render_matches_liquidjs() {
+        let themes = example_themes();
+        let cases: Vec<Value> = serde_json::from_str(
+            &std::fs::read_to_string(parity_dir().join("cases.json")).unwrap(),
+        )
+        .unwrap();
+        assert!(!cases.is_empty());
+        for case in &cases {
+            let name = case["name"].as_str().unwrap();
+            let (source, theme) = match case["file"].as_str() {
+                Some(file) => {
+                    let path = themes.join(file);
+                    let theme = path
+                        .ancestors()
+                        .find(|dir| dir.join("snippets").is_dir())
+                        .unwrap()
+                        .to_path_buf();
+                    (std::fs::read_to_string(path).unwrap(), theme)
+                }
+                None => (
+                    case["template"].as_str().unwrap().to_string(),
+                    themes.clone(),
+                ),
+            };
+            let expected =
+                std::fs::read_to_string(parity_dir().join(format!("expected/{}.html", name)))
+                    .unwrap();
+            let output = render_theme_file(&theme, source, parity_context()).unwrap();
+            assert_eq!(output, expected, "{}", name);
+        }
+    }
+}
@@ -0,0 +1,347 @@
+/*
+ * Copyright 2025 Fasttify LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Section schemas.
+//!
+//! The `{% schema %}` block is found with the tokenizer and its body read
+//! with the tolerant JSON parser, so errors point at a line and column of
+//! the `.liquid` file.
+
+mod spec;
+mod validate;
+
+pub use validate::*;
+
+use serde_json::{Map, Value};
+
+use super::json5::{parse_json5, Json5Document};
+use super::lexer::{lex, TokenKind};
+use super::span::{LineIndex, SourceSpan};
+
+/// A problem with the schema block.
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaError {
+    /// `invalid-json`, `invalid-schema`, `unclosed-schema` or
+    /// `multiple-schemas`.
+    pub code: String,
+    pub message: String,
+    pub span: SourceSpan,
+}
+
+/// The schema of a section, in one call.
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtractedSchema {
+    /// Whether the template has a `{% schema %}` block.
+    pub found: bool,
+    /// The whole schema, or `None` when it is missing or invalid.
+    pub schema: Option<Value>,
+    /// Default value of every setting with an `id`. Settings without a
+    /// `default` get the one of their type.
+    pub settings: Map<String, Value>,
+    /// Block definitions, as written.
+    pub blocks: Vec<Value>,
+    pub presets: Vec<Value>,
+    pub errors: Vec<SchemaError>,
+}
+
+/// A schema body and where it starts in the template.
+struct SchemaSource<'a> {
+    body: &'a str,
+    offset: usize,
+}
+
+/// Finds the schema blocks of a template, plus the spans of `schema` tags
+/// that are never closed. Like the parser, a body runs from the tag to the
+/// next `endschema`, whatever it contains.
+fn find_schemas(template: &str) -> (Vec<SchemaSource<'_>>, Vec<(usize, usize)>) {
+    let tokens = lex(template);
+    let mut schemas = Vec::new();
+    let mut unclosed = Vec::new();
+    let mut tags = tokens.iter().filter(|token| token.kind == TokenKind::Tag);
+    while let Some(open) = tags.next() {
+        if open.name() != "schema" {
+            continue;
+        }
+        match tags.find(|token| token.name() == "endschema") {
+            Some(close) => schemas.push(SchemaSource {
+                body: &template[open.end()..close.start],
+                offset: open.end(),
+            }),
+            None => unclosed.push((open.start, open.end())),
+        }
+    }
+    (schemas, unclosed)
+}
+
+/// Parses a schema body. Errors are spans of the template.
+fn parse_schema(schema: &SchemaSource, index: &LineIndex) -> Result<Json5Document, SchemaError> {
+    let document = parse_json5(schema.body).map_err(|error| {
+        let offset = schema.offset + error.offset;
+        let end = schema.body[error.offset..]
+            .chars()
+            .next()
+            .map_or(offset, |c| offset + c.len_utf8());
+        SchemaError {
+            code: "invalid-json".to_string(),
+            message: error.message,
+            span: index.span(offset, end),
+        }
+    })?;
+    if !document.value.is_object() {
+        let (start, end) = document.spans[""];
+        return Err(SchemaError {
+            code: "invalid-schema".to_string(),
+            message: "The schema must be a JSON object".to_string(),
+            span: index.span(schema.offset + start, schema.offset + end),
+        });
+    }
+    Ok(document)
+}
+
+/// The first schema of a template, parsed.
+pub(crate) struct ReadSchema {
+    /// Whether the template has a `{% schema %}` tag.
+    pub found: bool,
+    pub document: Option<Json5Document>,
+    /// Offset of the schema body in the template.
+    pub offset: usize,
+    /// Errors sorted by position.
+    pub errors: Vec<SchemaError>,
+}
+
+/// Finds and parses the schema of a template.
+pub(crate) fn read_schema(template: &str, index: &LineIndex) -> ReadSchema {
+    let (schemas, unclosed) = find_schemas(template);
+
+    let mut errors: Vec<SchemaError> = unclosed
+        .into_iter()
+        .map(|(start, end)| SchemaError {
+            code: "unclosed-schema".to_string(),
+            message: "'schema' tag was never closed".to_string(),
+            span: index.span(start, end),
+        })
+        .collect();
+    for extra in schemas.iter().skip(1) {
+        errors.push(SchemaError {
+            code: "multiple-schemas".to_string(),
+            message: "A template can only have one schema block".to_string(),
+            span: index.span(extra.offset, extra.offset + extra.body.len()),
+        });
+    }
+
+    let mut read = ReadSchema {
+        found: !schemas.is_empty() || !errors.is_empty(),
+        document: None,
+        offset: 0,
+        errors: Vec::new(),
+    };
+    if let Some(schema) = schemas.first() {
+        read.offset = schema.offset;
+        match parse_schema(schema, index) {
+            Ok(document) => read.document = Some(document),
+            Err(error) => errors.push(error),
+        }
+    }
+    errors.sort_by_key(|error| error.span.start);
+    read.errors = errors;
+    read
+}
+
+/// Default value of a setting type, as `getDefaultValue` returns it.
+pub(crate) fn default_for_type(setting_type: &str) -> Value {
+    match setting_type {
+        "number" | "range" => Value::from(0),
+        "checkbox" => Value::Bool(false),
+        "color" => Value::from("#000000"),
+        "image_picker" | "video" | "file" => Value::Null,
+        // text, textarea, richtext, html, url, select, radio and the rest
+        _ => Value::from(""),
+    }
+}
+
+/// Defaults of a list of settings by `id`.
+pub(crate) fn setting_defaults(settings: Option<&Value>) -> Map<String, Value> {
+    let Some(Value::Array(settings)) = settings else {
+        return Map::new();
+    };
+    settings
+        .iter()
+        .filter_map(|setting| {
+            let id = setting.get("id")?.as_str()?;
+            let default = match setting.get("default") {
+                Some(value) if !value.is_null() => value.clone(),
+                _ => default_for_type(setting.get("type").and_then(Value::as_str).unwrap_or("")),
+            };
+            Some((id.to_string(), default))
+        })
+        .collect()
+}
+
+/// Extracts the schema of a section with its setting defaults, blocks and
+/// presets.
+///
+/// The body may use comments, trailing commas, single quotes and unquoted
+/// keys. A template with more than one schema block uses the first one.
+///
+/// # Arguments
+///
+/// * `template` - The Liquid source of the section
+///
+/// # Returns
+///
+/// The parsed schema, or the errors that prevent reading it with their
+/// position in the template
+///
+/// # Examples
+///
+/// ```javascript
+/// extractSchema(`{% schema %}
+/// {
+///   "name": "Hero",
+///   "settings": [
+///     { "type": "text", "id": "title", "default": "Hola" },
+///     { "type": "checkbox", "id": "show_button" }, // trailing comma below
+///   ],
+/// }
+/// {% endschema %}`)
+/// // {
+/// //   found: true,
+/// //   schema: { name: "Hero", settings: [...] },
+/// //   settings: { title: "Hola", show_button: false },
+/// //   blocks: [],
+/// //   presets: [],
+/// //   errors: []
+/// // }
+/// ```
+#[napi]
+pub fn extract_schema(template: String) -> ExtractedSchema {
+    let index = LineIndex::new(&template);
+    let read = read_schema(&template, &index);
+
+    let mut result = ExtractedSchema {
+        found: read.found,
+        schema: None,
+        settings: Map::new(),
+        blocks: Vec::new(),
+        presets: Vec::new(),
+        errors: read.errors,
+    };
+    if let Some(document) = read.document {
+        let schema = document.value;
+        let list = |key: &str| match schema.get(key) {
+            Some(Value::Array(items)) => items.clone(),
+            _ => Vec::new(),
+        };
+        result.settings = setting_defaults(schema.get("settings"));
+        result.blocks = list("blocks");
+        result.presets = list("presets");
+        result.schema = Some(schema);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    use crate::test_support::example_theme_files;
+
+    #[test]
+    fn test_extract_schema() {
+        let template = "<div>{{ section.settings.title }}</div>\n\
+            {% schema %}\n\
+            {\n\
+              // Shown in the editor\n\
+              name: 'Hero',\n\
+              \"settings\": [\n\
+                { \"type\": \"text\", \"id\": \"title\", \"default\": \"Hola  //  mundo\" },\n\
+                { \"type\": \"range\", \"id\": \"speed\", \"default\": null },\n\
+                { \"type\": \"color\", \"id\": \"bg\" },\n\
+                { \"type\": \"image_picker\", \"id\": \"image\" },\n\
+                { \"type\": \"header\", \"content\": \"Más\" },\n\
+                { \"type\": \"custom\", \"id\": \"other\" },\n\
+              ],\n\
+              \"blocks\": [{ \"type\": \"slide\", \"name\": \"Slide\" }],\n\
+              \"presets\": [{ \"name\": \"Hero\" }],\n\
+            }\n\
+            {% endschema %}";
+        let result = extract_schema(template.to_string());
+
+        assert!(result.found);
+        assert_eq!(result.errors, vec![]);
+        assert_eq!(
+            Value::Object(result.settings),
+            json!({
+                "title": "Hola  //  mundo",
+                "speed": 0,
+                "bg": "#000000",
+                "image": null,
+                "other": ""
+            })
+        );
+        assert_eq!(result.blocks, [json!({"type": "slide", "name": "Slide"})]);
+        assert_eq!(result.presets, [json!({"name": "Hero"})]);
+        assert_eq!(result.schema.unwrap()["name"], "Hero");
+    }
+
+    #[test]
+    fn test_schema_errors() {
+        let result = extract_schema("<p></p>".to_string());
+        assert!(!result.found && result.errors.is_empty() && result.schema.is_none());
+
+        let template = "{{ x }}\n{% schema %}\n{\n  \"name\": \"Hero\"\n  \"settings\": []\n}\n{% endschema %}";
+        let result = extract_schema(template.to_string());
+        assert!(result.found);
+        assert!(result.schema.is_none());
+        let error = &result.errors[0];
+        assert_eq!(error.code, "invalid-json");
+        assert_eq!(error.message, "Expected ',' or '}' after object member");
+        assert_eq!((error.span.line, error.span.column), (5, 3));
+
+        let result = extract_schema("{% schema %}[1]{% endschema %}".to_string());
+        assert_eq!(result.errors[0].code, "invalid-schema");
+
+        let template = format!("{{% schema %}}{}{{% endschema %}}", "[".repeat(200_000));
+        let result = extract_schema(template);
+        assert_eq!(result.errors[0].code, "invalid-json");
+        assert_eq!(result.errors[0].message, "Too deeply nested");
+
+        let template = "{% schema %}{\"name\": \"a\"}{% endschema %}{% schema %}{}{% endschema %}";
+        let result = extract_schema(template.to_string());
+        assert_eq!(result.schema, Some(json!({"name": "a"})));
+        assert_eq!(result.errors[0].code, "multiple-schemas");
+
+        let result = extract_schema("{% schema %}{\"name\": \"a\"}".to_string());
+        assert!(result.found);
+        assert_eq!(result.errors[0].code, "unclosed-schema");
+    }
+
+    #[test]
+    fn test_example_theme_schemas() {
+        let sections: Vec<_> = example_theme_files("liquid")
+            .into_iter()
+            .filter(|path| path.parent().is_some_and(|dir| dir.ends_with("sections")))
+            .collect();
+        assert!(!sections.is_empty());
+        for path in sections {
+            let result = extract_schema(std::fs::read_to_string(&path).unwrap());
+            assert_eq!(result.errors, vec![], "{}", path.display());
+        }
+    }
+}
@@ -0,0 +1,122 @@
+/*
+ * Copyright 2025 Fasttify LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Specification of the supported setting types.
+
+/// Values a setting accepts as `default`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum DefaultKind {
+    /// The type takes no default; one is ignored by the editor.
+    Unsupported,
+    Bool,
+    Number,
+    String,
+    /// One of the `value`s of the setting's `options`.
+    Option,
+    /// One of a fixed set of strings.
+    OneOf(&'static [&'static str]),
+}
+
+/// One setting type.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct SettingSpec {
+    pub name: &'static str,
+    /// Sidebar settings only show `content` in the editor and have no id
+    /// or value.
+    pub sidebar: bool,
+    pub default: DefaultKind,
+    /// Attributes the type needs besides `type` and `id`.
+    pub required: &'static [&'static str],
+}
+
+const fn input(
+    name: &'static str,
+    default: DefaultKind,
+    required: &'static [&'static str],
+) -> SettingSpec {
+    SettingSpec {
+        name,
+        sidebar: false,
+        default,
+        required,
+    }
+}
+
+const fn sidebar(name: &'static str) -> SettingSpec {
+    SettingSpec {
+        name,
+        sidebar: true,
+        default: DefaultKind::Unsupported,
+        required: &["content"],
+    }
+}
+
+/// Every supported setting type.
+pub(crate) const SETTING_SPECS: &[SettingSpec] = &[
+    sidebar("header"),
+    sidebar("paragraph"),
+    // Basic inputs
+    input("checkbox", DefaultKind::Bool, &[]),
+    input("number", DefaultKind::Number, &[]),
+    input("radio", DefaultKind::Option, &["options"]),
+    input("range", DefaultKind::Number, &["min", "max", "default"]),
+    input("select", DefaultKind::Option, &["options"]),
+    input("text", DefaultKind::String, &[]),
+    input("textarea", DefaultKind::String, &[]),
+    // Specialized inputs
+    input("article", DefaultKind::Unsupported, &[]),
+    input("blog", DefaultKind::Unsupported, &[]),
+    input("collection", DefaultKind::Unsupported, &[]),
+    input("collection_list", DefaultKind::Unsupported, &[]),
+    input("color", DefaultKind::String, &[]),
+    input("color_background", DefaultKind::String, &[]),
+    input("color_scheme", DefaultKind::String, &[]),
+    input(
+        "color_scheme_group",
+        DefaultKind::Unsupported,
+        &["definition", "role"],
+    ),
+    input("file", DefaultKind::Unsupported, &[]),
+    input("font_picker", DefaultKind::String, &["default"]),
+    input("html", DefaultKind::String, &[]),
+    input("image_picker", DefaultKind::Unsupported, &[]),
+    input("inline_richtext", DefaultKind::String, &[]),
+    input("link_list", DefaultKind::String, &[]),
+    input("liquid", DefaultKind::String, &[]),
+    input("metaobject", DefaultKind::Unsupported, &["metaobject_type"]),
+    input(
+        "metaobject_list",
+        DefaultKind::Unsupported,
+        &["metaobject_type"],
+    ),
+    input("page", DefaultKind::Unsupported, &[]),
+    input("product", DefaultKind::Unsupported, &[]),
+    input("product_list", DefaultKind::Unsupported, &[]),
+    input("richtext", DefaultKind::String, &[]),
+    input(
+        "text_alignment",
+        DefaultKind::OneOf(&["left", "center", "right"]),
+        &[],
+    ),
+    input("url", DefaultKind::String, &[]),
+    input("video", DefaultKind::Unsupported, &[]),
+    input("video_url", DefaultKind::Unsupported, &["accept"]),
+];
+
+/// The specification of a setting type.
+pub(crate) fn setting_spec(name: &str) -> Option<&'static SettingSpec> {
+    SETTING_SPECS.iter().find(|spec| spec.name == name)
+}
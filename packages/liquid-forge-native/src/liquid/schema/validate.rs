@@ -0,0 +1,807 @@
+/*
+ * Copyright 2025 Fasttify LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Schema validation against the setting type specification.
+
+use std::collections::HashMap;
+
+use serde_json::{Map, Value};
+
+use super::read_schema;
+use super::spec::{setting_spec, DefaultKind};
+use crate::liquid::json5::{pointer_token, Json5Document};
+use crate::liquid::span::{LineIndex, SourceSpan};
+
+/// Most steps a `range` slider can have.
+const MAX_RANGE_STEPS: f64 = 101.0;
+
+/// A problem found in a schema.
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaDiagnostic {
+    /// Stable identifier, such as `duplicate-id` or `default-out-of-range`.
+    pub code: String,
+    /// "error" or "warning".
+    pub severity: String,
+    pub message: String,
+    /// JSON pointer of the offending value inside the schema, such as
+    /// `/settings/2/default`. Empty for the schema as a whole.
+    pub pointer: String,
+    /// Where the value is in the template.
+    pub span: Option<SourceSpan>,
+}
+
+/// Result of validating a section schema.
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaValidation {
+    /// Whether the template has a `{% schema %}` block.
+    pub found: bool,
+    /// Whether no diagnostic is an error.
+    pub valid: bool,
+    /// Diagnostics in source order.
+    pub diagnostics: Vec<SchemaDiagnostic>,
+}
+
+#[derive(Clone, Copy)]
+enum Severity {
+    Error,
+    Warning,
+}
+
+/// What presets are checked against.
+#[derive(Default)]
+struct BlockDefinition {
+    limit: Option<u64>,
+    setting_ids: Vec<String>,
+}
+
+struct Validator<'a> {
+    document: &'a Json5Document,
+    /// Offset of the schema body in the template.
+    offset: usize,
+    index: &'a LineIndex<'a>,
+    diagnostics: Vec<SchemaDiagnostic>,
+}
+
+fn describe(value: &Value) -> String {
+    match value {
+        Value::String(s) => format!("'{}'", s),
+        other => other.to_string(),
+    }
+}
+
+impl Validator<'_> {
+    /// Span of the value at `pointer`, or of its closest ancestor that is
+    /// in the source.
+    fn span(&self, pointer: &str) -> Option<SourceSpan> {
+        let mut pointer = pointer;
+        loop {
+            if let Some((start, end)) = self.document.spans.get(pointer) {
+                return Some(self.index.span(self.offset + start, self.offset + end));
+            }
+            pointer = &pointer[..pointer.rfind('/')?];
+        }
+    }
+
+    fn report(&mut self, severity: Severity, code: &str, pointer: &str, message: String) {
+        self.diagnostics.push(SchemaDiagnostic {
+            code: code.to_string(),
+            severity: match severity {
+                Severity::Error => "error",
+                Severity::Warning => "warning",
+            }
+            .to_string(),
+            message,
+            pointer: pointer.to_string(),
+            span: self.span(pointer),
+        });
+    }
+
+    fn error(&mut self, code: &str, pointer: &str, message: String) {
+        self.report(Severity::Error, code, pointer, message);
+    }
+
+    /// The array at `pointer`, reporting anything else.
+    fn array<'v>(&mut self, value: Option<&'v Value>, pointer: &str) -> &'v [Value] {
+        match value {
+            Some(Value::Array(items)) => items,
+            None | Some(Value::Null) => &[],
+            Some(_) => {
+                self.error(
+                    "invalid-type",
+                    pointer,
+                    format!("'{}' must be an array", key(pointer)),
+                );
+                &[]
+            }
+        }
+    }
+
+    fn limit(&mut self, value: Option<&Value>, pointer: &str) -> Option<u64> {
+        let value = value?;
+        match value.as_u64().filter(|limit| *limit > 0) {
+            Some(limit) => Some(limit),
+            None => {
+                self.error(
+                    "invalid-limit",
+                    pointer,
+                    format!("'{}' must be a positive integer", key(pointer)),
+                );
+                None
+            }
+        }
+    }
+
+    fn schema(&mut self, schema: &Map<String, Value>) {
+        let section_ids = self.settings(schema.get("settings"), "/settings");
+        let max_blocks = self.limit(schema.get("max_blocks"), "/max_blocks");
+        let blocks = self.blocks(schema.get("blocks"));
+        self.presets(schema.get("presets"), &section_ids, &blocks, max_blocks);
+    }
+
+    /// Checks a list of settings and returns their ids.
+    fn settings(&mut self, settings: Option<&Value>, pointer: &str) -> Vec<String> {
+        let mut ids: HashMap<String, String> = HashMap::new();
+        let mut order = Vec::new();
+        for (i, setting) in self.array(settings, pointer).iter().enumerate() {
+            let pointer = format!("{}/{}", pointer, i);
+            if let Some(id) = self.setting(setting, &pointer) {
+                match ids.get(&id) {
+                    Some(first) => {
+                        let message = format!("Setting id '{}' is already used at {}", id, first);
+                        self.error("duplicate-id", &format!("{}/id", pointer), message);
+                    }
+                    None => {
+                        ids.insert(id.clone(), pointer);
+                        order.push(id);
+                    }
+                }
+            }
+        }
+        order
+    }
+
+    /// Checks one setting and returns its id.
+    fn setting(&mut self, setting: &Value, pointer: &str) -> Option<String> {
+        let Value::Object(setting) = setting else {
+            self.error(
+                "invalid-type",
+                pointer,
+                "A setting must be an object".to_string(),
+            );
+            return None;
+        };
+        let Some(setting_type) = setting.get("type").and_then(Value::as_str) else {
+            self.error(
+                "missing-type",
+                pointer,
+                "The setting has no 'type'".to_string(),
+            );
+            return None;
+        };
+        let id = setting
+            .get("id")
+            .and_then(Value::as_str)
+            .filter(|id| !id.is_empty());
+        let Some(spec) = setting_spec(setting_type) else {
+            self.error(
+                "unknown-setting-type",
+                &format!("{}/type", pointer),
+                format!("Unknown setting type '{}'", setting_type),
+            );
+            return id.map(str::to_string);
+        };
+        let name = id.unwrap_or(setting_type);
+
+        if !spec.sidebar && id.is_none() {
+            let message = format!("The '{}' setting has no 'id'", setting_type);
+            self.error("missing-id", pointer, message);
+        }
+        for attribute in spec.required {
+            if !setting.contains_key(*attribute) {
+                let message = format!("Setting '{}' needs '{}'", name, attribute);
+                self.error("missing-attribute", pointer, message);
+            }
+        }
+
+        let default_pointer = format!("{}/default", pointer);
+        let default = setting.get("default").filter(|value| !value.is_null());
+        if let Some(default) = default {
+            let expected = match spec.default {
+                DefaultKind::Unsupported => {
+                    let message = format!("'{}' settings take no default", setting_type);
+                    self.report(
+                        Severity::Warning,
+                        "default-not-supported",
+                        &default_pointer,
+                        message,
+                    );
+                    None
+                }
+                DefaultKind::Bool => (!default.is_boolean()).then_some("a boolean"),
+                DefaultKind::Number => (!default.is_number()).then_some("a number"),
+                DefaultKind::String | DefaultKind::Option => {
+                    (!default.is_string()).then_some("a string")
+                }
+                DefaultKind::OneOf(values) => {
+                    if !default
+                        .as_str()
+                        .is_some_and(|value| values.contains(&value))
+                    {
+                        let message =
+                            format!("Default of '{}' must be one of {}", name, values.join(", "));
+                        self.error("invalid-default", &default_pointer, message);
+                    }
+                    None
+                }
+            };
+            if let Some(expected) = expected {
+                let message = format!("Default of '{}' must be {}", name, expected);
+                self.error("invalid-default", &default_pointer, message);
+            }
+        }
+
+        if spec.default == DefaultKind::Option {
+            self.options(setting, pointer, name, default);
+        }
+        if setting_type == "range" {
+            self.range(setting, pointer, name);
+        }
+        id.map(str::to_string)
+    }
+
+    fn options(
+        &mut self,
+        setting: &Map<String, Value>,
+        pointer: &str,
+        name: &str,
+        default: Option<&Value>,
+    ) {
+        let Some(options) = setting.get("options") else {
+            return;
+        };
+        let options_pointer = format!("{}/options", pointer);
+        let options = self.array(Some(options), &options_pointer);
+        if options.is_empty() {
+            let message = format!("Setting '{}' needs at least one option", name);
+            self.error("missing-options", &options_pointer, message);
+            return;
+        }
+
+        let mut values: Vec<&Value> = Vec::new();
+        for (i, option) in options.iter().enumerate() {
+            let pointer = format!("{}/{}", options_pointer, i);
+            match option.get("value") {
+                Some(value @ (Value::String(_) | Value::Number(_)))
+                    if option.get("label").is_some() =>
+                {
+                    if values.contains(&value) {
+                        let message = format!("Option value {} is repeated", describe(value));
+                        self.error("invalid-option", &pointer, message);
+                    }
+                    values.push(value);
+                }
+                _ => {
+                    let message = "An option needs a 'value' and a 'label'".to_string();
+                    self.error("invalid-option", &pointer, message);
+                }
+            }
+        }
+
+        if let Some(default) = default.filter(|default| default.is_string()) {
+            if !values.contains(&default) {
+                let message = format!(
+                    "Default {} of '{}' is not one of its options",
+                    describe(default),
+                    name
+                );
+                self.error("invalid-default", &format!("{}/default", pointer), message);
+            }
+        }
+    }
+
+    fn range(&mut self, setting: &Map<String, Value>, pointer: &str, name: &str) {
+        let mut number = |key: &str| -> Option<f64> {
+            let value = setting.get(key)?;
+            let number = value.as_f64();
+            if number.is_none() {
+                let message = format!("'{}' of '{}' must be a number", key, name);
+                self.error("invalid-type", &format!("{}/{}", pointer, key), message);
+            }
+            number
+        };
+        let (Some(min), Some(max)) = (number("min"), number("max")) else {
+            return;
+        };
+        let step = number("step").unwrap_or(1.0);
+        let default = setting.get("default").and_then(Value::as_f64);
+
+        if min >= max {
+            let message = format!("'min' of '{}' must be lower than 'max'", name);
+            self.error("invalid-range", &format!("{}/max", pointer), message);
+            return;
+        }
+        if step <= 0.0 {
+            let message = format!("'step' of '{}' must be positive", name);
+            self.error("invalid-range", &format!("{}/step", pointer), message);
+            return;
+        }
+        let steps = (max - min) / step;
+        if steps > MAX_RANGE_STEPS {
+            let message = format!(
+                "Range '{}' has {} steps; the editor shows at most {}",
+                name, steps, MAX_RANGE_STEPS
+            );
+            self.report(Severity::Warning, "too-many-steps", pointer, message);
+        }
+
+        let default_pointer = format!("{}/default", pointer);
+        let Some(default) = default else {
+            return;
+        };
+        if default < min || default > max {
+            let message = format!(
+                "Default {} of '{}' is outside {}..{}",
+                default, name, min, max
+            );
+            self.error("default-out-of-range", &default_pointer, message);
+        } else {
+            let position = (default - min) / step;
+            if (position - position.round()).abs() > 1e-9 {
+                let message = format!(
+                    "Default {} of '{}' is not a multiple of the step {} from {}",
+                    default, name, step, min
+                );
+                self.error("default-off-step", &default_pointer, message);
+            }
+        }
+    }
+
+    fn blocks(&mut self, blocks: Option<&Value>) -> HashMap<String, BlockDefinition> {
+        let mut definitions: HashMap<String, BlockDefinition> = HashMap::new();
+        for (i, block) in self.array(blocks, "/blocks").iter().enumerate() {
+            let pointer = format!("/blocks/{}", i);
+            let Value::Object(block) = block else {
+                self.error(
+                    "invalid-type",
+                    &pointer,
+                    "A block must be an object".to_string(),
+                );
+                continue;
+            };
+            let Some(block_type) = block.get("type").and_then(Value::as_str) else {
+                self.error(
+                    "missing-type",
+                    &pointer,
+                    "The block has no 'type'".to_string(),
+                );
+                continue;
+            };
+            if definitions.contains_key(block_type) {
+                let message = format!("Block type '{}' is defined more than once", block_type);
+                self.error(
+                    "duplicate-block-type",
+                    &format!("{}/type", pointer),
+                    message,
+                );
+                continue;
+            }
+            // `@app` and `@theme` accept blocks defined elsewhere
+            if !block_type.starts_with('@') && !block.contains_key("name") {
+                let message = format!("Block '{}' needs 'name'", block_type);
+                self.error("missing-attribute", &pointer, message);
+            }
+            let definition = BlockDefinition {
+                limit: self.limit(block.get("limit"), &format!("{}/limit", pointer)),
+                setting_ids: self.settings(block.get("settings"), &format!("{}/settings", pointer)),
+            };
+            definitions.insert(block_type.to_string(), definition);
+        }
+        definitions
+    }
+
+    /// Warns about preset settings no setting defines.
+    fn preset_settings(&mut self, settings: Option<&Value>, pointer: &str, ids: &[String]) {
+        let Some(Value::Object(settings)) = settings else {
+            return;
+        };
+        for key in settings.keys() {
+            if !ids.contains(key) {
+                let message = format!("Preset sets '{}', which is not a setting", key);
+                let pointer = format!("{}/{}", pointer, pointer_token(key));
+                self.report(
+                    Severity::Warning,
+                    "unknown-preset-setting",
+                    &pointer,
+                    message,
+                );
+            }
+        }
+    }
+
+    fn presets(
+        &mut self,
+        presets: Option<&Value>,
+        section_ids: &[String],
+        blocks: &HashMap<String, BlockDefinition>,
+        max_blocks: Option<u64>,
+    ) {
+        let accepts_any = blocks.keys().any(|block_type| block_type.starts_with('@'));
+        for (i, preset) in self.array(presets, "/presets").iter().enumerate() {
+            let pointer = format!("/presets/{}", i);
+            let Value::Object(preset) = preset else {
+                self.error(
+                    "invalid-type",
+                    &pointer,
+                    "A preset must be an object".to_string(),
+                );
+                continue;
+            };
+            let name = match preset.get("name").and_then(Value::as_str) {
+                Some(name) => name.to_string(),
+                None => {
+                    self.error(
+                        "missing-attribute",
+                        &pointer,
+                        "Preset needs 'name'".to_string(),
+                    );
+                    format!("#{}", i)
+                }
+            };
+            self.preset_settings(
+                preset.get("settings"),
+                &format!("{}/settings", pointer),
+                section_ids,
+            );
+
+            // Blocks are a list, or an object keyed by block id
+            let blocks_pointer = format!("{}/blocks", pointer);
+            let preset_blocks: Vec<(String, &Value)> = match preset.get("blocks") {
+                Some(Value::Array(items)) => items
+                    .iter()
+                    .enumerate()
+                    .map(|(j, block)| (format!("{}/{}", blocks_pointer, j), block))
+                    .collect(),
+                Some(Value::Object(items)) => items
+                    .iter()
+                    .map(|(id, block)| (format!("{}/{}", blocks_pointer, pointer_token(id)), block))
+                    .collect(),
+                _ => Vec::new(),
+            };
+
+            let mut counts: Vec<(&str, u64)> = Vec::new();
+            for (block_pointer, block) in &preset_blocks {
+                let Some(block_type) = block.get("type").and_then(Value::as_str) else {
+                    self.error(
+                        "missing-type",
+                        block_pointer,
+                        "The block has no 'type'".to_string(),
+                    );
+                    continue;
+                };
+                match blocks.get(block_type) {
+                    Some(definition) => self.preset_settings(
+                        block.get("settings"),
+                        &format!("{}/settings", block_pointer),
+                        &definition.setting_ids,
+                    ),
+                    None if !accepts_any => {
+                        let message = format!(
+                            "Preset '{}' uses undefined block type '{}'",
+                            name, block_type
+                        );
+                        self.error(
+                            "unknown-block-type",
+                            &format!("{}/type", block_pointer),
+                            message,
+                        );
+                    }
+                    None => {}
+                }
+                match counts.iter_mut().find(|(t, _)| *t == block_type) {
+                    Some((_, count)) => *count += 1,
+                    None => counts.push((block_type, 1)),
+                }
+            }
+
+            for (block_type, count) in counts {
+                if let Some(limit) = blocks.get(block_type).and_then(|d| d.limit) {
+                    if count > limit {
+                        let message = format!(
+                            "Preset '{}' has {} '{}' blocks but their limit is {}",
+                            name, count, block_type, limit
+                        );
+                        self.error("block-limit-exceeded", &blocks_pointer, message);
+                    }
+                }
+            }
+            if let Some(max_blocks) = max_blocks {
+                if preset_blocks.len() as u64 > max_blocks {
+                    let message = format!(
+                        "Preset '{}' has {} blocks but 'max_blocks' is {}",
+                        name,
+                        preset_blocks.len(),
+                        max_blocks
+                    );
+                    self.error("max-blocks-exceeded", &blocks_pointer, message);
+                }
+            }
+        }
+    }
+}
+
+/// Last token of a pointer, for messages.
+fn key(pointer: &str) -> &str {
+    pointer.rsplit('/').next().unwrap_or(pointer)
+}
+
+/// Validates the `{% schema %}` of a section against the specification
+/// of every supported setting type.
+///
+/// Checks unknown setting types, missing and duplicate ids, defaults of
+/// the wrong type, `select` and `radio` options, `range` bounds and
+/// steps, block definitions, and presets against block limits.
+///
+/// # Arguments
+///
+/// * `template` - The Liquid source of the section
+///
+/// # Returns
+///
+/// Diagnostics with a stable `code`, a severity, the JSON pointer of the
+/// offending value and its span in the template. Errors reading the
+/// schema use the codes of `extractSchema`
+///
+/// # Examples
+///
+/// ```javascript
+/// validateSchema(`{% schema %}
+/// { "name": "Hero", "settings": [
+///   { "type": "range", "id": "speed", "min": 0, "max": 10, "step": 2, "default": 5 }
+/// ] }
+/// {% endschema %}`)
+/// // {
+/// //   found: true,
+/// //   valid: false,
+/// //   diagnostics: [{
+/// //     code: "default-off-step",
+/// //     severity: "error",
+/// //     message: "Default 5 of 'speed' is not a multiple of the step 2 from 0",
+/// //     pointer: "/settings/0/default",
+/// //     span: { line: 3, ... }
+/// //   }]
+/// // }
+/// ```
+#[napi]
+pub fn validate_schema(template: String) -> SchemaValidation {
+    let index = LineIndex::new(&template);
+    let read = read_schema(&template, &index);
+
+    let mut diagnostics: Vec<SchemaDiagnostic> = read
+        .errors
+        .into_iter()
+        .map(|error| SchemaDiagnostic {
+            code: error.code,
+            severity: "error".to_string(),
+            message: error.message,
+            pointer: String::new(),
+            span: Some(error.span),
+        })
+        .collect();
+    if let Some(document) = &read.document {
+        let mut validator = Validator {
+            document,
+            offset: read.offset,
+            index: &index,
+            diagnostics: Vec::new(),
+        };
+        if let Value::Object(schema) = &document.value {
+            validator.schema(schema);
+        }
+        diagnostics.extend(validator.diagnostics);
+    }
+    diagnostics.sort_by_key(|diagnostic| diagnostic.span.as_ref().map(|span| span.start));
+
+    SchemaValidation {
+        found: read.found,
+        valid: !diagnostics.iter().any(|d| d.severity == "error"),
+        diagnostics,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test_support::example_theme_files;
+
+    fn codes(schema: &str) -> Vec<(String, String)> {
+        let template = format!("{{% schema %}}{}{{% endschema %}}", schema);
+        validate_schema(template)
+            .diagnostics
+            .into_iter()
+            .map(|d| (d.code, d.pointer))
+            .collect()
+    }
+
+    #[test]
+    fn test_setting_diagnostics() {
+        let cases = [
+            (
+                r#"{"settings": [{"type": "texto", "id": "a"}]}"#,
+                vec![("unknown-setting-type", "/settings/0/type")],
+            ),
+            (
+                r#"{"settings": [{"type": "text", "id": "a"}, {"type": "url", "id": "a"}]}"#,
+                vec![("duplicate-id", "/settings/1/id")],
+            ),
+            (
+                r#"{"settings": [{"type": "text"}, {"type": "header"}, {"id": "x"}]}"#,
+                vec![
+                    ("missing-id", "/settings/0"),
+                    ("missing-attribute", "/settings/1"),
+                    ("missing-type", "/settings/2"),
+                ],
+            ),
+            (
+                r#"{"settings": [{"type": "select", "id": "s", "options": []}]}"#,
+                vec![("missing-options", "/settings/0/options")],
+            ),
+            (
+                r#"{"settings": [{"type": "select", "id": "s", "default": "c",
+                    "options": [{"value": "a", "label": "A"}, {"value": "a", "label": "B"}, {"label": "C"}]}]}"#,
+                vec![
+                    ("invalid-default", "/settings/0/default"),
+                    ("invalid-option", "/settings/0/options/1"),
+                    ("invalid-option", "/settings/0/options/2"),
+                ],
+            ),
+            (
+                r#"{"settings": [{"type": "range", "id": "r", "min": 0, "max": 10, "step": 2, "default": 12}]}"#,
+                vec![("default-out-of-range", "/settings/0/default")],
+            ),
+            (
+                r#"{"settings": [{"type": "range", "id": "r", "min": 0, "max": 10, "step": 2, "default": 5}]}"#,
+                vec![("default-off-step", "/settings/0/default")],
+            ),
+            (
+                r#"{"settings": [{"type": "range", "id": "r", "min": 5, "max": 5, "default": 5},
+                    {"type": "range", "id": "q", "min": 0, "max": 500, "default": 0}]}"#,
+                vec![
+                    ("invalid-range", "/settings/0/max"),
+                    ("too-many-steps", "/settings/1"),
+                ],
+            ),
+            (
+                r#"{"settings": [{"type": "checkbox", "id": "c", "default": "true"},
+                    {"type": "image_picker", "id": "i", "default": "x.png"},
+                    {"type": "text_alignment", "id": "t", "default": "justify"}]}"#,
+                vec![
+                    ("invalid-default", "/settings/0/default"),
+                    ("default-not-supported", "/settings/1/default"),
+                    ("invalid-default", "/settings/2/default"),
+                ],
+            ),
+            (
+                r#"{"settings": [{"type": "file", "id": "f"},
+                    {"type": "file", "id": "g", "default": "guide.pdf"}]}"#,
+                vec![("default-not-supported", "/settings/1/default")],
+            ),
+            (
+                r#"{"settings": [{"type": "range", "id": "r", "min": 0, "max": 1, "step": 0.1, "default": 0.3},
+                    {"type": "select", "id": "s", "options": [{"value": "a", "label": "A"}], "default": "a"}]}"#,
+                vec![],
+            ),
+        ];
+        for (schema, expected) in cases {
+            let expected: Vec<(String, String)> = expected
+                .into_iter()
+                .map(|(code, pointer)| (code.to_string(), pointer.to_string()))
+                .collect();
+            assert_eq!(codes(schema), expected, "{}", schema);
+        }
+    }
+
+    #[test]
+    fn test_block_and_preset_diagnostics() {
+        let schema = r#"{
+            "name": "Slideshow",
+            "max_blocks": 3,
+            "settings": [{"type": "text", "id": "title"}],
+            "blocks": [
+                {"type": "slide", "name": "Slide", "limit": 2, "settings": [{"type": "image_picker", "id": "image"}]},
+                {"type": "slide", "name": "Again"},
+                {"type": "quote", "limit": 0}
+            ],
+            "presets": [{
+                "name": "Default",
+                "settings": {"titulo": "x"},
+                "blocks": [
+                    {"type": "slide", "settings": {"image": null, "alt": ""}},
+                    {"type": "slide"},
+                    {"type": "slide"},
+                    {"type": "video"}
+                ]
+            }, {}]
+        }"#;
+        assert_eq!(
+            codes(schema),
+            [
+                ("duplicate-block-type", "/blocks/1/type"),
+                ("missing-attribute", "/blocks/2"),
+                ("invalid-limit", "/blocks/2/limit"),
+                ("unknown-preset-setting", "/presets/0/settings/titulo"),
+                ("block-limit-exceeded", "/presets/0/blocks"),
+                ("max-blocks-exceeded", "/presets/0/blocks"),
+                ("unknown-preset-setting", "/presets/0/blocks/0/settings/alt"),
+                ("unknown-block-type", "/presets/0/blocks/3/type"),
+                ("missing-attribute", "/presets/1"),
+            ]
+            .map(|(code, pointer)| (code.to_string(), pointer.to_string()))
+        );
+    }
+
+    #[test]
+    fn test_diagnostic_positions() {
+        let template = "<div></div>\n{% schema %}\n{\n  \"settings\": [\n    { \"type\": \"colour\", \"id\": \"bg\" }\n  ]\n}\n{% endschema %}";
+        let result = validate_schema(template.to_string());
+        assert!(result.found && !result.valid);
+        let diagnostic = &result.diagnostics[0];
+        assert_eq!(diagnostic.severity, "error");
+        assert_eq!(diagnostic.message, "Unknown setting type 'colour'");
+        let span = diagnostic.span.as_ref().unwrap();
+        assert_eq!((span.line, span.column), (5, 15));
+        assert_eq!(
+            &template[span.start as usize..span.end as usize],
+            "\"colour\""
+        );
+
+        let result = validate_schema("{% schema %}{,}{% endschema %}".to_string());
+        assert_eq!(result.diagnostics[0].code, "invalid-json");
+        let result = validate_schema("<p></p>".to_string());
+        assert!(!result.found && result.valid);
+    }
+
+    #[test]
+    fn test_example_theme_schemas() {
+        let mut errors = Vec::new();
+        for path in example_theme_files("liquid") {
+            if !path.parent().is_some_and(|dir| dir.ends_with("sections")) {
+                continue;
+            }
+            let result = validate_schema(std::fs::read_to_string(&path).unwrap());
+            let file = path.file_name().unwrap().to_string_lossy().to_string();
+            errors.extend(
+                result
+                    .diagnostics
+                    .into_iter()
+                    .filter(|d| d.severity == "error")
+                    .map(|d| (file.clone(), d.code, d.pointer)),
+            );
+        }
+        // `products_count` goes from 1 in steps of 2, so its default of 8
+        // can't be picked in the editor
+        assert_eq!(
+            errors,
+            [(
+                "featured-products.liquid".to_string(),
+                "default-off-step".to_string(),
+                "/settings/24/default".to_string()
+            )]
+        );
+    }
+}
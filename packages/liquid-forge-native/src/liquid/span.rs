@@ -0,0 +1,112 @@
+/*
+ * Copyright 2025 Fasttify LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Source locations shared by every template analysis export.
+
+use memchr::memchr_iter;
+use serde::Serialize;
+
+/// A range of the template source.
+///
+/// `start`/`end` are UTF-8 byte offsets (end exclusive). Lines and columns
+/// are 1-based; columns count Unicode characters from the start of the line.
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceSpan {
+    pub start: u32,
+    pub end: u32,
+    pub line: u32,
+    pub column: u32,
+    pub end_line: u32,
+    pub end_column: u32,
+}
+
+/// Maps byte offsets of a source to line/column positions.
+pub struct LineIndex<'a> {
+    source: &'a str,
+    line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub fn new(source: &'a str) -> Self {
+        let mut line_starts = vec![0];
+        line_starts.extend(memchr_iter(b'\n', source.as_bytes()).map(|i| i + 1));
+        Self {
+            source,
+            line_starts,
+        }
+    }
+
+    /// Returns the 1-based line and column of a byte offset.
+    pub fn position(&self, offset: usize) -> (u32, u32) {
+        let offset = offset.min(self.source.len());
+        let line = self.line_starts.partition_point(|start| *start <= offset) - 1;
+        let line_start = self.line_starts[line];
+        let column = self
+            .source
+            .get(line_start..offset)
+            .map_or(offset - line_start, |prefix| prefix.chars().count());
+        (line as u32 + 1, column as u32 + 1)
+    }
+
+    /// Builds the span covering `start..end`.
+    pub fn span(&self, start: usize, end: usize) -> SourceSpan {
+        let (line, column) = self.position(start);
+        let (end_line, end_column) = self.position(end);
+        SourceSpan {
+            start: start as u32,
+            end: end as u32,
+            line,
+            column,
+            end_line,
+            end_column,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_index_positions() {
+        let index = LineIndex::new("ab\nñé\n\nx");
+        assert_eq!(index.position(0), (1, 1));
+        assert_eq!(index.position(2), (1, 3));
+        assert_eq!(index.position(3), (2, 1));
+        assert_eq!(index.position(5), (2, 2));
+        assert_eq!(index.position(8), (3, 1));
+        assert_eq!(index.position(9), (4, 1));
+        assert_eq!(index.position(100), (4, 2));
+    }
+
+    #[test]
+    fn test_line_index_span() {
+        let index = LineIndex::new("a\n{{ x }}");
+        assert_eq!(
+            index.span(2, 9),
+            SourceSpan {
+                start: 2,
+                end: 9,
+                line: 2,
+                column: 1,
+                end_line: 2,
+                end_column: 8,
+            }
+        );
+    }
+}
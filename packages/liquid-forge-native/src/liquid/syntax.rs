@@ -0,0 +1,481 @@
+/*
+ * Copyright 2025 Fasttify LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Syntax checking of templates.
+//!
+//! Reports every parse error plus the tags and filters the engine does not
+//! register. The registries mirror what `LiquidEngine` registers: the
+//! LiquidJS built-ins followed by our own filters and tags, with the later
+//! registration winning, as `registerFilter` does.
+
+use super::ast::{walk, Condition, Filter, Node};
+use super::parser::parse_template;
+use super::span::{LineIndex, SourceSpan};
+
+/// Accepts any number of arguments.
+const VARIADIC: u8 = u8::MAX;
+
+/// A registered filter and how many positional arguments it takes. Named
+/// arguments are not counted.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct FilterSpec {
+    pub name: &'static str,
+    pub min_args: u8,
+    pub max_args: u8,
+    /// Implemented by `apply_filter` too, so filter chains run it natively.
+    pub native: bool,
+}
+
+const fn filter(name: &'static str, min_args: u8, max_args: u8) -> FilterSpec {
+    FilterSpec {
+        name,
+        min_args,
+        max_args,
+        native: false,
+    }
+}
+
+const fn native(name: &'static str, min_args: u8, max_args: u8) -> FilterSpec {
+    FilterSpec {
+        name,
+        min_args,
+        max_args,
+        native: true,
+    }
+}
+
+/// Every registered filter. Names appear once, with the signature of the
+/// registration that wins.
+pub(crate) const FILTERS: &[FilterSpec] = &[
+    // LiquidJS built-ins
+    native("abs", 0, 0),
+    filter("array_to_sentence_string", 0, 1),
+    native("at_least", 1, 1),
+    native("at_most", 1, 1),
+    filter("base64_decode", 0, 0),
+    filter("base64_encode", 0, 0),
+    native("capitalize", 0, 0),
+    native("ceil", 0, 0),
+    filter("cgi_escape", 0, 0),
+    native("compact", 0, 1),
+    native("concat", 1, 1),
+    filter("date_to_long_string", 0, 2),
+    filter("date_to_rfc822", 0, 0),
+    filter("date_to_string", 0, 2),
+    filter("date_to_xmlschema", 0, 0),
+    native("divided_by", 1, 2),
+    native("downcase", 0, 0),
+    native("escape_once", 0, 0),
+    filter("find", 1, 2),
+    filter("find_exp", 2, 2),
+    filter("find_index", 1, 2),
+    filter("find_index_exp", 2, 2),
+    native("first", 0, 0),
+    native("floor", 0, 0),
+    filter("group_by", 1, 1),
+    filter("group_by_exp", 2, 2),
+    filter("has", 1, 2),
+    filter("has_exp", 2, 2),
+    filter("inspect", 0, 1),
+    native("join", 0, 1),
+    native("json", 0, 1),
+    filter("jsonify", 0, 1),
+    native("last", 0, 0),
+    native("lstrip", 0, 1),
+    native("map", 1, 1),
+    native("minus", 1, 1),
+    native("modulo", 1, 1),
+    native("newline_to_br", 0, 0),
+    filter("normalize_whitespace", 0, 0),
+    filter("number_of_words", 0, 1),
+    native("plus", 1, 1),
+    filter("pop", 0, 0),
+    filter("push", 1, 1),
+    filter("raw", 0, 0),
+    filter("reject", 1, 2),
+    filter("reject_exp", 2, 2),
+    native("remove", 1, 1),
+    native("remove_first", 1, 1),
+    filter("remove_last", 1, 1),
+    native("replace", 1, 2),
+    native("replace_first", 1, 2),
+    filter("replace_last", 2, 2),
+    native("reverse", 0, 0),
+    native("round", 0, 1),
+    native("rstrip", 0, 1),
+    filter("sample", 0, 1),
+    filter("shift", 0, 0),
+    native("size", 0, 0),
+    native("slice", 1, 2),
+    filter("slugify", 0, 2),
+    native("sort", 0, 1),
+    native("sort_natural", 0, 1),
+    native("split", 1, 1),
+    native("strip", 0, 1),
+    native("strip_html", 0, 0),
+    native("strip_newlines", 0, 0),
+    native("sum", 0, 1),
+    native("times", 1, 1),
+    filter("to_integer", 0, 0),
+    native("truncatewords", 0, 2),
+    native("uniq", 0, 0),
+    filter("unshift", 1, 1),
+    native("upcase", 0, 0),
+    filter("uri_escape", 0, 0),
+    filter("url_decode", 0, 0),
+    native("url_encode", 0, 0),
+    filter("where_exp", 2, 2),
+    filter("xml_escape", 0, 0),
+    // Base filters
+    native("append", 1, 1),
+    filter("date", 0, 1),
+    native("default", 0, 1),
+    native("escape", 0, 0),
+    native("handleize", 0, 0),
+    native("pluralize", 1, 2),
+    native("prepend", 1, 1),
+    native("truncate", 0, 2),
+    filter("url", 0, 1),
+    native("where", 1, 2),
+    // Money filters
+    filter("cents_to_price", 0, 0),
+    filter("currency_symbol", 0, 0),
+    filter("money", 0, 1),
+    filter("money_without_currency", 0, 0),
+    filter("money_without_decimal", 0, 0),
+    // E-commerce filters
+    filter("collection_url", 0, 0),
+    filter("image_url", 0, VARIADIC),
+    filter("img_url", 0, VARIADIC),
+    filter("product_url", 0, 0),
+    filter("variant_url", 1, 1),
+    filter("within", 1, 1),
+    // HTML filters
+    filter("asset_url", 0, 0),
+    filter("default_pagination", 0, 0),
+    filter("img_tag", 0, 2),
+    filter("inline_asset_content", 0, 0),
+    filter("link_to", 1, 2),
+    filter("script_tag", 0, 3),
+    filter("stylesheet_tag", 0, 2),
+    // Cart filters
+    filter("cart_add_url", 0, 0),
+    filter("cart_change_url", 0, 1),
+    filter("cart_clear_url", 0, 0),
+    filter("cart_item_key", 0, 1),
+    filter("cart_update_url", 0, 0),
+    filter("cart_url", 0, 0),
+    filter("item_count_for_variant", 1, 1),
+    filter("line_items_for", 1, 1),
+    filter("remove_from_cart_url", 0, 0),
+    // Data access filters
+    filter("collection_by_handle", 0, 0),
+    filter("limit", 1, 1),
+    filter("product_by_handle", 0, 0),
+    filter("products_from_collection", 0, 0),
+    filter("products_to_json", 0, 0),
+    filter("fasttify_attributes", 0, 1),
+];
+
+/// Registered tags the grammar keeps as generic `Tag` nodes.
+const GENERIC_TAGS: &[&str] = &["filters"];
+
+/// Native filters LiquidJS doesn't register, so only filter chains and
+/// the native renderer can use them.
+pub(crate) const NATIVE_ONLY_FILTERS: &[&str] = &[
+    "excerpt",
+    "handle",
+    "markdown",
+    "reading_time",
+    "sanitize_html",
+    "simple_format",
+    "unescape_html",
+    "word_count",
+];
+
+pub(crate) fn filter_spec(name: &str) -> Option<&'static FilterSpec> {
+    FILTERS.iter().find(|spec| spec.name == name)
+}
+
+/// Whether `apply_filter` implements a filter called `name`.
+pub(crate) fn is_native_filter(name: &str) -> bool {
+    filter_spec(name).is_some_and(|spec| spec.native) || NATIVE_ONLY_FILTERS.contains(&name)
+}
+
+/// A problem found in a template.
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxDiagnostic {
+    /// Stable identifier: a parse error code such as `unclosed-block`,
+    /// `mismatched-end-tag` or `unterminated-string`, or `unknown-tag`,
+    /// `unknown-filter` or `filter-arity`.
+    pub code: String,
+    /// "error" for what the engine rejects, "warning" for what it
+    /// renders anyway (unknown filters leave the value unchanged).
+    pub severity: String,
+    pub message: String,
+    pub span: SourceSpan,
+}
+
+/// Result of checking a template.
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxCheck {
+    /// Whether no diagnostic is an error.
+    pub valid: bool,
+    /// Diagnostics in source order.
+    pub diagnostics: Vec<SyntaxDiagnostic>,
+}
+
+/// Filters of a condition, including those nested in `and`/`or`.
+fn condition_filters<'a>(condition: &'a Condition, filters: &mut Vec<&'a Filter>) {
+    match condition {
+        Condition::And { left, right } | Condition::Or { left, right } => {
+            condition_filters(left, filters);
+            condition_filters(right, filters);
+        }
+        Condition::Filtered {
+            condition,
+            filters: applied,
+        } => {
+            condition_filters(condition, filters);
+            filters.extend(applied);
+        }
+        Condition::Test { .. } | Condition::Compare { .. } => {}
+    }
+}
+
+/// Every filter applied in the tree, in source order.
+pub(crate) fn applied_filters(nodes: &[Node]) -> Vec<&Filter> {
+    let mut filters = Vec::new();
+    walk(nodes, &mut |node| match node {
+        Node::Output { value, .. } | Node::Echo { value, .. } | Node::Assign { value, .. } => {
+            filters.extend(&value.filters)
+        }
+        Node::Render(partial) | Node::Include(partial) => {
+            filters.extend(partial.args.iter().flat_map(|arg| &arg.value.filters))
+        }
+        Node::If { branches, .. } | Node::Unless { branches, .. } => {
+            for branch in branches {
+                condition_filters(&branch.condition, &mut filters);
+            }
+        }
+        _ => {}
+    });
+    filters.sort_by_key(|filter| filter.span.start);
+    filters
+}
+
+fn arity_message(spec: &FilterSpec, count: usize) -> Option<String> {
+    let plural = |n: u8| if n == 1 { "argument" } else { "arguments" };
+    if count < spec.min_args as usize {
+        Some(format!(
+            "'{}' needs {} {}, got {}",
+            spec.name,
+            spec.min_args,
+            plural(spec.min_args),
+            count
+        ))
+    } else if spec.max_args != VARIADIC && count > spec.max_args as usize {
+        Some(format!(
+            "'{}' takes at most {} {}, got {}",
+            spec.name,
+            spec.max_args,
+            plural(spec.max_args),
+            count
+        ))
+    } else {
+        None
+    }
+}
+
+/// Checks the syntax of a template, reporting every problem instead of
+/// stopping at the first one.
+///
+/// # Arguments
+///
+/// * `template` - The Liquid source
+///
+/// # Returns
+///
+/// Whether the template has errors, and every diagnostic with a stable
+/// `code` and a line/column span
+///
+/// # Examples
+///
+/// ```javascript
+/// checkSyntax("{% if a %}{% for x in y %}{% endif %}{{ x | upcasse }}{{ 'open }}")
+/// // {
+/// //   valid: false,
+/// //   diagnostics: [
+/// //     { code: "unclosed-block", severity: "error", message: "'for' tag was never closed", span: {...} },
+/// //     { code: "mismatched-end-tag", severity: "error", message: "'endif' found before 'for' was closed", span: {...} },
+/// //     { code: "unknown-filter", severity: "warning", message: "Unknown filter 'upcasse'", span: {...} },
+/// //     { code: "unterminated-string", severity: "error", ... }
+/// //   ]
+/// // }
+/// ```
+#[napi]
+pub fn check_syntax(template: String) -> SyntaxCheck {
+    let index = LineIndex::new(&template);
+    let parsed = parse_template(&template);
+
+    let mut diagnostics: Vec<SyntaxDiagnostic> = Vec::new();
+    let mut report = |code: &str, warning: bool, message: String, start: u32, end: u32| {
+        diagnostics.push(SyntaxDiagnostic {
+            code: code.to_string(),
+            severity: if warning { "warning" } else { "error" }.to_string(),
+            message,
+            span: index.span(start as usize, end as usize),
+        })
+    };
+
+    for error in &parsed.errors {
+        report(
+            error.code,
+            false,
+            error.message.clone(),
+            error.span.start,
+            error.span.end,
+        );
+    }
+    walk(&parsed.nodes, &mut |node| {
+        if let Node::Tag { name, span, .. } = node {
+            if !GENERIC_TAGS.contains(&name.as_str()) {
+                let message = format!("Unknown tag '{}'", name);
+                report("unknown-tag", false, message, span.start, span.end);
+            }
+        }
+    });
+    for applied in applied_filters(&parsed.nodes) {
+        let (start, end) = (applied.span.start, applied.span.end);
+        match filter_spec(&applied.name) {
+            None => {
+                let message = format!("Unknown filter '{}'", applied.name);
+                report("unknown-filter", true, message, start, end);
+            }
+            Some(spec) => {
+                if let Some(message) = arity_message(spec, applied.args.len()) {
+                    report("filter-arity", true, message, start, end);
+                }
+            }
+        }
+    }
+
+    diagnostics.sort_by_key(|diagnostic| diagnostic.span.start);
+    SyntaxCheck {
+        valid: !diagnostics.iter().any(|d| d.severity == "error"),
+        diagnostics,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    use serde_json::Value;
+
+    use crate::liquid::filters::apply_filter;
+
+    fn codes(template: &str) -> Vec<(String, String)> {
+        check_syntax(template.to_string())
+            .diagnostics
+            .into_iter()
+            .map(|d| (d.code, d.severity))
+            .collect()
+    }
+
+    #[test]
+    fn test_check_syntax() {
+        let cases: [(&str, &[(&str, &str)]); 10] = [
+            ("{% if a %}{{ a | upcase }}{% endif %}", &[]),
+            ("{% block content %}<main>{{ a }}</main>{% endblock %}", &[]),
+            ("{% if a %}x", &[("unclosed-block", "error")]),
+            (
+                "{% for x in y %}{% if x %}{% endfor %}",
+                &[("unclosed-block", "error"), ("mismatched-end-tag", "error")],
+            ),
+            ("{% endunless %}", &[("unexpected-end-tag", "error")]),
+            ("{% assign t = 'abc %}", &[("unterminated-string", "error")]),
+            ("{% include_relative 'x' %}", &[("unknown-tag", "error")]),
+            (
+                "{{ a | upcasse }}{% if b | t %}{% endif %}",
+                &[("unknown-filter", "warning"), ("unknown-filter", "warning")],
+            ),
+            (
+                "{{ a | append }}{{ a | truncate: 1, '.', 'x' }}{{ a | image_url: width: 300 }}",
+                &[("filter-arity", "warning"), ("filter-arity", "warning")],
+            ),
+            (
+                "{% liquid\n  assign x = a | plus\n  filters\n%}{% render 'c', t: a | money: 'x' %}",
+                &[("filter-arity", "warning")],
+            ),
+        ];
+        for (template, expected) in cases {
+            let expected: Vec<(String, String)> = expected
+                .iter()
+                .map(|(code, severity)| (code.to_string(), severity.to_string()))
+                .collect();
+            assert_eq!(codes(template), expected, "{}", template);
+        }
+    }
+
+    #[test]
+    fn test_diagnostics_keep_going() {
+        let template = "{{ 'open }}\n{% if a %}\n{{ b | nope }}\n{% bogus %}\n{% endfor %}";
+        let check = check_syntax(template.to_string());
+        assert!(!check.valid);
+        let found: Vec<(&str, u32, u32)> = check
+            .diagnostics
+            .iter()
+            .map(|d| (d.code.as_str(), d.span.line, d.span.column))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("unterminated-string", 1, 4),
+                ("unclosed-block", 2, 1),
+                ("unknown-filter", 3, 8),
+                ("unknown-tag", 4, 1),
+                ("unexpected-end-tag", 5, 1),
+            ]
+        );
+        assert_eq!(check.diagnostics[2].message, "Unknown filter 'nope'");
+    }
+
+    #[test]
+    fn test_filter_registry() {
+        let mut names = HashSet::new();
+        for spec in FILTERS {
+            assert!(
+                names.insert(spec.name),
+                "'{}' is registered twice",
+                spec.name
+            );
+            assert!(spec.min_args <= spec.max_args, "{}", spec.name);
+        }
+
+        // The `native` flags match what `apply_filter` implements
+        let implemented = |name| apply_filter(name, &Value::Null, &[], &[]).is_some();
+        for spec in FILTERS {
+            assert_eq!(implemented(spec.name), spec.native, "{}", spec.name);
+        }
+        for name in NATIVE_ONLY_FILTERS {
+            assert!(implemented(name) && filter_spec(name).is_none(), "{}", name);
+        }
+    }
+}
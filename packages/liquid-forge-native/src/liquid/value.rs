@@ -0,0 +1,254 @@
+/*
+ * Copyright 2025 Fasttify LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Runtime values.
+//!
+//! Render contexts are plain JSON, so values are [`serde_json::Value`]s.
+//! These helpers give them the Liquid semantics of LiquidJS, which the
+//! native renderer replaces: truthiness, output, property access, number
+//! coercion and equality.
+
+use std::borrow::Cow;
+
+use serde_json::{Number, Value};
+
+/// Only `false` and `nil` are falsy; `""`, `0` and `[]` are truthy.
+pub fn is_truthy(value: &Value) -> bool {
+    !matches!(value, Value::Null | Value::Bool(false))
+}
+
+/// Appends the printed form of a value: `nil` prints nothing, arrays print
+/// their elements without a separator and objects print as LiquidJS does.
+pub fn write_output(value: &Value, out: &mut String) {
+    match value {
+        Value::Null => {}
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::Number(n) => write_number(n, out),
+        Value::String(s) => out.push_str(s),
+        Value::Array(items) => items.iter().for_each(|item| write_output(item, out)),
+        Value::Object(_) => out.push_str("[object Object]"),
+    }
+}
+
+/// The printed form of a value, borrowed when it is already a string.
+pub fn to_text(value: &Value) -> Cow<'_, str> {
+    match value {
+        Value::String(s) => Cow::Borrowed(s),
+        _ => {
+            let mut out = String::new();
+            write_output(value, &mut out);
+            Cow::Owned(out)
+        }
+    }
+}
+
+fn write_number(n: &Number, out: &mut String) {
+    match n.as_f64() {
+        // Integral floats print without a fraction, as in JavaScript
+        Some(f) if n.is_f64() && f.fract() == 0.0 && f.abs() < 1e15 => {
+            out.push_str(&(f as i64).to_string())
+        }
+        _ => out.push_str(&n.to_string()),
+    }
+}
+
+/// Builds a number value, keeping integral results as integers. Values that
+/// JSON cannot represent become the strings JavaScript prints for them
+/// (`NaN`, `Infinity`, `-Infinity`), which still coerce back to numbers.
+pub fn number(f: f64) -> Value {
+    if f.fract() == 0.0 && f.abs() < 9.0e15 {
+        Value::from(f as i64)
+    } else if let Some(n) = Number::from_f64(f) {
+        Value::Number(n)
+    } else if f.is_nan() {
+        Value::from("NaN")
+    } else if f > 0.0 {
+        Value::from("Infinity")
+    } else {
+        Value::from("-Infinity")
+    }
+}
+
+/// Coerces a value to a number: numbers as is, numeric strings parsed,
+/// `true` as 1 and anything else as 0.
+pub fn to_number(value: &Value) -> f64 {
+    match value {
+        Value::Number(n) => n.as_f64().unwrap_or(0.0),
+        Value::String(s) => s.trim().parse().unwrap_or(0.0),
+        Value::Bool(true) => 1.0,
+        _ => 0.0,
+    }
+}
+
+/// Whether a value is a whole number (an integer or an integral string).
+pub fn is_integer(value: &Value) -> bool {
+    match value {
+        Value::Number(n) => n.is_i64() || n.is_u64(),
+        Value::String(s) => s.trim().parse::<i64>().is_ok(),
+        _ => false,
+    }
+}
+
+/// Number of elements of an array, characters of a string or keys of an
+/// object; 0 for anything else.
+pub fn size(value: &Value) -> usize {
+    match value {
+        Value::String(s) => s.chars().count(),
+        Value::Array(items) => items.len(),
+        Value::Object(map) => map.len(),
+        _ => 0,
+    }
+}
+
+/// Reads `key` from a value. Objects are looked up first; `size`, `first`
+/// and `last` work on arrays and strings, and on objects without such a
+/// key.
+pub fn property<'v>(value: &'v Value, key: &str) -> Option<Cow<'v, Value>> {
+    if let Value::Object(map) = value {
+        if let Some(found) = map.get(key) {
+            return Some(Cow::Borrowed(found));
+        }
+    }
+
+    match (key, value) {
+        ("size", Value::String(_) | Value::Array(_) | Value::Object(_)) => {
+            Some(Cow::Owned(Value::from(size(value))))
+        }
+        ("first", Value::Array(items)) => items.first().map(Cow::Borrowed),
+        ("last", Value::Array(items)) => items.last().map(Cow::Borrowed),
+        ("first", Value::String(s)) => s.chars().next().map(|c| Cow::Owned(c.to_string().into())),
+        ("last", Value::String(s)) => s.chars().last().map(|c| Cow::Owned(c.to_string().into())),
+        _ => None,
+    }
+}
+
+/// Reads `[index]` from a value: a position in an array (negative counts
+/// from the end) or a key of an object.
+pub fn index<'v>(value: &'v Value, index: &Value) -> Option<Cow<'v, Value>> {
+    match (value, index) {
+        (Value::Array(items), Value::Number(n)) => {
+            let i = n.as_i64()?;
+            let i = if i < 0 { items.len() as i64 + i } else { i };
+            usize::try_from(i)
+                .ok()
+                .and_then(|i| items.get(i))
+                .map(Cow::Borrowed)
+        }
+        (_, Value::String(key)) => property(value, key),
+        (Value::Object(map), _) => map.get(to_text(index).as_ref()).map(Cow::Borrowed),
+        _ => None,
+    }
+}
+
+/// The items a `for` loop visits: array elements, `[key, value]` pairs of
+/// an object, or the value itself for strings and numbers.
+pub fn iterate(value: Value) -> Vec<Value> {
+    match value {
+        Value::Array(items) => items,
+        Value::Object(map) => map
+            .into_iter()
+            .map(|(k, v)| Value::Array(vec![Value::String(k), v]))
+            .collect(),
+        Value::Null | Value::Bool(false) => Vec::new(),
+        Value::String(s) if s.is_empty() => Vec::new(),
+        other => vec![other],
+    }
+}
+
+/// `==` between two values. Numbers compare by value (`1 == 1.0`), also
+/// inside arrays and objects; other values must be structurally equal.
+pub fn equals(left: &Value, right: &Value) -> bool {
+    match (left, right) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64() == b.as_f64(),
+        (Value::Array(a), Value::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| equals(a, b))
+        }
+        (Value::Object(a), Value::Object(b)) => {
+            a.len() == b.len()
+                && a.iter()
+                    .all(|(key, value)| b.get(key).is_some_and(|other| equals(value, other)))
+        }
+        _ => left == right,
+    }
+}
+
+/// Whether a value equals the `empty` literal: an empty string, array or
+/// object.
+pub fn is_empty(value: &Value) -> bool {
+    match value {
+        Value::String(s) => s.is_empty(),
+        Value::Array(items) => items.is_empty(),
+        Value::Object(map) => map.is_empty(),
+        _ => false,
+    }
+}
+
+/// Whether a value equals the `blank` literal: `nil`, `false`, an empty
+/// or whitespace-only string, or an empty array or object.
+pub fn is_blank(value: &Value) -> bool {
+    match value {
+        Value::Null | Value::Bool(false) => true,
+        Value::String(s) => s.trim().is_empty(),
+        _ => is_empty(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_output() {
+        assert_eq!(to_text(&json!(null)), "");
+        assert_eq!(to_text(&json!(3.0)), "3");
+        assert_eq!(to_text(&json!(2.5)), "2.5");
+        assert_eq!(to_text(&json!(["a", 1, null, true])), "a1true");
+        assert_eq!(to_text(&json!({"a": 1})), "[object Object]");
+        assert_eq!(number(1.0 / 0.0), json!("Infinity"));
+        assert_eq!(to_number(&number(-1.0 / 0.0)), f64::NEG_INFINITY);
+    }
+
+    #[test]
+    fn test_property_and_index() {
+        let data = json!({"items": [1, 2, 3], "title": "Hola", "size": "L"});
+        assert_eq!(property(&data["items"], "size").as_deref(), Some(&json!(3)));
+        assert_eq!(property(&data["items"], "last").as_deref(), Some(&json!(3)));
+        assert_eq!(
+            property(&data["title"], "first").as_deref(),
+            Some(&json!("H"))
+        );
+        assert_eq!(property(&data, "size").as_deref(), Some(&json!("L")));
+        assert_eq!(
+            index(&data["items"], &json!(-1)).as_deref(),
+            Some(&json!(3))
+        );
+        assert_eq!(
+            index(&data, &json!("title")).as_deref(),
+            Some(&json!("Hola"))
+        );
+        assert!(index(&data["items"], &json!(7)).is_none());
+    }
+
+    #[test]
+    fn test_comparisons() {
+        assert!(equals(&json!(1), &json!(1.0)));
+        assert!(!equals(&json!("1"), &json!(1)));
+        assert!(equals(&json!({"a": [1, 2.0]}), &json!({"a": [1.0, 2]})));
+        assert!(is_blank(&json!("  ")) && !is_empty(&json!("  ")));
+        assert!(is_empty(&json!([])) && !is_blank(&json!(0)));
+    }
+}
@@ -0,0 +1,619 @@
+/*
+ * Copyright 2025 Fasttify LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Stylesheet minifier.
+//!
+//! Whitespace is only removed next to tokens where it can't matter, so
+//! descendant combinators (`a :hover`) and the operators of `calc()` keep
+//! theirs. Strings, `url()` values and Liquid markup are copied unchanged.
+
+use super::MinifyResult;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Space,
+    Comment,
+    String,
+    /// An unquoted `url(...)`.
+    Url,
+    /// `{{ ... }}` or `{% ... %}`.
+    Liquid,
+    Ident,
+    AtKeyword,
+    Hash,
+    /// A number with its unit or `%`, if any.
+    Number,
+    Delim,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Token {
+    kind: Kind,
+    start: usize,
+    end: usize,
+}
+
+/// Where a token is in its declaration.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Role {
+    Other,
+    /// The `:` between a property and its value.
+    Colon,
+    /// Part of the value of a property other than a custom property.
+    Value,
+    /// Part of a value whose zero lengths keep their unit.
+    UnitValue,
+}
+
+/// Units of `<length>`, whose zero can be written without one.
+const LENGTH_UNITS: &[&str] = &[
+    "px", "em", "rem", "ex", "ch", "vw", "vh", "vmin", "vmax", "vi", "vb", "svw", "svh", "lvw",
+    "lvh", "dvw", "dvh", "cm", "mm", "q", "in", "pt", "pc",
+];
+
+/// Properties where a zero length keeps its unit: in `flex: 1 0px` the
+/// `0px` is the basis, but `flex: 1 0` reads it as the shrink factor.
+const KEEPS_UNIT: &[&str] = &["flex", "-webkit-flex", "-ms-flex"];
+
+fn is_name_start(byte: u8) -> bool {
+    byte.is_ascii_alphabetic() || byte == b'_' || byte >= 0x80
+}
+
+fn is_name(byte: u8) -> bool {
+    is_name_start(byte) || byte.is_ascii_digit() || byte == b'-'
+}
+
+/// Whether an identifier starts at `i`.
+fn starts_ident(bytes: &[u8], i: usize) -> bool {
+    match bytes.get(i) {
+        Some(b'-') => bytes
+            .get(i + 1)
+            .is_some_and(|&b| b == b'-' || b == b'\\' || is_name_start(b)),
+        Some(b'\\') => bytes.get(i + 1).is_some_and(|&b| b != b'\n'),
+        Some(&b) => is_name_start(b),
+        None => false,
+    }
+}
+
+/// Whether a number starts at `i`, with its sign if any.
+fn starts_number(bytes: &[u8], i: usize) -> bool {
+    let digit_at = |i: usize| bytes.get(i).is_some_and(u8::is_ascii_digit);
+    let i = if matches!(bytes.get(i), Some(b'+' | b'-')) {
+        i + 1
+    } else {
+        i
+    };
+    digit_at(i) || (bytes.get(i) == Some(&b'.') && digit_at(i + 1))
+}
+
+/// End of the identifier characters starting at `i`, escapes included.
+fn name_end(bytes: &[u8], mut i: usize) -> usize {
+    while i < bytes.len() {
+        if bytes[i] == b'\\' && bytes.get(i + 1).is_some_and(|&b| b != b'\n') {
+            i += 1;
+            let hex = bytes[i..]
+                .iter()
+                .take(6)
+                .take_while(|b| b.is_ascii_hexdigit())
+                .count();
+            if hex > 0 {
+                i += hex;
+                // A space after a hex escape belongs to it
+                if bytes.get(i).is_some_and(u8::is_ascii_whitespace) {
+                    i += 1;
+                }
+            } else {
+                i += 1;
+            }
+        } else if is_name(bytes[i]) {
+            i += 1;
+        } else {
+            break;
+        }
+    }
+    i
+}
+
+/// End of the numeric part of a number starting at `i`: sign, digits,
+/// fraction and exponent.
+fn number_end(bytes: &[u8], mut i: usize) -> usize {
+    let digits = |mut i: usize| {
+        while bytes.get(i).is_some_and(u8::is_ascii_digit) {
+            i += 1;
+        }
+        i
+    };
+    if matches!(bytes.get(i), Some(b'+' | b'-')) {
+        i += 1;
+    }
+    i = digits(i);
+    if bytes.get(i) == Some(&b'.') && bytes.get(i + 1).is_some_and(u8::is_ascii_digit) {
+        i = digits(i + 1);
+    }
+    if matches!(bytes.get(i), Some(b'e' | b'E')) {
+        let sign = usize::from(matches!(bytes.get(i + 1), Some(b'+' | b'-')));
+        if bytes.get(i + 1 + sign).is_some_and(u8::is_ascii_digit) {
+            i = digits(i + 1 + sign);
+        }
+    }
+    i
+}
+
+/// End of an unquoted `url(` whose body starts at `i`, skipping Liquid
+/// markup, which may contain spaces and quotes.
+fn url_end(source: &str, mut i: usize) -> usize {
+    let bytes = source.as_bytes();
+    while i < bytes.len() {
+        match bytes[i] {
+            b')' => return i + 1,
+            b'\\' => i += 2,
+            b'{' if matches!(bytes.get(i + 1), Some(b'{' | b'%')) => i = liquid_end(source, i),
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+/// End of the Liquid output or tag starting at `i`.
+fn liquid_end(source: &str, i: usize) -> usize {
+    let close = if source.as_bytes()[i + 1] == b'{' {
+        "}}"
+    } else {
+        "%}"
+    };
+    source[i + 2..]
+        .find(close)
+        .map_or(source.len(), |end| i + 2 + end + 2)
+}
+
+fn lex(source: &str) -> Vec<Token> {
+    let bytes = source.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let start = i;
+        let byte = bytes[i];
+        let kind = if byte.is_ascii_whitespace() {
+            while bytes.get(i).is_some_and(u8::is_ascii_whitespace) {
+                i += 1;
+            }
+            Kind::Space
+        } else if source[i..].starts_with("/*") {
+            i = source[i + 2..]
+                .find("*/")
+                .map_or(bytes.len(), |end| i + 2 + end + 2);
+            Kind::Comment
+        } else if byte == b'"' || byte == b'\'' {
+            i += 1;
+            while i < bytes.len() && bytes[i] != byte && bytes[i] != b'\n' {
+                i += if bytes[i] == b'\\' { 2 } else { 1 };
+            }
+            i = (i + 1).min(bytes.len());
+            Kind::String
+        } else if byte == b'{' && matches!(bytes.get(i + 1), Some(b'{' | b'%')) {
+            i = liquid_end(source, i);
+            Kind::Liquid
+        } else if starts_number(bytes, i) {
+            i = number_end(bytes, i);
+            if bytes.get(i) == Some(&b'%') {
+                i += 1;
+            } else if starts_ident(bytes, i) {
+                i = name_end(bytes, i);
+            }
+            Kind::Number
+        } else if starts_ident(bytes, i) {
+            i = name_end(bytes, i);
+            let body = source[i..].strip_prefix('(').map(|rest| {
+                let quoted = rest.trim_start().starts_with(['"', '\'']);
+                (i + 1, quoted)
+            });
+            match body {
+                Some((body, false)) if source[start..i].eq_ignore_ascii_case("url") => {
+                    i = url_end(source, body);
+                    Kind::Url
+                }
+                _ => Kind::Ident,
+            }
+        } else if byte == b'@' && starts_ident(bytes, i + 1) {
+            i = name_end(bytes, i + 1);
+            Kind::AtKeyword
+        } else if byte == b'#' && bytes.get(i + 1).is_some_and(|&b| is_name(b) || b == b'\\') {
+            i = name_end(bytes, i + 1);
+            Kind::Hash
+        } else {
+            i += source[i..].chars().next().map_or(1, char::len_utf8);
+            Kind::Delim
+        };
+        // Escapes and unterminated tokens can run past the end
+        let end = i.min(bytes.len());
+        i = end;
+        tokens.push(Token { kind, start, end });
+    }
+    tokens
+}
+
+/// Finds the declarations among the tokens: statements ended by `;` or
+/// `}` rather than `{` that start with a property and a `:`.
+fn roles(source: &str, tokens: &[Token]) -> Vec<Role> {
+    let mut roles = vec![Role::Other; tokens.len()];
+    let significant: Vec<usize> = (0..tokens.len())
+        .filter(|&i| !matches!(tokens[i].kind, Kind::Space | Kind::Comment))
+        .collect();
+    let text = |i: usize| &source[tokens[i].start..tokens[i].end];
+    let mut depth = 0usize;
+    let mut statement = 0;
+    for (position, &i) in significant.iter().enumerate() {
+        let delim = if tokens[i].kind == Kind::Delim {
+            text(i)
+        } else {
+            ""
+        };
+        match delim {
+            "(" => depth += 1,
+            ")" => depth = depth.saturating_sub(1),
+            "{" if depth == 0 => statement = position + 1,
+            ";" | "}" if depth == 0 => {
+                mark_declaration(
+                    source,
+                    tokens,
+                    &significant[statement..position],
+                    &mut roles,
+                );
+                statement = position + 1;
+            }
+            _ => {}
+        }
+    }
+    mark_declaration(source, tokens, &significant[statement..], &mut roles);
+    roles
+}
+
+fn mark_declaration(source: &str, tokens: &[Token], statement: &[usize], roles: &mut [Role]) {
+    let mut rest = statement
+        .iter()
+        .skip_while(|&&i| tokens[i].kind == Kind::Liquid);
+    let Some(&property) = rest.next() else {
+        return;
+    };
+    if tokens[property].kind != Kind::Ident {
+        return;
+    }
+    let is_colon =
+        |i: usize| tokens[i].kind == Kind::Delim && source.as_bytes()[tokens[i].start] == b':';
+    let Some(colon) = statement.iter().position(|&i| is_colon(i)) else {
+        return;
+    };
+    roles[statement[colon]] = Role::Colon;
+    let name = &source[tokens[property].start..tokens[property].end];
+    if name.starts_with("--") {
+        return;
+    }
+    let role = if KEEPS_UNIT
+        .iter()
+        .any(|keeps| name.eq_ignore_ascii_case(keeps))
+    {
+        Role::UnitValue
+    } else {
+        Role::Value
+    };
+    for &i in &statement[colon + 1..] {
+        roles[i] = role;
+    }
+}
+
+/// `#AABBCC` as `#abc`, `#AABBCCDD` as `#abcd`, other colors lowercased.
+fn shorten_hex(text: &str) -> String {
+    let digits = &text[1..];
+    if !matches!(digits.len(), 3 | 4 | 6 | 8) || !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return text.to_string();
+    }
+    let digits = digits.to_ascii_lowercase();
+    let bytes = digits.as_bytes();
+    if bytes.len() >= 6 && bytes.chunks(2).all(|pair| pair[0] == pair[1]) {
+        let short: String = bytes.chunks(2).map(|pair| pair[0] as char).collect();
+        return format!("#{}", short);
+    }
+    format!("#{}", digits)
+}
+
+/// Drops leading and trailing zeros, and the unit of a zero length when
+/// `drop_unit` is set.
+fn shorten_number(text: &str, drop_unit: bool) -> String {
+    let bytes = text.as_bytes();
+    let numeric = number_end(bytes, 0);
+    let (number, unit) = text.split_at(numeric);
+    if number.contains(['e', 'E']) {
+        return text.to_string();
+    }
+    let (sign, digits) = match number.strip_prefix(['+', '-']) {
+        Some(digits) => (&number[..1], digits),
+        None => ("", number),
+    };
+    let digits = if digits.contains('.') {
+        digits.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        digits
+    };
+    let digits = digits.trim_start_matches('0');
+    if digits.is_empty() {
+        let length = LENGTH_UNITS
+            .iter()
+            .any(|length| unit.eq_ignore_ascii_case(length));
+        return if drop_unit && length {
+            "0".to_string()
+        } else {
+            format!("0{}", unit)
+        };
+    }
+    format!("{}{}{}", sign, digits, unit)
+}
+
+/// Whether the whitespace between `previous` and `next` can be removed.
+fn drops_space(previous: (&str, Role), next: (&str, Role), depth: usize) -> bool {
+    let around = |text: &str| matches!(text, "{" | "}" | ";" | ",");
+    let combinator = |text: &str| depth == 0 && matches!(text, ">" | "~" | "+");
+    around(previous.0)
+        || around(next.0)
+        || combinator(previous.0)
+        || combinator(next.0)
+        || matches!(previous.0, "(" | ":")
+        || matches!(next.0, ")" | "!")
+        || previous.1 == Role::Colon
+        || next.1 == Role::Colon
+}
+
+/// Minifies a stylesheet without changing what it applies.
+///
+/// Comments other than `/*! ... */` are removed, as is whitespace where it
+/// can't matter. In declaration values, hex colors and numbers are written
+/// in their shortest form and zero lengths lose their unit, except in
+/// functions like `calc()`, in `flex` and in custom properties.
+///
+/// # Arguments
+///
+/// * `css` - The stylesheet, which may contain Liquid markup
+///
+/// # Returns
+///
+/// The minified stylesheet with its size before and after, in bytes
+///
+/// # Examples
+///
+/// ```javascript
+/// minifyCss("a :hover { margin: 0px 0.50em; width: calc(100% - 0px); color: #FFFFFF; }")
+/// // { content: "a :hover{margin:0 .5em;width:calc(100% - 0px);color:#fff}", originalSize: 73, ... }
+/// ```
+#[napi]
+pub fn minify_css(css: String) -> MinifyResult {
+    let tokens = lex(&css);
+    let roles = roles(&css, &tokens);
+    let mut out = String::with_capacity(css.len());
+    // The last token written, as its text if it's a delimiter or a comment
+    let mut previous: Option<(&str, Role)> = None;
+    let mut space = false;
+    let mut depth = 0usize;
+
+    for (token, &role) in tokens.iter().zip(&roles) {
+        let text = &css[token.start..token.end];
+        let delim = if token.kind == Kind::Delim { text } else { "" };
+        match token.kind {
+            Kind::Space => {
+                space = true;
+                continue;
+            }
+            Kind::Comment if !text.starts_with("/*!") => {
+                space = true;
+                continue;
+            }
+            _ => {}
+        }
+        if delim == ")" {
+            depth = depth.saturating_sub(1);
+        }
+        if delim == "}" && previous.is_some_and(|(text, _)| text == ";") {
+            out.pop();
+        }
+        if let Some(last) = previous {
+            let kept_comment = token.kind == Kind::Comment || last.0.starts_with("/*!");
+            // `{ {%` joined would read as a Liquid output
+            let opens_liquid = out.ends_with('{') && text.starts_with('{');
+            if space && !kept_comment && (opens_liquid || !drops_space(last, (delim, role), depth))
+            {
+                out.push(' ');
+            }
+        }
+        match token.kind {
+            Kind::Hash if matches!(role, Role::Value | Role::UnitValue) => {
+                out.push_str(&shorten_hex(text))
+            }
+            Kind::Number if matches!(role, Role::Value | Role::UnitValue) => {
+                out.push_str(&shorten_number(text, depth == 0 && role == Role::Value))
+            }
+            Kind::Url => {
+                let body = &text[4..text.len() - usize::from(text.ends_with(')'))];
+                out.push_str(&text[..4]);
+                out.push_str(body.trim());
+                out.push(')');
+            }
+            _ => out.push_str(text),
+        }
+        if delim == "(" {
+            depth += 1;
+        }
+        let written = if token.kind == Kind::Comment {
+            text
+        } else {
+            delim
+        };
+        previous = Some((written, role));
+        space = false;
+    }
+
+    MinifyResult::new(css.len(), out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test_support::example_theme_files;
+
+    fn minify(css: &str) -> String {
+        minify_css(css.to_string()).content
+    }
+
+    #[test]
+    fn test_minify_css_whitespace() {
+        let cases = [
+            (
+                ".a  >  .b ,\n.c + .d ~ .e {\n  color : red ;\n  margin: 0 auto !important;\n}\n",
+                ".a>.b,.c+.d~.e{color:red;margin:0 auto!important}",
+            ),
+            ("a :hover, a:hover { x: y }", "a :hover,a:hover{x:y}"),
+            (
+                "div { width: calc( 100% - (2 * 1px) ); height: min(1px + 2px, 3vh) }",
+                "div{width:calc(100% - (2 * 1px));height:min(1px + 2px,3vh)}",
+            ),
+            (
+                "li:nth-child( 2n + 1 ) { color: red; ; }",
+                "li:nth-child(2n + 1){color:red;}",
+            ),
+            (
+                "@media screen and (min-width: 768px) { a { b: c } }",
+                "@media screen and (min-width:768px){a{b:c}}",
+            ),
+            ("a/* x */b { c: d/**/e }", "a b{c:d e}"),
+            (
+                "/*! License */\n.a { b: c } /*! keep */ .d{}",
+                "/*! License */.a{b:c}/*! keep */.d{}",
+            ),
+        ];
+        for (css, expected) in cases {
+            assert_eq!(minify(css), expected, "{}", css);
+        }
+    }
+
+    #[test]
+    fn test_minify_css_preserves_strings_urls_and_liquid() {
+        let cases = [
+            (
+                ".a::before { content: '  a : b ;  /* c */ '; font-family: \"Open  Sans\" }",
+                ".a::before{content:'  a : b ;  /* c */ ';font-family:\"Open  Sans\"}",
+            ),
+            (
+                ".a { background: url( img/a b.png ) no-repeat, url( \"x y.png\" ) }",
+                ".a{background:url(img/a b.png) no-repeat,url(\"x y.png\")}",
+            ),
+            (
+                ".a { background: url({{ 'hero image.png' | asset_url }}) }",
+                ".a{background:url({{ 'hero image.png' | asset_url }})}",
+            ),
+            (
+                "{% if settings.dark %}\n.a { color: {{ settings.color }} }\n{% endif %}",
+                "{% if settings.dark %} .a{color:{{ settings.color }}}{% endif %}",
+            ),
+            (".\\31 0 { a: b }", ".\\31 0{a:b}"),
+            (
+                ".a { {% if b %}color: red{% endif %} } .c { {{ d }} }",
+                ".a{ {% if b %}color:red{% endif %}}.c{ {{ d }}}",
+            ),
+        ];
+        for (css, expected) in cases {
+            assert_eq!(minify(css), expected, "{}", css);
+        }
+    }
+
+    #[test]
+    fn test_minify_css_shortens_values() {
+        let cases = [
+            (
+                "#FFFFFF, #AaBbCc { color: #FFFFFF; border-color: #AABBCCDD #123456 #ABCD }",
+                "#FFFFFF,#AaBbCc{color:#fff;border-color:#abcd #123456 #abcd}",
+            ),
+            (
+                "a { margin: 0px -0.50em 1.0px 010%; transition: 0s; top: 0%; z: 0.0 }",
+                "a{margin:0 -.5em 1px 10%;transition:0s;top:0%;z:0}",
+            ),
+            (
+                "a { width: calc(0px + 0.5em); --gap: 0px; --c: #FFFFFF; x: 1e3px }",
+                "a{width:calc(0px + .5em);--gap:0px;--c:#FFFFFF;x:1e3px}",
+            ),
+            (
+                "@keyframes f { 0% { opacity: 0.0 } 100.0% { opacity: 1 } }",
+                "@keyframes f{0%{opacity:0}100.0%{opacity:1}}",
+            ),
+            (
+                ".a { &:not(#AABBCC) { color: #AABBCC } }",
+                ".a{&:not(#AABBCC){color:#abc}}",
+            ),
+            (
+                "a { flex: 1 0px; -webkit-flex: 1.0 1 0.0px; margin: 0px }",
+                "a{flex:1 0px;-webkit-flex:1 1 0px;margin:0}",
+            ),
+        ];
+        for (css, expected) in cases {
+            assert_eq!(minify(css), expected, "{}", css);
+        }
+    }
+
+    #[test]
+    fn test_minify_css_reports_sizes() {
+        let result = minify_css("a {  color: red;  }".to_string());
+        assert_eq!(result.content, "a{color:red}");
+        assert_eq!((result.original_size, result.processed_size), (19, 12));
+        assert_eq!(result.bytes_saved, 7);
+        assert!((result.compression_ratio - 7.0 / 19.0 * 100.0).abs() < 1e-9);
+
+        let empty = minify_css(String::new());
+        assert_eq!((empty.content.as_str(), empty.compression_ratio), ("", 0.0));
+    }
+
+    #[test]
+    fn test_minify_css_example_themes() {
+        // Strings and Liquid survive unchanged, in order
+        fn verbatim(source: &str) -> Vec<&str> {
+            lex(source)
+                .into_iter()
+                .filter(|token| matches!(token.kind, Kind::String | Kind::Liquid))
+                .map(|token| &source[token.start..token.end])
+                .collect()
+        }
+
+        let files = example_theme_files("css");
+        assert!(!files.is_empty());
+        for path in files {
+            let source = std::fs::read_to_string(&path).unwrap();
+            let result = minify_css(source.clone());
+            assert_eq!(
+                verbatim(&result.content),
+                verbatim(&source),
+                "{}",
+                path.display()
+            );
+            assert_eq!(
+                minify(&result.content),
+                result.content,
+                "{}",
+                path.display()
+            );
+            assert!(
+                result.processed_size < result.original_size,
+                "{}",
+                path.display()
+            );
+        }
+    }
+}
@@ -0,0 +1,580 @@
+/*
+ * Copyright 2025 Fasttify LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Liquid template minifier.
+//!
+//! The template is split with the Liquid lexer and only its text is
+//! rewritten, following the HTML around it:
+//!
+//! - Liquid outputs, tags and `{% raw %}` bodies are copied unchanged;
+//! - `{% comment %}` and `{% doc %}` blocks are removed, applying their
+//!   whitespace-control dashes so the output renders the same;
+//! - HTML comments are removed, except conditional comments and comments
+//!   that contain Liquid code;
+//! - `<pre>`, `<textarea>` and `<script>` contents, `{% schema %}` and
+//!   `{% javascript %}` bodies and quoted attribute values are kept as is;
+//! - `<style>` and `{% stylesheet %}` bodies are minified as with
+//!   `minifyCss`;
+//! - everywhere else, runs of whitespace collapse to a single newline if
+//!   they contain one, or to a single space.
+
+use crate::liquid::{lex, Token, TokenKind};
+
+use super::{minify_css, MinifyResult};
+
+/// Options for `minifyLiquid`.
+#[napi(object)]
+#[derive(Default)]
+pub struct MinifyLiquidOptions {
+    /// Remove `{% comment %}` and `{% doc %}` blocks (default: true).
+    pub remove_liquid_comments: Option<bool>,
+    /// Remove HTML comments (default: true).
+    pub remove_html_comments: Option<bool>,
+}
+
+/// Where the minifier is in the HTML of the text tokens.
+#[derive(Debug, Clone, PartialEq)]
+enum Html {
+    Text,
+    /// Inside `<name ...>`. `quote` is set inside a quoted attribute value.
+    Tag {
+        name: String,
+        closing: bool,
+        quote: Option<char>,
+    },
+    /// Inside an HTML comment that is kept.
+    Comment,
+    /// Inside `<pre>`, `<textarea>` or `<script>`, kept verbatim up to the
+    /// closing tag.
+    Verbatim {
+        name: String,
+    },
+    /// Inside `<style>`.
+    Style,
+}
+
+/// Elements whose content is copied unchanged.
+const VERBATIM_ELEMENTS: &[&str] = &["pre", "textarea", "script"];
+
+/// Liquid blocks whose body is copied unchanged.
+const VERBATIM_BLOCKS: &[&str] = &["schema", "javascript"];
+
+/// Liquid blocks whose body is CSS.
+const STYLE_BLOCKS: &[&str] = &["stylesheet", "style"];
+
+struct Minifier {
+    out: String,
+    html: Html,
+    /// Closing tag of the Liquid block being copied verbatim.
+    verbatim_block: Option<String>,
+    /// Closing tag of the Liquid block holding CSS.
+    style_block: Option<String>,
+    /// CSS of the `<style>` element or Liquid block being read, minified
+    /// as a whole when it ends.
+    style: Option<String>,
+    remove_html_comments: bool,
+    /// The last character written was collapsed whitespace.
+    after_space: bool,
+    /// A removed comment trimmed the whitespace after it.
+    trim_next: bool,
+}
+
+impl Minifier {
+    fn push_str(&mut self, text: &str) {
+        if let Some(css) = &mut self.style {
+            return css.push_str(text);
+        }
+        if !text.is_empty() {
+            self.out.push_str(text);
+            self.after_space = false;
+        }
+    }
+
+    /// Writes a collapsed run of whitespace, merging it with a run written
+    /// just before (around a removed comment).
+    fn push_space(&mut self, run: &str) {
+        let collapsed = if run.contains('\n') { '\n' } else { ' ' };
+        if self.after_space {
+            if collapsed == '\n' && self.out.ends_with(' ') {
+                self.out.pop();
+                self.out.push('\n');
+            }
+            return;
+        }
+        self.out.push(collapsed);
+        self.after_space = true;
+    }
+
+    /// Writes the minified CSS of the style being read.
+    fn end_style(&mut self) {
+        if let Some(css) = self.style.take() {
+            self.push_str(&minify_css(css).content);
+        }
+    }
+
+    fn liquid(&mut self, token: &Token<'_>) {
+        let mut starts_style = false;
+        if token.kind == TokenKind::Tag {
+            let name = token.name();
+            if let Some(end) = &self.verbatim_block {
+                if name == end {
+                    self.verbatim_block = None;
+                }
+            } else if self.style_block.as_ref().is_some_and(|end| name == end) {
+                self.style_block = None;
+                self.end_style();
+            } else if VERBATIM_BLOCKS.contains(&name) {
+                self.verbatim_block = Some(format!("end{}", name));
+            } else if STYLE_BLOCKS.contains(&name) && self.style.is_none() {
+                self.style_block = Some(format!("end{}", name));
+                starts_style = true;
+            }
+        }
+        self.push_str(token.source);
+        if starts_style {
+            self.style = Some(String::new());
+        }
+    }
+
+    fn text(&mut self, mut text: &str) {
+        if std::mem::take(&mut self.trim_next) {
+            text = text.trim_start();
+        }
+        if self.verbatim_block.is_some() || self.style_block.is_some() {
+            return self.push_str(text);
+        }
+
+        let mut rest = text;
+        while !rest.is_empty() {
+            rest = match std::mem::replace(&mut self.html, Html::Text) {
+                Html::Text => self.html_text(rest),
+                Html::Tag {
+                    name,
+                    closing,
+                    quote,
+                } => self.html_tag(rest, name, closing, quote),
+                Html::Comment => match rest.find("-->") {
+                    Some(end) => {
+                        self.push_str(&rest[..end + 3]);
+                        &rest[end + 3..]
+                    }
+                    None => {
+                        self.html = Html::Comment;
+                        self.push_str(rest);
+                        ""
+                    }
+                },
+                Html::Verbatim { name } => {
+                    let end = find_closing_tag(rest, &name);
+                    self.push_str(&rest[..end]);
+                    if end < rest.len() {
+                        self.open_tag(&rest[end..], name, true)
+                    } else {
+                        self.html = Html::Verbatim { name };
+                        ""
+                    }
+                }
+                Html::Style => {
+                    let end = find_closing_tag(rest, "style");
+                    self.push_str(&rest[..end]);
+                    if end == rest.len() {
+                        self.html = Html::Style;
+                        ""
+                    } else {
+                        self.end_style();
+                        self.open_tag(&rest[end..], "style".to_string(), true)
+                    }
+                }
+            };
+        }
+    }
+
+    /// Plain HTML text, up to the start of a tag or a kept comment.
+    fn html_text<'t>(&mut self, text: &'t str) -> &'t str {
+        let bytes = text.as_bytes();
+        let mut start = 0;
+        let mut i = 0;
+        while i < bytes.len() {
+            let byte = bytes[i];
+            if is_space(byte) {
+                self.push_str(&text[start..i]);
+                let run = space_run(text, i);
+                self.push_space(&text[i..i + run]);
+                i += run;
+                start = i;
+            } else if bytes[i..].starts_with(b"<!--") {
+                self.push_str(&text[start..i]);
+                let end = text[i..].find("-->").map(|end| i + end + 3);
+                let conditional =
+                    text[i + 4..].starts_with("[if") || text[i + 4..].starts_with("<![");
+                match end {
+                    Some(end) if self.remove_html_comments && !conditional => {
+                        return &text[end..];
+                    }
+                    Some(end) => {
+                        self.push_str(&text[i..end]);
+                        return &text[end..];
+                    }
+                    // The comment continues past Liquid code: keep it all
+                    None => {
+                        self.html = Html::Comment;
+                        self.push_str(&text[i..]);
+                        return "";
+                    }
+                }
+            } else if byte == b'<'
+                && bytes
+                    .get(i + 1)
+                    .is_some_and(|next| next.is_ascii_alphabetic() || matches!(next, b'/' | b'!'))
+            {
+                self.push_str(&text[start..i]);
+                let closing = bytes[i + 1] == b'/';
+                let name_start = i + 1 + closing as usize;
+                let name_end = text[name_start..]
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '-' | ':' | '!')))
+                    .map_or(text.len(), |end| name_start + end);
+                self.push_str(&text[i..name_end]);
+                return self.html_tag(
+                    &text[name_end..],
+                    text[name_start..name_end].to_ascii_lowercase(),
+                    closing,
+                    None,
+                );
+            } else {
+                i += 1;
+            }
+        }
+        self.push_str(&text[start..]);
+        ""
+    }
+
+    /// Writes `</name` at the start of `text` and continues in the tag.
+    fn open_tag<'t>(&mut self, text: &'t str, name: String, closing: bool) -> &'t str {
+        let len = 1 + closing as usize + name.len();
+        self.push_str(&text[..len]);
+        self.html = Html::Tag {
+            name,
+            closing,
+            quote: None,
+        };
+        &text[len..]
+    }
+
+    /// Attributes of a tag, up to its `>`.
+    fn html_tag<'t>(
+        &mut self,
+        text: &'t str,
+        name: String,
+        closing: bool,
+        mut quote: Option<char>,
+    ) -> &'t str {
+        let mut start = 0;
+        let mut chars = text.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            match quote {
+                Some(q) if c == q => quote = None,
+                Some(_) => {}
+                None if c == '"' || c == '\'' => quote = Some(c),
+                None if c.is_ascii_whitespace() => {
+                    self.push_str(&text[start..i]);
+                    let run = space_run(text, i);
+                    // No space before the end of the tag
+                    let next = text[i + run..].chars().next();
+                    if !matches!(next, Some('>') | None) {
+                        self.out.push(' ');
+                    }
+                    while chars.peek().is_some_and(|(j, _)| *j < i + run) {
+                        chars.next();
+                    }
+                    start = i + run;
+                }
+                None if c == '>' => {
+                    self.push_str(&text[start..=i]);
+                    let self_closing = text[..i].ends_with('/');
+                    if !closing && !self_closing {
+                        if VERBATIM_ELEMENTS.contains(&name.as_str()) {
+                            self.html = Html::Verbatim { name };
+                        } else if name == "style" {
+                            self.html = Html::Style;
+                            self.style = Some(String::new());
+                        }
+                    }
+                    return &text[i + 1..];
+                }
+                None => {}
+            }
+        }
+        self.push_str(&text[start..]);
+        self.html = Html::Tag {
+            name,
+            closing,
+            quote,
+        };
+        ""
+    }
+}
+
+fn is_space(byte: u8) -> bool {
+    matches!(byte, b' ' | b'\t' | b'\n' | b'\r' | b'\x0c')
+}
+
+/// Length of the whitespace run starting at `start`.
+fn space_run(text: &str, start: usize) -> usize {
+    text.as_bytes()[start..]
+        .iter()
+        .take_while(|byte| is_space(**byte))
+        .count()
+}
+
+/// Offset of `</name` in `text` (ignoring case), or its length.
+fn find_closing_tag(text: &str, name: &str) -> usize {
+    let bytes = text.as_bytes();
+    (0..bytes.len())
+        .find(|&i| {
+            bytes[i..].starts_with(b"</")
+                && bytes
+                    .get(i + 2..i + 2 + name.len())
+                    .is_some_and(|found| found.eq_ignore_ascii_case(name.as_bytes()))
+        })
+        .unwrap_or(text.len())
+}
+
+/// Minifies a Liquid template without changing what it renders.
+///
+/// Liquid code is never modified. Templates the lexer cannot fully
+/// tokenize are returned unchanged.
+///
+/// # Arguments
+///
+/// * `template` - The Liquid source
+/// * `options` - Which comments to remove
+///
+/// # Returns
+///
+/// The minified template with its size before and after, in bytes
+///
+/// # Examples
+///
+/// ```javascript
+/// minifyLiquid("<div>\n    {% comment %}x{% endcomment %}\n    <pre>  a  </pre>\n</div>")
+/// // { content: "<div>\n<pre>  a  </pre>\n</div>", originalSize: 68, processedSize: 29, bytesSaved: 39, ... }
+/// ```
+#[napi]
+pub fn minify_liquid(template: String, options: Option<MinifyLiquidOptions>) -> MinifyResult {
+    let options = options.unwrap_or_default();
+    let remove_comments = options.remove_liquid_comments.unwrap_or(true);
+    let tokens = lex(&template);
+    if tokens.iter().any(|token| token.kind == TokenKind::Error) {
+        return MinifyResult::new(template.len(), template);
+    }
+
+    let mut minifier = Minifier {
+        out: String::with_capacity(template.len()),
+        html: Html::Text,
+        verbatim_block: None,
+        style_block: None,
+        style: None,
+        remove_html_comments: options.remove_html_comments.unwrap_or(true),
+        after_space: false,
+        trim_next: false,
+    };
+
+    let mut i = 0;
+    while i < tokens.len() {
+        let token = &tokens[i];
+        match token.kind {
+            TokenKind::Tag
+                if remove_comments
+                    && matches!(token.name(), "comment" | "doc")
+                    && minifier.verbatim_block.is_none() =>
+            {
+                // The opening tag, the body (absent when empty) and the
+                // closing tag
+                let mut close = i + 1;
+                if tokens
+                    .get(close)
+                    .is_some_and(|t| t.kind == TokenKind::Comment)
+                {
+                    close += 1;
+                }
+                if token.trim_left {
+                    let out = minifier.style.as_mut().unwrap_or(&mut minifier.out);
+                    out.truncate(out.trim_end().len());
+                    minifier.after_space = false;
+                }
+                minifier.trim_next = tokens.get(close).is_some_and(|t| t.trim_right);
+                i = close + 1;
+                continue;
+            }
+            TokenKind::Text => minifier.text(token.source),
+            _ => minifier.liquid(token),
+        }
+        i += 1;
+    }
+    minifier.end_style();
+
+    MinifyResult::new(template.len(), minifier.out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test_support::example_theme_files;
+
+    fn minify(source: &str) -> String {
+        minify_liquid(source.to_string(), None).content
+    }
+
+    #[test]
+    fn test_minify_collapses_text_and_keeps_liquid() {
+        let cases = [
+            (
+                "<div   class=\"a  b\"\n   id='x' >\n\n   {{ title  |  upcase }}   <b> hi </b>\n</div>\n",
+                "<div class=\"a  b\" id='x'>\n{{ title  |  upcase }} <b> hi </b>\n</div>\n",
+            ),
+            (
+                "a\n  {% comment %} note {% endcomment %}\n  b",
+                "a\nb",
+            ),
+            ("a  {%- comment -%} x {%- endcomment -%}  b", "ab"),
+            ("a {%- doc %} x {% enddoc %}\n b", "a\nb"),
+            (
+                "<!-- x -->a<!--[if IE]>ie<![endif]--> <!-- {{ keep }} -->",
+                "a<!--[if IE]>ie<![endif]--> <!-- {{ keep }} -->",
+            ),
+            (
+                "{% raw %}  {{ x }}   {% endraw %}   {%   if a   %}  x  {% endif %}",
+                "{% raw %}  {{ x }}   {% endraw %} {%   if a   %} x {% endif %}",
+            ),
+            ("{% # note %}   x", "{% # note %} x"),
+            ("a < b   and  c", "a < b and c"),
+        ];
+        for (source, expected) in cases {
+            assert_eq!(minify(source), expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn test_minify_keeps_whitespace_sensitive_content() {
+        let cases = [
+            "<pre>\n  line  {{ x }}\n    indented\n</pre>",
+            "<textarea name=x>\n  a   b\n</TEXTAREA>",
+            "<script>\n  var url = \"https://x.com\";  // note\n  var t = `a\n    b`;\n</script>",
+            "{% schema %}\n{\n  \"name\": \"a   b\"\n}\n{% endschema %}",
+            "{% javascript %}\n  const a = '  x  ';\n{% endjavascript %}",
+            "<input value=\"  {{ v }}   x  \">",
+        ];
+        for source in cases {
+            assert_eq!(minify(source), source);
+        }
+        assert_eq!(
+            minify("<pre>a  b</pre>  <p>  c  </p>"),
+            "<pre>a  b</pre> <p> c </p>"
+        );
+    }
+
+    #[test]
+    fn test_minify_css() {
+        assert_eq!(
+            minify("<style>\n  a  {  content: \"  x  \" ; }\n  /* c */ /*! keep */\n</style>  x"),
+            "<style>a{content:\"  x  \"}/*! keep */</style> x"
+        );
+        assert_eq!(
+            minify("{% stylesheet %}\n  .a  { color: {{ c }}; }\n{% endstylesheet %}"),
+            "{% stylesheet %}.a{color:{{ c }}}{% endstylesheet %}"
+        );
+        // Minified as a whole, across Liquid code and removed comments
+        assert_eq!(
+            minify("<style>\n  .a {\n    {% if b %} margin: 0px {% endif %}\n  }\n  .c  {%- comment -%} x {%- endcomment -%}  { color: #FFFFFF }\n</style>"),
+            "<style>.a{ {% if b %} margin:0 {% endif %}}.c{color:#fff}</style>"
+        );
+    }
+
+    #[test]
+    fn test_minify_reports_sizes_and_options() {
+        let result = minify_liquid("a   b".to_string(), None);
+        assert_eq!(result.content, "a b");
+        assert_eq!((result.original_size, result.processed_size), (5, 3));
+        assert_eq!(result.bytes_saved, 2);
+        assert!((result.compression_ratio - 40.0).abs() < 1e-9);
+
+        let options = MinifyLiquidOptions {
+            remove_liquid_comments: Some(false),
+            remove_html_comments: Some(false),
+        };
+        let source = "{% comment %}x{% endcomment %}<!-- y -->";
+        assert_eq!(
+            minify_liquid(source.to_string(), Some(options)).content,
+            source
+        );
+
+        let result = minify_liquid(
+            "<div>\n    {% comment %}x{% endcomment %}\n    <pre>  a  </pre>\n</div>".to_string(),
+            None,
+        );
+        assert_eq!(result.content, "<div>\n<pre>  a  </pre>\n</div>");
+        assert_eq!((result.original_size, result.bytes_saved), (68, 39));
+        assert_eq!(minify("a{% comment %}{% endcomment %}{{ b }}"), "a{{ b }}");
+
+        let broken = "  {{ x  ";
+        assert_eq!(minify(broken), broken);
+    }
+
+    #[test]
+    fn test_minify_example_themes_keeps_liquid() {
+        // Every Liquid token except comments survives, in order
+        fn liquid_tokens(source: &str) -> Vec<&str> {
+            let tokens = lex(source);
+            let mut kept = Vec::new();
+            let mut in_comment = false;
+            for token in &tokens {
+                match token.kind {
+                    TokenKind::Tag if matches!(token.name(), "comment" | "doc") => {
+                        in_comment = true
+                    }
+                    TokenKind::Tag if matches!(token.name(), "endcomment" | "enddoc") => {
+                        in_comment = false
+                    }
+                    TokenKind::Text | TokenKind::Comment => {}
+                    _ if !in_comment => kept.push(token.source),
+                    _ => {}
+                }
+            }
+            kept
+        }
+
+        let files = example_theme_files("liquid");
+        assert!(!files.is_empty());
+        let (mut original, mut minified) = (0, 0);
+        for path in files {
+            let source = std::fs::read_to_string(&path).unwrap();
+            let result = minify_liquid(source.clone(), None);
+            assert_eq!(
+                liquid_tokens(&result.content),
+                liquid_tokens(&source),
+                "{}",
+                path.display()
+            );
+            assert!(crate::liquid::parse_template(&result.content)
+                .errors
+                .is_empty());
+            original += result.original_size;
+            minified += result.processed_size;
+        }
+        assert!(minified < original);
+    }
+}
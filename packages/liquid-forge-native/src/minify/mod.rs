@@ -0,0 +1,58 @@
+/*
+ * Copyright 2025 Fasttify LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Minifiers for theme assets.
+//!
+//! They tokenize their input instead of running regexes over it, so
+//! strings, Liquid markup and whitespace-sensitive content come out intact.
+
+mod css;
+mod liquid;
+
+pub use css::*;
+pub use liquid::*;
+
+/// Result of a minifier, in the shape of the TypeScript `ProcessingResult`.
+/// Sizes are in UTF-8 bytes.
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct MinifyResult {
+    pub content: String,
+    pub original_size: u32,
+    pub processed_size: u32,
+    pub bytes_saved: u32,
+    /// Percentage of the original size saved, from 0 to 100.
+    pub compression_ratio: f64,
+}
+
+impl MinifyResult {
+    pub(crate) fn new(original_size: usize, content: String) -> Self {
+        let processed_size = content.len();
+        let bytes_saved = original_size.saturating_sub(processed_size);
+        let compression_ratio = if original_size > 0 {
+            bytes_saved as f64 / original_size as f64 * 100.0
+        } else {
+            0.0
+        };
+        MinifyResult {
+            content,
+            original_size: original_size as u32,
+            processed_size: processed_size as u32,
+            bytes_saved: bytes_saved as u32,
+            compression_ratio,
+        }
+    }
+}
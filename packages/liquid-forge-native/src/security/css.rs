@@ -0,0 +1,163 @@
+/*
+ * Copyright 2025 Fasttify LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Stylesheet imports from other hosts.
+
+use super::{Context, Finding};
+
+/// Skips the string starting at `pos`, returning the offset after it.
+fn skip_string(bytes: &[u8], pos: usize) -> usize {
+    let quote = bytes[pos];
+    let mut pos = pos + 1;
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b'\\' => pos += 1,
+            b'\n' => return pos,
+            byte if byte == quote => return pos + 1,
+            _ => {}
+        }
+        pos += 1;
+    }
+    bytes.len()
+}
+
+/// The URL of the `@import` whose rule starts at `pos`, with its range.
+fn import_url(source: &str, pos: usize) -> Option<(usize, usize, &str)> {
+    let rest = &source[pos..];
+    let trimmed = rest.trim_start();
+    let start = pos + rest.len() - trimmed.len();
+    let (start, trimmed) = match trimmed.get(..4) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("url(") => {
+            let inner = trimmed[4..].trim_start();
+            (start + trimmed.len() - inner.len(), inner)
+        }
+        _ => (start, trimmed),
+    };
+    match trimmed.bytes().next()? {
+        quote @ (b'"' | b'\'') => {
+            let end = skip_string(source.as_bytes(), start);
+            // An unterminated string ends at the line break or the input
+            let close = if end > start + 1 && source.as_bytes()[end - 1] == quote {
+                end - 1
+            } else {
+                end
+            };
+            Some((start, end, &source[start + 1..close]))
+        }
+        _ => {
+            let len = trimmed
+                .find(|c: char| c.is_whitespace() || c == ')' || c == ';')
+                .unwrap_or(trimmed.len());
+            Some((start, start + len, &trimmed[..len]))
+        }
+    }
+}
+
+/// Checks a stylesheet for imports from hosts outside the allowlist.
+/// `offset` is where `source` starts in the file; the comments found are
+/// added to `regions`.
+pub(crate) fn check_css(
+    source: &str,
+    offset: usize,
+    context: &Context,
+    regions: &mut Vec<(usize, usize, &'static str)>,
+) -> Vec<Finding> {
+    let bytes = source.as_bytes();
+    let mut findings = Vec::new();
+    let mut pos = 0;
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b'/' if bytes.get(pos + 1) == Some(&b'*') => {
+                let end = memchr::memmem::find(&bytes[pos + 2..], b"*/")
+                    .map_or(bytes.len(), |end| pos + 2 + end + 2);
+                regions.push((offset + pos, offset + end, "comment"));
+                pos = end;
+            }
+            b'"' | b'\'' => pos = skip_string(bytes, pos),
+            b'@' if bytes[pos + 1..]
+                .get(..6)
+                .is_some_and(|name| name.eq_ignore_ascii_case(b"import")) =>
+            {
+                pos += 7;
+                if let Some((start, end, url)) = import_url(source, pos) {
+                    if let Some(host) = context.blocked_host(url) {
+                        findings.push(Finding {
+                            rule: "external-stylesheet",
+                            severity: "warning",
+                            message: format!(
+                                "Stylesheet imported from '{}', which isn't an allowed domain",
+                                host
+                            ),
+                            context: "code",
+                            start: offset + start,
+                            end: offset + end,
+                        });
+                    }
+                    pos = end;
+                }
+            }
+            _ => pos += 1,
+        }
+    }
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_css() {
+        let source = concat!(
+            "/* @import url(https://evil.example/a.css); */\n",
+            "@import url( \"https://evil.example/b.css\" );\n",
+            "@IMPORT 'https://fonts.evil.example/c.css';\n",
+            "@import url(https://cdn.fasttify.com/d.css);\n",
+            "@import 'local.css';\n",
+            "a::before { content: '@import url(https://evil.example/e.css)' }"
+        );
+        let mut regions = Vec::new();
+        let findings = check_css(source, 0, &Context::default(), &mut regions);
+        let urls: Vec<&str> = findings
+            .iter()
+            .map(|finding| &source[finding.start..finding.end])
+            .collect();
+        assert_eq!(
+            urls,
+            [
+                "\"https://evil.example/b.css\"",
+                "'https://fonts.evil.example/c.css'"
+            ]
+        );
+        assert_eq!(
+            findings[0].message,
+            "Stylesheet imported from 'evil.example', which isn't an allowed domain"
+        );
+        assert_eq!(regions, [(0, 46, "comment")]);
+
+        // Unterminated strings, at the end of the input or of the line
+        let cases = [
+            ("@import '", 0),
+            ("@import \"\n", 0),
+            ("@import url('", 0),
+            ("@import \"https://evil.example", 1),
+        ];
+        for (source, count) in cases {
+            let findings = check_css(source, 0, &Context::default(), &mut Vec::new());
+            assert_eq!(findings.len(), count, "{}", source);
+        }
+    }
+}
@@ -0,0 +1,712 @@
+/*
+ * Copyright 2025 Fasttify LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! A JavaScript lexer and the checks that run on its tokens.
+//!
+//! The lexer only needs to tell code, strings and comments apart, so it is
+//! forgiving: anything it doesn't understand becomes punctuation and
+//! unterminated strings end at the line break.
+
+use super::secrets::{secret_finding, SecretCandidate};
+use super::{Context, Finding};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum JsKind {
+    Ident,
+    Number,
+    Punct,
+    /// A quoted string.
+    String,
+    /// A chunk of a template literal between its delimiters and `${ }`.
+    Template,
+    Regex,
+    Comment,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct JsToken {
+    pub kind: JsKind,
+    pub start: usize,
+    pub end: usize,
+}
+
+const MULTI_CHAR_PUNCT: &[&str] = &[
+    ">>>=", "===", "!==", "**=", "<<=", ">>=", ">>>", "...", "&&=", "||=", "??=", "=>", "==", "!=",
+    "<=", ">=", "&&", "||", "??", "?.", "++", "--", "+=", "-=", "*=", "/=", "%=", "&=", "|=", "^=",
+    "**", "<<", ">>",
+];
+
+/// Keywords after which `/` starts a regular expression.
+const REGEX_KEYWORDS: &[&str] = &[
+    "return",
+    "typeof",
+    "instanceof",
+    "in",
+    "of",
+    "new",
+    "delete",
+    "void",
+    "throw",
+    "case",
+    "do",
+    "else",
+    "yield",
+    "await",
+];
+
+fn is_ident_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_' || c == '$'
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$'
+}
+
+struct Lexer<'a> {
+    source: &'a str,
+    bytes: &'a [u8],
+    pos: usize,
+    tokens: Vec<JsToken>,
+    /// Open braces of each `${ }` being lexed, innermost last.
+    templates: Vec<usize>,
+}
+
+impl<'a> Lexer<'a> {
+    fn push(&mut self, kind: JsKind, start: usize) {
+        self.tokens.push(JsToken {
+            kind,
+            start,
+            end: self.pos,
+        });
+    }
+
+    fn char_at(&self, pos: usize) -> Option<char> {
+        self.source[pos..].chars().next()
+    }
+
+    /// Whether a `/` here starts a regular expression rather than a division.
+    fn regex_allowed(&self) -> bool {
+        let last = self.tokens.iter().rev().find(|t| t.kind != JsKind::Comment);
+        match last {
+            None => true,
+            Some(token) => {
+                let text = &self.source[token.start..token.end];
+                match token.kind {
+                    JsKind::Punct => !matches!(text, ")" | "]" | "}"),
+                    JsKind::Ident => REGEX_KEYWORDS.contains(&text),
+                    _ => false,
+                }
+            }
+        }
+    }
+
+    fn string(&mut self, quote: u8) {
+        let start = self.pos;
+        self.pos += 1;
+        while self.pos < self.bytes.len() {
+            match self.bytes[self.pos] {
+                b'\\' => self.pos += 2,
+                b'\n' => break,
+                byte if byte == quote => {
+                    self.pos += 1;
+                    break;
+                }
+                _ => self.pos += 1,
+            }
+        }
+        self.pos = self.pos.min(self.bytes.len());
+        self.push(JsKind::String, start);
+    }
+
+    /// Lexes a template chunk starting at `self.pos`, just after a backtick
+    /// or the `}` closing a substitution.
+    fn template(&mut self, start: usize) {
+        while self.pos < self.bytes.len() {
+            match self.bytes[self.pos] {
+                b'\\' => self.pos += 2,
+                b'`' => {
+                    self.pos += 1;
+                    break;
+                }
+                b'$' if self.bytes.get(self.pos + 1) == Some(&b'{') => {
+                    self.pos += 2;
+                    self.templates.push(0);
+                    break;
+                }
+                _ => self.pos += 1,
+            }
+        }
+        self.pos = self.pos.min(self.bytes.len());
+        self.push(JsKind::Template, start);
+    }
+
+    fn regex(&mut self) {
+        let start = self.pos;
+        self.pos += 1;
+        let mut class = false;
+        while self.pos < self.bytes.len() {
+            match self.bytes[self.pos] {
+                b'\\' => self.pos += 1,
+                b'[' => class = true,
+                b']' => class = false,
+                b'/' if !class => break,
+                b'\n' => {
+                    // Not a regular expression after all
+                    self.pos = start + 1;
+                    self.push(JsKind::Punct, start);
+                    return;
+                }
+                _ => {}
+            }
+            self.pos += 1;
+        }
+        self.pos = (self.pos + 1).min(self.bytes.len());
+        while self.char_at(self.pos).is_some_and(is_ident_char) {
+            self.pos += 1;
+        }
+        self.push(JsKind::Regex, start);
+    }
+
+    fn run(mut self) -> Vec<JsToken> {
+        while let Some(c) = self
+            .source
+            .get(self.pos..)
+            .and_then(|rest| rest.chars().next())
+        {
+            let start = self.pos;
+            let rest = &self.source[start..];
+            if c.is_whitespace() {
+                self.pos += c.len_utf8();
+            } else if rest.starts_with("//") {
+                self.pos = rest.find('\n').map_or(self.bytes.len(), |end| start + end);
+                self.push(JsKind::Comment, start);
+            } else if let Some(comment) = rest.strip_prefix("/*") {
+                self.pos = comment
+                    .find("*/")
+                    .map_or(self.bytes.len(), |end| start + end + 4);
+                self.push(JsKind::Comment, start);
+            } else if c == '"' || c == '\'' {
+                self.string(c as u8);
+            } else if c == '`' {
+                self.pos += 1;
+                self.template(start);
+            } else if c == '/' && self.regex_allowed() {
+                self.regex();
+            } else if c.is_ascii_digit()
+                || (c == '.' && rest[1..].starts_with(|c: char| c.is_ascii_digit()))
+            {
+                self.pos += rest
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '.' || c == '_'))
+                    .unwrap_or(rest.len());
+                self.push(JsKind::Number, start);
+            } else if is_ident_start(c) || c == '\\' {
+                self.pos += rest
+                    .find(|c: char| !(is_ident_char(c) || c == '\\'))
+                    .unwrap_or(rest.len());
+                self.push(JsKind::Ident, start);
+            } else if c == '}' && self.templates.last() == Some(&0) {
+                self.templates.pop();
+                self.pos += 1;
+                self.template(start);
+            } else {
+                match c {
+                    '{' => {
+                        if let Some(depth) = self.templates.last_mut() {
+                            *depth += 1;
+                        }
+                    }
+                    '}' => {
+                        if let Some(depth) = self.templates.last_mut() {
+                            *depth -= 1;
+                        }
+                    }
+                    _ => {}
+                }
+                let len = MULTI_CHAR_PUNCT
+                    .iter()
+                    .find(|punct| rest.starts_with(*punct))
+                    .map_or(c.len_utf8(), |punct| punct.len());
+                self.pos += len;
+                self.push(JsKind::Punct, start);
+            }
+        }
+        self.tokens
+    }
+}
+
+/// Splits JavaScript into tokens.
+pub(crate) fn lex_js(source: &str) -> Vec<JsToken> {
+    Lexer {
+        source,
+        bytes: source.as_bytes(),
+        pos: 0,
+        tokens: Vec::new(),
+        templates: Vec::new(),
+    }
+    .run()
+}
+
+/// The value of a string literal, with escapes decoded, so `'\x65val'`
+/// reads as `eval`.
+pub(crate) fn string_value(literal: &str) -> String {
+    let inner = literal
+        .get(1..literal.len().saturating_sub(1).max(1))
+        .unwrap_or_default();
+    let mut value = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+        let hex = |chars: &mut std::str::Chars, len: usize| {
+            let digits: String = chars.by_ref().take(len).collect();
+            u32::from_str_radix(&digits, 16)
+                .ok()
+                .and_then(char::from_u32)
+        };
+        let decoded = match chars.next() {
+            Some('n') => Some('\n'),
+            Some('t') => Some('\t'),
+            Some('r') => Some('\r'),
+            Some('x') => hex(&mut chars, 2),
+            Some('u') => {
+                let rest = chars.as_str();
+                if let Some(braced) = rest.strip_prefix('{') {
+                    let end = braced.find('}').unwrap_or(braced.len());
+                    let decoded = u32::from_str_radix(&braced[..end], 16)
+                        .ok()
+                        .and_then(char::from_u32);
+                    chars = braced[(end + 1).min(braced.len())..].chars();
+                    decoded
+                } else {
+                    hex(&mut chars, 4)
+                }
+            }
+            other => other,
+        };
+        value.extend(decoded);
+    }
+    value
+}
+
+/// Globals through which the dangerous functions are reachable.
+const GLOBAL_OBJECTS: &[&str] = &[
+    "window",
+    "globalThis",
+    "self",
+    "top",
+    "parent",
+    "frames",
+    "this",
+];
+
+/// A name read in code: an identifier or a computed member with a string key.
+struct Name {
+    value: String,
+    start: usize,
+    end: usize,
+    /// Whether the name is a property (`a.name`, `a['name']`).
+    member: bool,
+    /// Whether it's a computed member (`a['na' + 'me']`).
+    computed: bool,
+    /// The identifier the property is read from, if any.
+    object: Option<usize>,
+    /// Index of the first token after the name.
+    next: usize,
+}
+
+struct Checker<'a> {
+    source: &'a str,
+    /// Tokens without comments.
+    tokens: Vec<JsToken>,
+    offset: usize,
+    findings: Vec<Finding>,
+}
+
+impl<'a> Checker<'a> {
+    fn text(&self, index: usize) -> &'a str {
+        self.tokens
+            .get(index)
+            .map_or("", |token| &self.source[token.start..token.end])
+    }
+
+    fn is_punct(&self, index: usize, punct: &str) -> bool {
+        self.tokens
+            .get(index)
+            .is_some_and(|token| token.kind == JsKind::Punct)
+            && self.text(index) == punct
+    }
+
+    fn is_string(&self, index: usize) -> bool {
+        self.tokens
+            .get(index)
+            .is_some_and(|token| matches!(token.kind, JsKind::String | JsKind::Template))
+    }
+
+    /// The identifier before `index`, if the token there reads a member.
+    fn member_of(&self, dot: usize) -> Option<usize> {
+        (dot > 0 && self.tokens[dot - 1].kind == JsKind::Ident).then(|| dot - 1)
+    }
+
+    /// Whether the token before `index` ends an expression, making a `[`
+    /// there a member access.
+    fn ends_expression(&self, index: usize) -> bool {
+        let Some(token) = index.checked_sub(1).map(|prev| self.tokens[prev]) else {
+            return false;
+        };
+        let text = self.text(index - 1);
+        match token.kind {
+            JsKind::Ident => !REGEX_KEYWORDS.contains(&text),
+            JsKind::Punct => matches!(text, ")" | "]"),
+            JsKind::String | JsKind::Template | JsKind::Regex => true,
+            _ => false,
+        }
+    }
+
+    /// The name at `index`, if there is one.
+    fn name(&self, index: usize) -> Option<Name> {
+        let token = self.tokens[index];
+        match token.kind {
+            JsKind::Ident => {
+                let dot =
+                    index > 0 && (self.is_punct(index - 1, ".") || self.is_punct(index - 1, "?."));
+                Some(Name {
+                    value: self.text(index).to_string(),
+                    start: token.start,
+                    end: token.end,
+                    member: dot,
+                    computed: false,
+                    object: if dot { self.member_of(index - 1) } else { None },
+                    next: index + 1,
+                })
+            }
+            JsKind::Punct if self.text(index) == "[" && self.ends_expression(index) => {
+                // `x['a' + 'b']`, not the array `['a' + 'b']`
+                let mut value = String::new();
+                let mut pos = index + 1;
+                loop {
+                    if !self.is_string(pos) {
+                        return None;
+                    }
+                    value.push_str(&string_value(self.text(pos)));
+                    pos += 1;
+                    if self.is_punct(pos, "]") {
+                        break;
+                    }
+                    if !self.is_punct(pos, "+") {
+                        return None;
+                    }
+                    pos += 1;
+                }
+                let object = (self.tokens[index - 1].kind == JsKind::Ident).then(|| index - 1);
+                Some(Name {
+                    value,
+                    start: token.start,
+                    end: self.tokens[pos].end,
+                    member: true,
+                    computed: true,
+                    object,
+                    next: pos + 1,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    fn on_global(&self, name: &Name) -> bool {
+        !name.member
+            || name
+                .object
+                .is_some_and(|object| GLOBAL_OBJECTS.contains(&self.text(object)))
+    }
+
+    fn report(
+        &mut self,
+        rule: &'static str,
+        severity: &'static str,
+        message: String,
+        start: usize,
+        end: usize,
+    ) {
+        self.findings.push(Finding {
+            rule,
+            severity,
+            message,
+            context: "code",
+            start: self.offset + start,
+            end: self.offset + end,
+        });
+    }
+
+    fn check_name(&mut self, name: Name) {
+        let called = self.is_punct(name.next, "(");
+        match name.value.as_str() {
+            "eval" if self.on_global(&name) || name.computed => {
+                let message = if name.computed || name.member {
+                    "Indirect eval() runs arbitrary code"
+                } else {
+                    "eval() runs arbitrary code"
+                };
+                self.report("eval", "error", message.to_string(), name.start, name.end);
+            }
+            "Function" if called && self.on_global(&name) => {
+                let message = "The Function constructor runs arbitrary code".to_string();
+                self.report(
+                    "function-constructor",
+                    "error",
+                    message,
+                    name.start,
+                    name.end,
+                );
+            }
+            "constructor" if name.member => {
+                // `x.constructor.constructor(...)` is the Function constructor
+                if let Some(next) = self.name_at(name.next).filter(|next| next.member) {
+                    if next.value == "constructor" {
+                        let message =
+                            "The Function constructor reached through 'constructor' runs arbitrary code"
+                                .to_string();
+                        self.report(
+                            "function-constructor",
+                            "error",
+                            message,
+                            name.start,
+                            next.end,
+                        );
+                    }
+                }
+            }
+            "setTimeout" | "setInterval"
+                if called && self.on_global(&name) && self.is_string(name.next + 1) =>
+            {
+                let message = format!("{}() with a string runs it as code", name.value);
+                self.report("string-timer", "warning", message, name.start, name.end);
+            }
+            "write" | "writeln"
+                if name
+                    .object
+                    .is_some_and(|object| self.text(object) == "document") =>
+            {
+                let message = format!("document.{}() can inject markup", name.value);
+                self.report("document-write", "warning", message, name.start, name.end);
+            }
+            "innerHTML" | "outerHTML"
+                if name.member
+                    && (self.is_punct(name.next, "=") || self.is_punct(name.next, "+=")) =>
+            {
+                let message = format!("Assigning {} can inject markup", name.value);
+                self.report("html-injection", "warning", message, name.start, name.end);
+            }
+            "insertAdjacentHTML" if name.member && called => {
+                let message = "insertAdjacentHTML() can inject markup".to_string();
+                self.report("html-injection", "warning", message, name.start, name.end);
+            }
+            _ => {}
+        }
+    }
+
+    fn name_at(&self, index: usize) -> Option<Name> {
+        if index < self.tokens.len() {
+            let dot = self.is_punct(index, ".") || self.is_punct(index, "?.");
+            if dot
+                && self
+                    .tokens
+                    .get(index + 1)
+                    .is_some_and(|t| t.kind == JsKind::Ident)
+            {
+                return self.name(index + 1);
+            }
+            return self.name(index);
+        }
+        None
+    }
+
+    /// `key = 'value'` and `key: 'value'` pairs.
+    fn check_assignment(&mut self, index: usize, secrets: &mut Vec<SecretCandidate>) {
+        let key = self.tokens[index];
+        if !(self.is_punct(index + 1, "=") || self.is_punct(index + 1, ":"))
+            || !self.is_string(index + 2)
+        {
+            return;
+        }
+        let name = match key.kind {
+            JsKind::Ident => self.text(index).to_string(),
+            JsKind::String => string_value(self.text(index)),
+            _ => return,
+        };
+        let value = self.tokens[index + 2];
+        let raw = self.text(index + 2);
+        // Template chunks with substitutions aren't constants
+        if value.kind == JsKind::Template && !raw.ends_with('`') {
+            return;
+        }
+        secrets.push(SecretCandidate {
+            name,
+            value: string_value(raw),
+            start: self.offset + value.start,
+            end: self.offset + value.end,
+        });
+    }
+}
+
+/// Checks JavaScript for dangerous functions and hard-coded secrets.
+/// `offset` is where `source` starts in the file.
+pub(crate) fn check_js(source: &str, offset: usize, context: &Context) -> Vec<Finding> {
+    let tokens = lex_js(source);
+    let code: Vec<JsToken> = tokens
+        .iter()
+        .copied()
+        .filter(|token| token.kind != JsKind::Comment)
+        .collect();
+    let mut checker = Checker {
+        source,
+        tokens: code,
+        offset,
+        findings: Vec::new(),
+    };
+    let mut secrets = Vec::new();
+    for index in 0..checker.tokens.len() {
+        if let Some(name) = checker.name(index) {
+            checker.check_name(name);
+        }
+        checker.check_assignment(index, &mut secrets);
+    }
+    let mut findings = checker.findings;
+    findings.extend(
+        secrets
+            .iter()
+            .filter_map(|candidate| secret_finding(candidate, "string", context)),
+    );
+    findings
+}
+
+/// Byte ranges of the comments and strings of JavaScript, with their kind.
+pub(crate) fn js_regions(source: &str, offset: usize) -> Vec<(usize, usize, &'static str)> {
+    lex_js(source)
+        .into_iter()
+        .filter_map(|token| {
+            let context = match token.kind {
+                JsKind::Comment => "comment",
+                JsKind::String | JsKind::Template => "string",
+                _ => return None,
+            };
+            Some((offset + token.start, offset + token.end, context))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(source: &str) -> Vec<(JsKind, &str)> {
+        lex_js(source)
+            .into_iter()
+            .map(|token| (token.kind, &source[token.start..token.end]))
+            .collect()
+    }
+
+    #[test]
+    fn test_lex_js() {
+        use JsKind::*;
+        assert_eq!(
+            kinds("a = b / 2 /* eval( */ // x\nc = /[/]eval(/g.test('\\'x') === `t${ {a: `u`} }v`"),
+            [
+                (Ident, "a"),
+                (Punct, "="),
+                (Ident, "b"),
+                (Punct, "/"),
+                (Number, "2"),
+                (Comment, "/* eval( */"),
+                (Comment, "// x"),
+                (Ident, "c"),
+                (Punct, "="),
+                (Regex, "/[/]eval(/g"),
+                (Punct, "."),
+                (Ident, "test"),
+                (Punct, "("),
+                (String, "'\\'x'"),
+                (Punct, ")"),
+                (Punct, "==="),
+                (Template, "`t${"),
+                (Punct, "{"),
+                (Ident, "a"),
+                (Punct, ":"),
+                (Template, "`u`"),
+                (Punct, "}"),
+                (Template, "}v`"),
+            ]
+        );
+        assert_eq!(kinds("return /a/i"), [(Ident, "return"), (Regex, "/a/i")]);
+        assert_eq!(kinds("'open\nx"), [(String, "'open"), (Ident, "x")]);
+    }
+
+    #[test]
+    fn test_string_value() {
+        assert_eq!(string_value(r"'\x65v\u0061\u{6c}'"), "eval");
+        assert_eq!(string_value(r#""a\"b\n""#), "a\"b\n");
+        assert_eq!(string_value("'"), "");
+    }
+
+    #[test]
+    fn test_check_js() {
+        let context = Context::default();
+        let cases: [(&str, &[&str]); 12] = [
+            ("eval(code)", &["eval"]),
+            ("window['ev' + 'al'](code); (0, eval)(x)", &["eval", "eval"]),
+            ("globalThis[\"\\x65val\"](x)", &["eval"]),
+            ("obj.eval(x); // eval(x)\nvar s = 'eval(x)'", &[]),
+            ("new Function('a', body)", &["function-constructor"]),
+            (
+                "[].constructor['constructor'](code)()",
+                &["function-constructor"],
+            ),
+            (
+                "setTimeout('go()', 10); setTimeout(go, 10); setInterval(`tick()`, 5)",
+                &["string-timer", "string-timer"],
+            ),
+            ("document.write('<p>'); doc.write(x)", &["document-write"]),
+            (
+                "el.innerHTML = html; if (el.innerHTML === '') {}",
+                &["html-injection"],
+            ),
+            (
+                "el.insertAdjacentHTML('beforeend', html)",
+                &["html-injection"],
+            ),
+            ("const apiKey = 'Zk3vQ9xLp2Rt8Wm4';", &["hardcoded-secret"]),
+            (
+                "const config = { password: 'Contraseña incorrecta', token: 'csrf-token' }",
+                &[],
+            ),
+        ];
+        for (source, expected) in cases {
+            let rules: Vec<&str> = check_js(source, 0, &context)
+                .into_iter()
+                .map(|finding| finding.rule)
+                .collect();
+            assert_eq!(rules, expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn test_check_js_offsets() {
+        let findings = check_js("x;\neval(y)", 10, &Context::default());
+        assert_eq!((findings[0].start, findings[0].end), (13, 17));
+        assert_eq!(findings[0].message, "eval() runs arbitrary code");
+    }
+}
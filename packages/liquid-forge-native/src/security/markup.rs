@@ -0,0 +1,359 @@
+/*
+ * Copyright 2025 Fasttify LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Liquid templates: external resources in the markup, and the scripts and
+//! stylesheets embedded in it.
+//!
+//! Embedded code is scanned with Liquid blanked out, so
+//! `var id = {{ product.id }};` lexes as JavaScript with the same offsets.
+
+use memchr::memmem;
+
+use super::css::check_css;
+use super::js::{check_js, js_regions};
+use super::secrets::{secret_finding, SecretCandidate};
+use super::{Context, Finding};
+use crate::liquid::ast::{walk, Expr, Node};
+use crate::liquid::html::{blank_liquid, start_tags, Element};
+use crate::liquid::{lex, parse_template, TokenKind};
+
+/// Script types that hold JavaScript.
+const JS_TYPES: &[&str] = &["", "text/javascript", "application/javascript", "module"];
+
+/// Filters that turn a URL into a `<script>` or `<link>` tag.
+const TAG_FILTERS: &[(&str, &str)] = &[
+    ("script_tag", "external-script"),
+    ("stylesheet_tag", "external-stylesheet"),
+];
+
+/// The end of a raw text element's content: the start of its end tag.
+fn content_end(source: &str, element: &Element) -> usize {
+    let close = format!("</{}", element.name);
+    let rest = source[element.end..].to_ascii_lowercase();
+    memmem::find(rest.as_bytes(), close.as_bytes()).map_or(source.len(), |end| element.end + end)
+}
+
+/// A literal attribute value: Liquid makes it something the scan can't see.
+fn literal<'a>(element: &'a Element, name: &str) -> Option<&'a str> {
+    element
+        .attribute(name)
+        .and_then(|attr| attr.value.as_deref())
+        .filter(|value| !value.contains("{{") && !value.contains("{%"))
+}
+
+fn external(rule: &'static str, host: &str, start: usize, end: usize) -> Finding {
+    let what = if rule == "external-script" {
+        "Script loaded"
+    } else {
+        "Stylesheet loaded"
+    };
+    Finding {
+        rule,
+        severity: "warning",
+        message: format!("{} from '{}', which isn't an allowed domain", what, host),
+        context: "markup",
+        start,
+        end,
+    }
+}
+
+struct Scan<'a> {
+    source: &'a str,
+    blanked: String,
+    context: &'a Context,
+    findings: Vec<Finding>,
+    regions: Vec<(usize, usize, &'static str)>,
+}
+
+impl Scan<'_> {
+    fn javascript(&mut self, start: usize, end: usize) {
+        let code = &self.blanked[start..end];
+        self.findings.extend(check_js(code, start, self.context));
+        self.regions.extend(js_regions(code, start));
+    }
+
+    fn stylesheet(&mut self, start: usize, end: usize) {
+        let findings = check_css(
+            &self.blanked[start..end],
+            start,
+            self.context,
+            &mut self.regions,
+        );
+        self.findings.extend(findings);
+    }
+
+    fn element(&mut self, element: &Element) {
+        self.secret_attributes(element);
+        match element.name.as_str() {
+            "script" => {
+                if let Some(src) = element.attribute("src") {
+                    if let Some(host) =
+                        literal(element, "src").and_then(|url| self.context.blocked_host(url))
+                    {
+                        let finding = external("external-script", &host, src.start, src.end);
+                        self.findings.push(finding);
+                    }
+                    return;
+                }
+                let kind = literal(element, "type")
+                    .unwrap_or_default()
+                    .to_ascii_lowercase();
+                if JS_TYPES.contains(&kind.as_str()) || kind.ends_with("json") {
+                    self.javascript(element.end, content_end(self.source, element));
+                }
+            }
+            "style" => self.stylesheet(element.end, content_end(self.source, element)),
+            "link" => {
+                let (Some(href), Some(url)) = (element.attribute("href"), literal(element, "href"))
+                else {
+                    return;
+                };
+                let stylesheet = literal(element, "rel")
+                    .is_some_and(|rel| rel.to_ascii_lowercase().contains("stylesheet"))
+                    || url
+                        .split(['?', '#'])
+                        .next()
+                        .unwrap_or_default()
+                        .ends_with(".css");
+                if let Some(host) = self.context.blocked_host(url).filter(|_| stylesheet) {
+                    self.findings.push(external(
+                        "external-stylesheet",
+                        &host,
+                        href.start,
+                        href.end,
+                    ));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// `data-api-key="..."`, and `<input name="api_key" value="...">`.
+    fn secret_attributes(&mut self, element: &Element) {
+        let field = ["name", "id"].iter().find_map(|name| {
+            literal(element, name).filter(|field| super::secrets::is_sensitive_name(field))
+        });
+        for attr in &element.attributes {
+            let name = match field {
+                Some(field) if attr.name == "value" => field,
+                _ if attr.name.starts_with("data-") || attr.name.starts_with("aria-") => &attr.name,
+                _ => continue,
+            };
+            let Some(value) = literal(element, &attr.name) else {
+                continue;
+            };
+            let candidate = SecretCandidate {
+                name: name.to_string(),
+                value: value.to_string(),
+                start: attr.start,
+                end: attr.end,
+            };
+            self.findings
+                .extend(secret_finding(&candidate, "markup", self.context));
+        }
+    }
+
+    fn liquid(&mut self) {
+        let mut block: Option<(&str, usize)> = None;
+        for token in lex(self.source) {
+            match token.kind {
+                TokenKind::Comment => self.regions.push((token.start, token.end(), "comment")),
+                TokenKind::Tag if token.name() == "#" => {
+                    self.regions.push((token.start, token.end(), "comment"))
+                }
+                TokenKind::Tag => match (block, token.name()) {
+                    (None, name @ ("javascript" | "stylesheet" | "style")) => {
+                        block = Some((name, token.end()))
+                    }
+                    (Some((name, start)), end) if end.strip_prefix("end") == Some(name) => {
+                        block = None;
+                        if name == "javascript" {
+                            self.javascript(start, token.start);
+                        } else {
+                            self.stylesheet(start, token.start);
+                        }
+                    }
+                    _ => {}
+                },
+                _ => {}
+            }
+        }
+
+        let parsed = parse_template(self.source);
+        let mut candidates = Vec::new();
+        let mut resources = Vec::new();
+        walk(&parsed.nodes, &mut |node| match node {
+            Node::Assign { name, value, span } => {
+                if let (Expr::String { value: text }, []) = (&value.expr, value.filters.as_slice())
+                {
+                    candidates.push(SecretCandidate {
+                        name: name.clone(),
+                        value: text.clone(),
+                        start: span.start as usize,
+                        end: span.end as usize,
+                    });
+                }
+            }
+            Node::Output { value, span } | Node::Echo { value, span } => {
+                if let Expr::String { value: url } = &value.expr {
+                    let rule = value.filters.iter().find_map(|filter| {
+                        TAG_FILTERS
+                            .iter()
+                            .find(|(name, _)| *name == filter.name)
+                            .map(|(_, rule)| *rule)
+                    });
+                    if let Some(rule) = rule {
+                        resources.push((rule, url.clone(), span.start as usize, span.end as usize));
+                    }
+                }
+            }
+            _ => {}
+        });
+        for candidate in &candidates {
+            self.findings
+                .extend(secret_finding(candidate, "markup", self.context));
+        }
+        for (rule, url, start, end) in resources {
+            if let Some(host) = self.context.blocked_host(&url) {
+                self.findings.push(external(rule, &host, start, end));
+            }
+        }
+    }
+}
+
+/// Checks a Liquid template. Returns the findings and the comment and
+/// string regions of the file.
+pub(crate) fn check_markup(
+    source: &str,
+    context: &Context,
+) -> (Vec<Finding>, Vec<(usize, usize, &'static str)>) {
+    let mut scan = Scan {
+        source,
+        blanked: String::from_utf8_lossy(&blank_liquid(source, b' ', &[])).into_owned(),
+        context,
+        findings: Vec::new(),
+        regions: Vec::new(),
+    };
+    for element in start_tags(source) {
+        scan.element(&element);
+    }
+    let bytes = source.as_bytes();
+    for start in memmem::find_iter(bytes, b"<!--") {
+        let end = memmem::find(&bytes[start..], b"-->").map_or(bytes.len(), |end| start + end + 3);
+        scan.regions.push((start, end, "comment"));
+    }
+    scan.liquid();
+    (scan.findings, scan.regions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(source: &str) -> Vec<(&'static str, String)> {
+        let (mut findings, _) = check_markup(source, &Context::default());
+        findings.sort_by_key(|finding| finding.start);
+        findings
+            .into_iter()
+            .map(|finding| (finding.rule, source[finding.start..finding.end].to_string()))
+            .collect()
+    }
+
+    fn pairs(expected: &[(&'static str, &str)]) -> Vec<(&'static str, String)> {
+        expected
+            .iter()
+            .map(|(rule, text)| (*rule, text.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_external_resources() {
+        let source = concat!(
+            "<script src=\"https://evil.example/a.js\"></script>",
+            "<script src='//cdn.fasttify.com/b.js' defer></script>",
+            "<script src=\"{{ 'c.js' | asset_url }}\"></script>",
+            "<link rel=\"stylesheet\" href=\"https://fonts.evil.example/d\">",
+            "<link rel=\"preconnect\" href=\"https://evil.example\">",
+            "<link href=\"http://evil.example/e.css?v=1\">",
+            "{{ 'https://evil.example/f.js' | script_tag }}",
+            "{{ 'g.css' | asset_url | stylesheet_tag }}"
+        );
+        assert_eq!(
+            rules(source),
+            pairs(&[
+                ("external-script", "src=\"https://evil.example/a.js\""),
+                (
+                    "external-stylesheet",
+                    "href=\"https://fonts.evil.example/d\""
+                ),
+                (
+                    "external-stylesheet",
+                    "href=\"http://evil.example/e.css?v=1\""
+                ),
+                (
+                    "external-script",
+                    "{{ 'https://evil.example/f.js' | script_tag }}"
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_embedded_code() {
+        let source = concat!(
+            "<script>var id = {{ product.id }}; eval(code); // eval(x)\n</script>",
+            "<script type=\"text/template\">eval(x)</script>",
+            "<p>eval(x)</p><!-- <script>eval(x)</script> -->",
+            "{% javascript %}el.innerHTML = '{{ x }}';{% endjavascript %}",
+            "<style>@import 'https://evil.example/a.css';</style>",
+            "{% stylesheet %}@import url(https://evil.example/b.css);{% endstylesheet %}",
+            "{% comment %}<script>eval(x)</script>{% endcomment %}"
+        );
+        assert_eq!(
+            rules(source),
+            pairs(&[
+                ("eval", "eval"),
+                ("html-injection", "innerHTML"),
+                ("external-stylesheet", "'https://evil.example/a.css'"),
+                ("external-stylesheet", "https://evil.example/b.css"),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_markup_secrets() {
+        let source = concat!(
+            "{% assign stripe_secret = 'Zk3vQ9xLp2Rt8Wm4' %}",
+            "{% assign api_token = settings.token %}",
+            "<div data-api-key=\"Zk3vQ9xLp2Rt8Wm4\" data-title=\"Zk3vQ9xLp2Rt8Wm4\"></div>",
+            "<input type=\"hidden\" name=\"access_token\" value=\"Zk3vQ9xLp2Rt8Wm4\">",
+            "<input name=\"password\" value=\"{{ form.password }}\">",
+            "<script type=\"application/ld+json\">{\"apiKey\": \"Zk3vQ9xLp2Rt8Wm4\"}</script>"
+        );
+        assert_eq!(
+            rules(source),
+            pairs(&[
+                (
+                    "hardcoded-secret",
+                    "{% assign stripe_secret = 'Zk3vQ9xLp2Rt8Wm4' %}"
+                ),
+                ("hardcoded-secret", "data-api-key=\"Zk3vQ9xLp2Rt8Wm4\""),
+                ("hardcoded-secret", "value=\"Zk3vQ9xLp2Rt8Wm4\""),
+                ("hardcoded-secret", "\"Zk3vQ9xLp2Rt8Wm4\""),
+            ])
+        );
+    }
+}
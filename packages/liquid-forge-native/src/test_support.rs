@@ -0,0 +1,43 @@
+/*
+ * Copyright 2025 Fasttify LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Helpers shared by the tests.
+
+use std::path::{Path, PathBuf};
+
+/// The `example-themes` directory of the repository.
+pub fn example_themes() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../example-themes")
+}
+
+/// Every file under `example-themes` with the extension `ext`, or every
+/// file if `ext` is empty, in path order.
+pub fn example_theme_files(ext: &str) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut dirs = vec![example_themes()];
+    while let Some(dir) = dirs.pop() {
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else if ext.is_empty() || path.extension().is_some_and(|found| found == ext) {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    files
+}